@@ -1,25 +1,103 @@
-use crate::api::RequestBody;
-use crate::error::Result;
+use crate::api::RateLimiter;
+use crate::config::Config;
+use crate::error::{Cmd2AiError, Result};
+use colored::Colorize;
+use serde::Serialize;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use std::time::Duration;
 
-pub async fn make_api_request(
-    api_key: &str,
-    api_endpoint: &str,
-    request_body: &RequestBody,
-) -> Result<reqwest::Response> {
+/// Build the HTTP client shared by every request of a run, so tool follow-ups reuse the
+/// connection (and its TLS session) instead of reconnecting. Proxies come from the
+/// standard `HTTP(S)_PROXY` env vars; keep-alive follows `api.pool_*`.
+pub fn build_client(config: &Config) -> Result<reqwest::Client> {
     let mut headers = HeaderMap::new();
     headers.insert(
         AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key))
+        HeaderValue::from_str(&format!("Bearer {}", config.api_key))
             .map_err(|e| crate::error::Cmd2AiError::Other(format!("Invalid authorization header: {}", e)))?,
     );
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
+    // Accept gzip/brotli responses; decompression is transparent, and an SSE body is still
+    // decoded chunk by chunk as it arrives
     let client = reqwest::Client::builder()
         .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
         .build()?;
+    Ok(client)
+}
 
+pub async fn make_api_request(
+    client: &reqwest::Client,
+    api_endpoint: &str,
+    request_body: &impl Serialize,
+    rate_limiter: Option<&RateLimiter>,
+) -> Result<reqwest::Response> {
+    if let Some(limiter) = rate_limiter {
+        limiter.acquire().await;
+    }
     let response = client.post(api_endpoint).json(&request_body).send().await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        if let Some(limiter) = rate_limiter {
+            limiter.back_off(response.headers());
+        }
+    }
     Ok(response)
 }
 
+/// The OpenAI-compatible `/models` URL next to a chat-completions endpoint
+pub fn models_url(api_endpoint: &str) -> String {
+    let base = api_endpoint
+        .trim_end_matches('/')
+        .trim_end_matches("/chat/completions");
+    format!("{}/models", base)
+}
+
+/// Quick reachability check before the real request, so a mistyped `--api-endpoint`
+/// fails fast with a clear message. Any HTTP response counts as reachable; only
+/// connection errors and timeouts are reported.
+pub async fn check_endpoint(
+    client: &reqwest::Client,
+    api_endpoint: &str,
+    timeout: Duration,
+    verbose: bool,
+) -> Result<()> {
+    let url = models_url(api_endpoint);
+    match client.get(&url).timeout(timeout).send().await {
+        Ok(response) => {
+            if verbose {
+                eprintln!(
+                    "{}",
+                    format!("[AI] Endpoint check: GET {} -> {}", url, response.status()).dimmed()
+                );
+            }
+            Ok(())
+        }
+        Err(e) => {
+            let reason = if e.is_timeout() {
+                format!("no response within {}s", timeout.as_secs())
+            } else {
+                e.to_string()
+            };
+            Err(Cmd2AiError::Other(format!(
+                "Cannot reach endpoint {}: {}",
+                api_endpoint, reason
+            )))
+        }
+    }
+}
+
+/// Response headers that carry a provider request id, in order of preference
+const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "x-generation-id", "request-id"];
+
+/// Extract the provider's request id from response headers, if any
+pub fn request_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
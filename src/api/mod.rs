@@ -1,9 +1,18 @@
 pub mod client;
+pub mod model_list;
 pub mod models;
+pub mod rate_limit;
 pub mod response;
 pub mod streaming;
 
-pub use client::make_api_request;
-pub use models::RequestBody;
-pub use streaming::process_streaming_response;
+pub use client::{
+    build_client, check_endpoint, make_api_request, models_url, request_id_from_headers,
+};
+pub use model_list::{
+    fetch_models, fetch_models_cached, format_model_list, parse_model_list, suggest_models,
+    ModelInfo,
+};
+pub use models::{Citation, RequestBody, Usage};
+pub use rate_limit::RateLimiter;
+pub use streaming::{process_streaming_response, StreamSettings};
 
@@ -0,0 +1,180 @@
+use crate::api::client::models_url;
+use crate::error::{Cmd2AiError, Result};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a fetched model list is reused before asking the endpoint again
+const MODEL_LIST_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Most recent model list per process: (endpoint, fetched at, models)
+static MODEL_LIST_CACHE: Mutex<Option<(String, Instant, Vec<ModelInfo>)>> = Mutex::new(None);
+
+/// One entry of an endpoint's `/models` list
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    pub id: String,
+    /// Context window in tokens (OpenRouter)
+    pub context_length: Option<u64>,
+    /// USD per prompt / completion token, as reported (OpenRouter)
+    pub prompt_price: Option<f64>,
+    pub completion_price: Option<f64>,
+}
+
+/// Parse a `/models` response: `{"data": [...]}` (OpenAI, OpenRouter, most local servers)
+/// or a bare array. Entries without an id are skipped; the result is sorted by id.
+pub fn parse_model_list(response_json: &Value) -> Vec<ModelInfo> {
+    let entries = response_json
+        .get("data")
+        .or_else(|| response_json.get("models"))
+        .unwrap_or(response_json)
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    // Prices are decimal strings on OpenRouter, but accept numbers too
+    let price = |entry: &Value, key: &str| -> Option<f64> {
+        let value = entry.get("pricing")?.get(key)?;
+        value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+    };
+
+    let mut models: Vec<ModelInfo> = entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("id").and_then(|id| id.as_str())?;
+            Some(ModelInfo {
+                id: id.to_string(),
+                context_length: entry.get("context_length").and_then(|c| c.as_u64()),
+                prompt_price: price(entry, "prompt"),
+                completion_price: price(entry, "completion"),
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+/// One line per model: id, then context window and price per million tokens when known
+pub fn format_model_list(models: &[ModelInfo]) -> String {
+    let width = models.iter().map(|m| m.id.len()).max().unwrap_or(0);
+    let mut output = String::new();
+
+    for model in models {
+        let mut details = Vec::new();
+        if let Some(context) = model.context_length {
+            details.push(format!("{}k context", context / 1000));
+        }
+        if let (Some(prompt), Some(completion)) = (model.prompt_price, model.completion_price) {
+            details.push(format!(
+                "${:.2}/${:.2} per 1M tokens",
+                prompt * 1_000_000.0,
+                completion * 1_000_000.0
+            ));
+        }
+
+        if details.is_empty() {
+            output.push_str(&format!("{}\n", model.id));
+        } else {
+            output.push_str(&format!(
+                "{:width$}  {}\n",
+                model.id,
+                details.join(", "),
+                width = width
+            ));
+        }
+    }
+    output
+}
+
+/// Fetch the model list from the `/models` route next to `api_endpoint`
+pub async fn fetch_models(
+    client: &reqwest::Client,
+    api_endpoint: &str,
+    timeout: Duration,
+) -> Result<Vec<ModelInfo>> {
+    let url = models_url(api_endpoint);
+    let response = client.get(&url).timeout(timeout).send().await?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(Cmd2AiError::ApiError { status, message });
+    }
+
+    let body: Value = response.json().await?;
+    let models = parse_model_list(&body);
+    if models.is_empty() {
+        return Err(Cmd2AiError::Other(format!("No models listed at {}", url)));
+    }
+    Ok(models)
+}
+
+/// Like `fetch_models`, but reuses a list fetched for the same endpoint in the last few minutes
+pub async fn fetch_models_cached(
+    client: &reqwest::Client,
+    api_endpoint: &str,
+    timeout: Duration,
+) -> Result<Vec<ModelInfo>> {
+    if let Some((endpoint, fetched_at, models)) = MODEL_LIST_CACHE.lock().unwrap().as_ref() {
+        if endpoint == api_endpoint && fetched_at.elapsed() < MODEL_LIST_CACHE_TTL {
+            return Ok(models.clone());
+        }
+    }
+
+    let models = fetch_models(client, api_endpoint, timeout).await?;
+    *MODEL_LIST_CACHE.lock().unwrap() =
+        Some((api_endpoint.to_string(), Instant::now(), models.clone()));
+    Ok(models)
+}
+
+/// Model ids closest to `wanted` by edit distance, best first. An id also matches on its
+/// part after the provider prefix, so `gpt-4o` finds `openai/gpt-4o`. Returns nothing when
+/// `wanted` is itself listed.
+pub fn suggest_models(models: &[ModelInfo], wanted: &str, limit: usize) -> Vec<String> {
+    if models.iter().any(|m| m.id == wanted) {
+        return Vec::new();
+    }
+
+    let wanted_lower = wanted.to_lowercase();
+    let max_distance = (wanted.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = models
+        .iter()
+        .map(|m| {
+            let id = m.id.to_lowercase();
+            let bare = id.rsplit('/').next().unwrap_or(&id);
+            let distance =
+                edit_distance(&wanted_lower, &id).min(edit_distance(&wanted_lower, bare));
+            (distance, m.id.as_str())
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    scored.sort();
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, id)| id.to_string())
+        .collect()
+}
+
+/// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
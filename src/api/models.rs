@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Serialize)]
 pub struct RequestBody {
@@ -10,13 +11,19 @@ pub struct RequestBody {
     pub reasoning: Option<crate::models::Reasoning>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Value>>,
+    /// Token id -> bias (-100..100), passed through verbatim; ids are model-specific
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Citation {
     pub url: String,
     pub title: String,
-    #[allow(dead_code)]
     pub content: Option<String>,
 }
 
@@ -32,17 +39,56 @@ pub struct Delta {
     pub content: Option<String>,
     pub annotations: Option<Vec<Annotation>>,
     pub reasoning: Option<String>,
-    #[allow(dead_code)]
-    pub tool_calls: Option<Vec<crate::models::ToolCall>>,
+    /// Partial tool calls, see `response::merge_tool_call_delta`
+    pub tool_calls: Option<Vec<Value>>,
 }
 
 #[derive(Deserialize)]
 pub struct Choice {
     pub delta: Option<Delta>,
+    pub finish_reason: Option<String>,
+}
+
+/// Token counts reported by the provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
 }
 
 #[derive(Deserialize)]
 pub struct StreamResponse {
+    /// Generation id (e.g. OpenRouter's "gen-..."), repeated on every chunk
+    pub id: Option<String>,
     pub choices: Option<Vec<Choice>>,
+    /// Usually only present on the last chunk
+    pub usage: Option<Usage>,
+    /// Set when the provider fails after the stream has started (the HTTP status was 200)
+    pub error: Option<StreamError>,
+}
+
+/// Error object sent as a stream event, e.g. `{"error": {"code": 502, "message": "..."}}`
+#[derive(Debug, Deserialize)]
+pub struct StreamError {
+    /// An HTTP-like status number or a string such as "server_error"
+    #[serde(default)]
+    pub code: Option<Value>,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl StreamError {
+    /// Status for `Cmd2AiError::ApiError`: the code when it is an HTTP status, otherwise 502
+    /// since the upstream provider failed mid-response
+    pub fn status(&self) -> u16 {
+        self.code
+            .as_ref()
+            .and_then(|code| code.as_u64())
+            .filter(|code| (400..600).contains(code))
+            .map(|code| code as u16)
+            .unwrap_or(502)
+    }
 }
 
@@ -0,0 +1,59 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Longest pause taken on a 429's `Retry-After`, so a bogus header can't stall a run
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Client-side pacing for `api.rate_limit_rpm`: requests are spaced evenly, 60s / rpm
+/// apart (a token bucket holding one token). Shared by every request of a run, including
+/// parallel `--batch` workers.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// When the next request may be sent
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(rpm: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / rpm.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for this request's slot. Slots are handed out in call order.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+
+    /// After a 429, hold every later request back for the server's `Retry-After`
+    /// (or one interval when it doesn't say)
+    pub fn back_off(&self, headers: &HeaderMap) {
+        let delay = retry_after(headers)
+            .unwrap_or(self.interval)
+            .min(MAX_RATE_LIMIT_BACKOFF);
+        let mut next_slot = self.next_slot.lock().unwrap();
+        *next_slot = (*next_slot).max(Instant::now() + delay);
+    }
+}
+
+/// `Retry-After` in seconds; the HTTP-date form is ignored
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
@@ -1,7 +1,10 @@
+use crate::api::models::{Annotation, Citation, Usage};
+use crate::api::streaming::normalize_citation_url;
 use crate::error::Result;
-use serde_json::Value;
+use serde_json::{json, Value};
 
-/// Parse a non-streaming API response and extract tool calls if present
+/// Parse a non-streaming API response and extract tool calls if present. The deprecated
+/// single `function_call` object is returned as a one-element `tool_calls` array.
 pub fn parse_tool_calls(response_json: &Value) -> Result<Option<Vec<Value>>> {
     let choices = response_json
         .get("choices")
@@ -22,9 +25,47 @@ pub fn parse_tool_calls(response_json: &Value) -> Result<Option<Vec<Value>>> {
         }
     }
 
+    // Legacy shape has no call id; the follow-up needs one to pair the result with the call
+    if let Some(function_call) = message.get("function_call").filter(|f| f.is_object()) {
+        return Ok(Some(vec![json!({
+            "id": "call_0",
+            "type": "function",
+            "function": function_call,
+        })]));
+    }
+
     Ok(None)
 }
 
+/// Add one streamed `tool_calls` delta to the calls assembled so far. Fragments of a call
+/// share its `index`; id and type arrive once, name and arguments in pieces to append.
+/// The result has the shape of a complete response's `tool_calls`.
+pub fn merge_tool_call_delta(calls: &mut Vec<Value>, fragment: &Value) {
+    let index = fragment
+        .get("index")
+        .and_then(|i| i.as_u64())
+        .map_or(calls.len(), |i| i as usize);
+    while calls.len() <= index {
+        calls.push(json!({"type": "function", "function": {}}));
+    }
+    let call = &mut calls[index];
+
+    for key in ["id", "type"] {
+        if let Some(value) = fragment.get(key).filter(|v| v.is_string()) {
+            call[key] = value.clone();
+        }
+    }
+    let Some(function) = fragment.get("function") else {
+        return;
+    };
+    for key in ["name", "arguments"] {
+        if let Some(piece) = function.get(key).and_then(|v| v.as_str()) {
+            let so_far = call["function"][key].as_str().unwrap_or_default();
+            call["function"][key] = Value::String(format!("{}{}", so_far, piece));
+        }
+    }
+}
+
 /// Extract content from a non-streaming response
 pub fn extract_content(response_json: &Value) -> Result<Option<String>> {
     let choices = response_json
@@ -46,7 +87,9 @@ pub fn extract_content(response_json: &Value) -> Result<Option<String>> {
         .map(|s| s.to_string()))
 }
 
-/// Extract reasoning content from a non-streaming response
+/// Extract reasoning content from a non-streaming response: `message.reasoning_content`,
+/// else `message.reasoning` (OpenRouter, like the streaming delta), else the text and
+/// summaries of `message.reasoning_details` joined. Empty values count as missing.
 pub fn extract_reasoning(response_json: &Value) -> Result<Option<String>> {
     let choices = response_json
         .get("choices")
@@ -57,10 +100,159 @@ pub fn extract_reasoning(response_json: &Value) -> Result<Option<String>> {
         .first()
         .ok_or_else(|| crate::error::Cmd2AiError::Other("Empty choices array".to_string()))?;
 
-    Ok(first_choice
-        .get("message")
-        .and_then(|m| m.get("reasoning_content"))
+    let Some(message) = first_choice.get("message") else {
+        return Ok(None);
+    };
+
+    let text = |key: &str| {
+        message
+            .get(key)
+            .and_then(|r| r.as_str())
+            .filter(|r| !r.trim().is_empty())
+            .map(|s| s.to_string())
+    };
+    // Encrypted details carry no readable text and are skipped
+    let details = || {
+        let parts: Vec<&str> = message
+            .get("reasoning_details")?
+            .as_array()?
+            .iter()
+            .filter_map(|detail| {
+                detail
+                    .get("text")
+                    .or_else(|| detail.get("summary"))
+                    .and_then(|t| t.as_str())
+            })
+            .filter(|t| !t.trim().is_empty())
+            .collect();
+        Some(parts.join("\n\n")).filter(|joined| !joined.is_empty())
+    };
+
+    Ok(text("reasoning_content")
+        .or_else(|| text("reasoning"))
+        .or_else(details))
+}
+
+/// Extract the first choice's refusal message (OpenAI sends it in place of content)
+pub fn extract_refusal(response_json: &Value) -> Option<String> {
+    response_json
+        .pointer("/choices/0/message/refusal")
         .and_then(|r| r.as_str())
-        .map(|s| s.to_string()))
+        .filter(|r| !r.trim().is_empty())
+        .map(|s| s.to_string())
 }
 
+/// Extract the first choice's finish reason (e.g. "stop", "length", "tool_calls")
+pub fn extract_finish_reason(response_json: &Value) -> Option<String> {
+    response_json
+        .pointer("/choices/0/finish_reason")
+        .and_then(|r| r.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Extract token usage, if the provider reported it
+pub fn extract_usage(response_json: &Value) -> Option<Usage> {
+    response_json
+        .get("usage")
+        .and_then(|u| serde_json::from_value(u.clone()).ok())
+}
+
+/// Extract url_citation annotations from the first choice's message
+pub fn extract_citations(response_json: &Value) -> Vec<Citation> {
+    let annotations: Vec<Annotation> = response_json
+        .pointer("/choices/0/message/annotations")
+        .and_then(|a| serde_json::from_value(a.clone()).ok())
+        .unwrap_or_default();
+
+    let mut citations: Vec<Citation> = Vec::new();
+    for mut citation in annotations
+        .into_iter()
+        .filter(|a| a.annotation_type == "url_citation")
+        .filter_map(|a| a.url_citation)
+    {
+        // Same normalization and dedup as the streaming path
+        citation.url = normalize_citation_url(&citation.url);
+        if !citations.iter().any(|c| c.url == citation.url) {
+            citations.push(citation);
+        }
+    }
+    citations
+}
+
+/// Attempt to repair malformed JSON tool-call arguments
+/// Handles markdown fences, single-quoted strings, raw newlines inside strings and
+/// trailing commas. Returns None if the input is already valid or still unparsable.
+pub fn repair_tool_arguments(raw: &str) -> Option<Value> {
+    if serde_json::from_str::<Value>(raw).is_ok() {
+        return None;
+    }
+
+    let trimmed = strip_code_fence(raw.trim());
+    let chars: Vec<char> = trimmed.chars().collect();
+    let mut repaired = String::with_capacity(trimmed.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                    // \' is not a valid JSON escape; inside single quotes it is just '
+                    if c == '\'' {
+                        repaired.pop();
+                    }
+                    repaired.push(c);
+                } else if c == '\\' {
+                    escaped = true;
+                    repaired.push(c);
+                } else if c == q {
+                    quote = None;
+                    repaired.push('"');
+                } else if c == '"' {
+                    // Only reachable inside a single-quoted string
+                    repaired.push_str("\\\"");
+                } else if c == '\n' {
+                    repaired.push_str("\\n");
+                } else if c == '\r' {
+                    repaired.push_str("\\r");
+                } else if c == '\t' {
+                    repaired.push_str("\\t");
+                } else {
+                    repaired.push(c);
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                    repaired.push('"');
+                } else if c == ',' {
+                    // Drop trailing commas before a closing brace/bracket
+                    let next = chars[i + 1..].iter().find(|n| !n.is_whitespace());
+                    if !matches!(next, Some('}') | Some(']')) {
+                        repaired.push(c);
+                    }
+                } else {
+                    repaired.push(c);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    serde_json::from_str(&repaired).ok()
+}
+
+/// Remove a surrounding ```json ... ``` fence if the model wrapped its arguments in one
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let body = match rest.find('\n') {
+        Some(pos) => &rest[pos + 1..],
+        None => rest,
+    };
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
@@ -1,44 +1,139 @@
-use crate::api::models::{Citation, StreamResponse};
+use crate::api::models::{Citation, StreamResponse, Usage};
+use crate::api::response::merge_tool_call_delta;
 use crate::error::{Cmd2AiError, Result};
 use crate::ui::highlight::CodeBuffer;
+use crate::ui::output::{hyperlink, json_response_block, JsonPrefixScanner};
+use crate::ui::{RenderOptions, Spinner};
 use colored::*;
 use futures::StreamExt;
+use serde_json::Value;
 use std::io::{self, Write};
 use tokio::time::{timeout, Duration};
 
+/// How a stream is read and reported
+#[derive(Debug, Clone)]
+pub struct StreamSettings {
+    /// Seconds allowed between chunks before giving up
+    pub timeout_secs: u64,
+    pub reasoning_exclude: bool,
+    pub verbose: bool,
+    /// Print latency and throughput stats when the stream ends (`--stats`)
+    pub show_stats: bool,
+    /// When the request was sent; latency is measured from here
+    pub request_started: std::time::Instant,
+    /// Rendered `ui.answer_header`, written when the first event arrives
+    pub answer_header: Option<String>,
+    /// How often rendered output is flushed to the terminal (`ui.flush_interval_ms`)
+    pub flush_interval: Duration,
+    /// Render the answer as it arrives; off (`--no-incremental`), it is rendered once complete
+    pub incremental: bool,
+    /// No progress notes on stderr (library calls, `--json`, concurrent `--batch`)
+    pub quiet: bool,
+}
+
 pub struct StreamingResult {
+    /// The answer exactly as the model sent it; what is saved to the session. Display-side
+    /// changes (pretty-printed JSON, highlighting) only ever apply to what is written to `out`.
     pub content: String,
+    /// Generation id reported in the stream chunks
+    pub generation_id: Option<String>,
+    /// Reasoning tokens received, if any (kept even with `reasoning_exclude`), also unchanged
+    /// by the cleanup applied for display
+    pub reasoning: Option<String>,
+    /// Deduplicated url_citation annotations, in the order they arrived
+    pub citations: Vec<Citation>,
+    pub usage: Option<Usage>,
+    pub finish_reason: Option<String>,
+    /// Tool calls assembled from `tool_calls` deltas, shaped like a complete response's
+    pub tool_calls: Vec<Value>,
 }
 
 pub async fn process_streaming_response(
     response: reqwest::Response,
-    timeout_secs: u64,
-    reasoning_exclude: bool,
-    verbose: bool,
+    out: &mut dyn Write,
+    settings: &StreamSettings,
+    render_options: &RenderOptions,
+    mut spinner: Spinner,
 ) -> Result<StreamingResult> {
+    let StreamSettings {
+        timeout_secs,
+        reasoning_exclude,
+        verbose,
+        show_stats,
+        request_started,
+        mut answer_header,
+        flush_interval,
+        incremental,
+        quiet,
+    } = settings.clone();
+    let started = std::time::Instant::now();
     let mut stream = response.bytes_stream();
     let mut buffer = String::new();
     let mut citations: Vec<Citation> = vec![];
-    let mut code_buffer = CodeBuffer::new();
-    let mut reasoning_code_buffer = CodeBuffer::new();
+    let mut tool_calls: Vec<Value> = Vec::new();
+    // "(source added: ...)" notes wait for the answer to reach the end of a line
+    let mut pending_sources: Vec<String> = Vec::new();
+    let mut at_line_start = true;
+    // Reasoning sent to stderr (--reasoning-to-stderr) is silenced with the rest when quiet
+    let mut err_out: Box<dyn Write> = if quiet {
+        Box::new(io::sink())
+    } else {
+        Box::new(io::stderr())
+    };
+    let mut code_buffer = CodeBuffer::with_options(render_options.clone());
+    let mut reasoning_code_buffer = CodeBuffer::with_options(render_options.for_reasoning());
     let mut last_flush = std::time::Instant::now();
-    let flush_interval = std::time::Duration::from_millis(50);
     let mut incomplete_line = String::new();
     let mut assistant_response = String::new();
     let mut reasoning_response = String::new();
     let mut reasoning_buffer = String::new();
     let mut reasoning_displayed = false;
+    // Some(true) once the response looks like a bare JSON document; it is then held
+    // back and pretty-printed when complete. Without incremental rendering the whole
+    // answer is held back the same way.
+    let mut json_candidate: Option<bool> = (!incremental).then_some(true);
+    let mut json_scanner = JsonPrefixScanner::default();
+    let mut generation_id: Option<String> = None;
+    let mut usage: Option<Usage> = None;
+    let mut finish_reason: Option<String> = None;
+    let mut first_chunk_at: Option<std::time::Instant> = None;
     let chunk_timeout = Duration::from_secs(timeout_secs);
+    // One listener for the whole stream so no interrupt is missed between chunks
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
 
     loop {
-        match timeout(chunk_timeout, stream.next()).await {
+        let next = tokio::select! {
+            next = timeout(chunk_timeout, stream.next()) => next,
+            _ = &mut ctrl_c => {
+                spinner.stop();
+                // Show what arrived so far and hand the partial answer back to be saved
+                finish_output(
+                    out,
+                    &mut err_out,
+                    render_options,
+                    &mut code_buffer,
+                    &mut reasoning_code_buffer,
+                    (reasoning_displayed && !reasoning_exclude).then_some(reasoning_buffer.as_str()),
+                    (json_candidate == Some(true)).then_some(assistant_response.as_str()),
+                )?;
+                writeln!(out)?;
+                out.flush()?;
+                return Err(Cmd2AiError::Interrupted {
+                    partial: assistant_response,
+                });
+            }
+        };
+
+        match next {
             Ok(Some(chunk)) => {
-                let chunk = chunk.map_err(|e| Cmd2AiError::NetworkError(e))?;
+                let chunk = chunk.map_err(Cmd2AiError::NetworkError)?;
                 let text = String::from_utf8_lossy(&chunk);
                 incomplete_line.push_str(&text);
             }
             Ok(None) => break,
             Err(_) => {
+                spinner.stop();
                 eprintln!(
                     "{}",
                     format!(
@@ -53,13 +148,20 @@ pub async fn process_streaming_response(
                         .dimmed()
                 );
 
-                let remaining = code_buffer.flush();
-                if !remaining.is_empty() {
-                    print!("{}", remaining.trim_end());
-                    println!();
+                let wrote = finish_output(
+                    out,
+                    &mut err_out,
+                    render_options,
+                    &mut code_buffer,
+                    &mut reasoning_code_buffer,
+                    (reasoning_displayed && !reasoning_exclude).then_some(reasoning_buffer.as_str()),
+                    (json_candidate == Some(true)).then_some(assistant_response.as_str()),
+                )?;
+                if wrote {
+                    writeln!(out)?;
                 }
 
-                io::stdout().flush()?;
+                out.flush()?;
                 return Err(Cmd2AiError::Timeout);
             }
         }
@@ -88,55 +190,99 @@ pub async fn process_streaming_response(
 
                 match field {
                     "data" => {
+                        // The first event means generation has started (comments are keep-alives)
+                        spinner.stop();
+                        first_chunk_at.get_or_insert_with(std::time::Instant::now);
+                        if let Some(header) = answer_header.take() {
+                            writeln!(out, "{}", header.dimmed())?;
+                        }
                         if value == "[DONE]" {
-                            // Close reasoning section if it was displayed
-                            if reasoning_displayed && !reasoning_exclude {
-                                // Close reasoning block with CodeBuffer
-                                // Avoid double newline if reasoning_buffer already ends with one
-                                let sep = if reasoning_buffer.ends_with('\n') { "" } else { "\n" };
-                                let reasoning_end = format!("{}\n```", sep);
-                                let formatted = reasoning_code_buffer.append(&reasoning_end);
-                                if !formatted.is_empty() {
-                                    print!("{}", formatted);
-                                }
-                                let remaining = reasoning_code_buffer.flush();
-                                if !remaining.is_empty() {
-                                    print!("{}", remaining.trim_end());
-                                }
-                                println!();
-                            }
-
-                            // Flush any remaining content
-                            let remaining = code_buffer.flush();
-                            if !remaining.is_empty() {
-                                print!("{}", remaining.trim_end());
-                            }
+                            let held = (json_candidate == Some(true)).then(|| {
+                                json_response_block(&assistant_response)
+                                    .unwrap_or_else(|| assistant_response.clone())
+                            });
+                            finish_output(
+                                out,
+                                &mut err_out,
+                                render_options,
+                                &mut code_buffer,
+                                &mut reasoning_code_buffer,
+                                (reasoning_displayed && !reasoning_exclude)
+                                    .then_some(reasoning_buffer.as_str()),
+                                held.as_deref(),
+                            )?;
 
                             // Display citations if any
-                            if !citations.is_empty() {
-                                println!("{}", "\n\n---\nSources:".dimmed());
-                                for (index, citation) in citations.iter().enumerate() {
-                                    println!(
-                                        "{}",
-                                        format!("[{}] {}", index + 1, citation.title).cyan()
-                                    );
-                                    println!("{}", format!("    {}", citation.url).dimmed());
-                                }
-                            }
+                            print_citations(out, &citations, render_options)?;
 
-                            println!();
-                            io::stdout().flush()?;
+                            writeln!(out)?;
+                            out.flush()?;
+
+                            if show_stats || verbose {
+                                let chars = assistant_response.chars().count()
+                                    + reasoning_response.chars().count();
+                                eprintln!(
+                                    "{}",
+                                    format_stream_stats(started.elapsed(), chars, citations.len())
+                                        .dimmed()
+                                );
+                                let first_chunk = first_chunk_at.map(|at| at - request_started);
+                                eprintln!(
+                                    "{}",
+                                    format_latency(first_chunk, request_started.elapsed()).dimmed()
+                                );
+                            }
 
                             return Ok(StreamingResult {
                                 content: assistant_response,
+                                generation_id,
+                                reasoning: Some(reasoning_response).filter(|r| !r.is_empty()),
+                                citations,
+                                usage,
+                                finish_reason,
+                                tool_calls,
                             });
                         }
 
                         // Parse JSON data
                         match serde_json::from_str::<StreamResponse>(value) {
                             Ok(parsed) => {
+                                if let Some(error) = parsed.error {
+                                    // Keep what streamed so far on screen, then report why it stopped
+                                    finish_output(
+                                        out,
+                                        &mut err_out,
+                                        render_options,
+                                        &mut code_buffer,
+                                        &mut reasoning_code_buffer,
+                                        (reasoning_displayed && !reasoning_exclude)
+                                            .then_some(reasoning_buffer.as_str()),
+                                        (json_candidate == Some(true))
+                                            .then_some(assistant_response.as_str()),
+                                    )?;
+                                    writeln!(out)?;
+                                    out.flush()?;
+
+                                    let mut message = error.message.clone();
+                                    if let Some(code) = error.code.as_ref().and_then(|c| c.as_str()) {
+                                        message = format!("{} ({})", message, code);
+                                    }
+                                    return Err(Cmd2AiError::ApiError {
+                                        status: error.status(),
+                                        message,
+                                    });
+                                }
+                                if generation_id.is_none() {
+                                    generation_id = parsed.id;
+                                }
+                                if parsed.usage.is_some() {
+                                    usage = parsed.usage;
+                                }
                                 if let Some(choices) = parsed.choices {
                                     for choice in choices {
+                                        if choice.finish_reason.is_some() {
+                                            finish_reason = choice.finish_reason;
+                                        }
                                         if let Some(delta) = choice.delta {
                                             // Process reasoning tokens
                                             if let Some(reasoning) = delta.reasoning {
@@ -146,12 +292,12 @@ pub async fn process_streaming_response(
                                                 if !reasoning_exclude {
                                                     if !reasoning_displayed {
                                                         // Start reasoning block with CodeBuffer
-                                                        println!();
+                                                        render_options.write_reasoning(out, &mut err_out, "\n")?;
                                                         let reasoning_start = "```REASONING\n";
                                                         let formatted =
                                                             reasoning_code_buffer.append(reasoning_start);
                                                         if !formatted.is_empty() {
-                                                            print!("{}", formatted);
+                                                            render_options.write_reasoning(out, &mut err_out, &formatted)?;
                                                         }
                                                         reasoning_displayed = true;
                                                     }
@@ -167,10 +313,10 @@ pub async fn process_streaming_response(
                                                         let formatted =
                                                             reasoning_code_buffer.append(&display_reasoning);
                                                         if !formatted.is_empty() {
-                                                            print!("{}", formatted);
+                                                            render_options.write_reasoning(out, &mut err_out, &formatted)?;
                                                         }
                                                         if last_flush.elapsed() > flush_interval {
-                                                            io::stdout().flush()?;
+                                                            out.flush()?;
                                                             last_flush = std::time::Instant::now();
                                                         }
                                                     }
@@ -194,39 +340,96 @@ pub async fn process_streaming_response(
                                                     let formatted =
                                                         reasoning_code_buffer.append(&reasoning_end);
                                                     if !formatted.is_empty() {
-                                                        print!("{}", formatted);
+                                                        render_options.write_reasoning(out, &mut err_out, &formatted)?;
                                                     }
                                                     let remaining = reasoning_code_buffer.flush();
                                                     if !remaining.is_empty() {
-                                                        print!("{}", remaining.trim_end());
+                                                        render_options.write_reasoning(out, &mut err_out, remaining.trim_end())?;
                                                     }
-                                                    println!(); // Add spacing after reasoning block
+                                                    render_options.write_reasoning(out, &mut err_out, "\n")?; // Add spacing after reasoning block
                                                     reasoning_displayed = false;
                                                     reasoning_buffer.clear();
                                                 }
 
                                                 assistant_response.push_str(&content);
 
-                                                let formatted = code_buffer.append(&content);
+                                                let to_render = match json_candidate {
+                                                    // Prose that merely opens with a bracket streams from here on
+                                                    Some(true)
+                                                        if incremental && !json_scanner.feed(&content) =>
+                                                    {
+                                                        json_candidate = Some(false);
+                                                        assistant_response.clone()
+                                                    }
+                                                    Some(true) => String::new(),
+                                                    Some(false) => content,
+                                                    None => {
+                                                        let start = assistant_response.trim_start();
+                                                        if start.is_empty() {
+                                                            String::new()
+                                                        } else {
+                                                            let is_json = (start.starts_with('{')
+                                                                || start.starts_with('['))
+                                                                && json_scanner.feed(start);
+                                                            json_candidate = Some(is_json);
+                                                            if is_json {
+                                                                String::new()
+                                                            } else {
+                                                                // Leading whitespace was held back too
+                                                                assistant_response.clone()
+                                                            }
+                                                        }
+                                                    }
+                                                };
+
+                                                let formatted = code_buffer.append(&to_render);
                                                 if !formatted.is_empty() {
-                                                    print!("{}", formatted);
+                                                    write!(out, "{}", formatted)?;
+                                                    at_line_start = formatted.ends_with('\n');
+                                                    if at_line_start && !pending_sources.is_empty() {
+                                                        out.flush()?;
+                                                        for note in pending_sources.drain(..) {
+                                                            eprintln!("{}", note.dimmed());
+                                                        }
+                                                    }
 
                                                     if last_flush.elapsed() > flush_interval {
-                                                        io::stdout().flush()?;
+                                                        out.flush()?;
                                                         last_flush = std::time::Instant::now();
                                                     }
                                                 }
                                             }
 
+                                            // Tool calls arrive in fragments, assembled by index
+                                            for fragment in delta.tool_calls.iter().flatten() {
+                                                merge_tool_call_delta(&mut tool_calls, fragment);
+                                            }
+
                                             // Process annotations
                                             if let Some(annotations) = delta.annotations {
                                                 for annotation in annotations {
                                                     if annotation.annotation_type == "url_citation" {
-                                                        if let Some(citation) = annotation.url_citation {
+                                                        if let Some(mut citation) = annotation.url_citation {
+                                                            // Dedup on the normalized URL, keeping the first title seen
+                                                            citation.url = normalize_citation_url(&citation.url);
                                                             if !citations
                                                                 .iter()
                                                                 .any(|c| c.url == citation.url)
                                                             {
+                                                                // Show that search is working before the Sources list at the end
+                                                                let note = format!(
+                                                                    "(source added: [{}] {})",
+                                                                    citations.len() + 1,
+                                                                    citation.title
+                                                                );
+                                                                if !quiet {
+                                                                    if at_line_start {
+                                                                        out.flush()?;
+                                                                        eprintln!("{}", note.dimmed());
+                                                                    } else {
+                                                                        pending_sources.push(note);
+                                                                    }
+                                                                }
                                                                 citations.push(citation);
                                                             }
                                                         }
@@ -263,40 +466,171 @@ pub async fn process_streaming_response(
     }
 
     // Handle case where stream ends without [DONE]
-    if reasoning_displayed && !reasoning_exclude {
-        // Close reasoning block with CodeBuffer
-        // Avoid double newline if reasoning_buffer already ends with one
-        let sep = if reasoning_buffer.ends_with('\n') { "" } else { "\n" };
-        let reasoning_end = format!("{}\n```", sep);
-        let formatted = reasoning_code_buffer.append(&reasoning_end);
+    let held = (json_candidate == Some(true)).then(|| {
+        json_response_block(&assistant_response).unwrap_or_else(|| assistant_response.clone())
+    });
+    finish_output(
+        out,
+        &mut err_out,
+        render_options,
+        &mut code_buffer,
+        &mut reasoning_code_buffer,
+        (reasoning_displayed && !reasoning_exclude).then_some(reasoning_buffer.as_str()),
+        held.as_deref(),
+    )?;
+
+    print_citations(out, &citations, render_options)?;
+
+    writeln!(out)?;
+    out.flush()?;
+
+    if show_stats || verbose {
+        let chars = assistant_response.chars().count() + reasoning_response.chars().count();
+        eprintln!(
+            "{}",
+            format_stream_stats(started.elapsed(), chars, citations.len()).dimmed()
+        );
+        let first_chunk = first_chunk_at.map(|at| at - request_started);
+        eprintln!(
+            "{}",
+            format_latency(first_chunk, request_started.elapsed()).dimmed()
+        );
+    }
+
+    Ok(StreamingResult {
+        content: assistant_response,
+        generation_id,
+        reasoning: Some(reasoning_response).filter(|r| !r.is_empty()),
+        citations,
+        usage,
+        finish_reason,
+        tool_calls,
+    })
+}
+
+/// Render what is still pending when the stream stops, however it stops: close an open
+/// reasoning block (`open_reasoning` is its text so far), write text that was held back
+/// (`held`: a JSON candidate, or the whole answer with `--no-incremental`), then flush
+/// `code_buffer`. Returns whether anything was written after the reasoning.
+fn finish_output(
+    out: &mut dyn Write,
+    err_out: &mut dyn Write,
+    render_options: &RenderOptions,
+    code_buffer: &mut CodeBuffer,
+    reasoning_code_buffer: &mut CodeBuffer,
+    open_reasoning: Option<&str>,
+    held: Option<&str>,
+) -> io::Result<bool> {
+    if let Some(reasoning) = open_reasoning {
+        // Avoid double newline if the reasoning already ends with one
+        let sep = if reasoning.ends_with('\n') { "" } else { "\n" };
+        let formatted = reasoning_code_buffer.append(&format!("{}\n```", sep));
         if !formatted.is_empty() {
-            print!("{}", formatted);
+            render_options.write_reasoning(out, err_out, &formatted)?;
         }
         let remaining = reasoning_code_buffer.flush();
         if !remaining.is_empty() {
-            print!("{}", remaining.trim_end());
+            render_options.write_reasoning(out, err_out, remaining.trim_end())?;
         }
-        println!();
+        render_options.write_reasoning(out, err_out, "\n")?;
     }
 
-    let remaining = code_buffer.flush();
-    if !remaining.is_empty() {
-        print!("{}", remaining.trim_end());
+    let mut rendered = String::new();
+    if let Some(held) = held {
+        rendered.push_str(&code_buffer.append(held));
     }
+    rendered.push_str(code_buffer.flush().trim_end());
+    write!(out, "{}", rendered)?;
+    Ok(!rendered.is_empty())
+}
 
-    if !citations.is_empty() {
-        println!("{}", "\n\n---\nSources:".dimmed());
-        for (index, citation) in citations.iter().enumerate() {
-            println!("{}", format!("[{}] {}", index + 1, citation.title).cyan());
-            println!("{}", format!("    {}", citation.url).dimmed());
-        }
+/// Write the Sources list, with titles as OSC-8 links when the terminal supports them
+fn print_citations(
+    out: &mut dyn Write,
+    citations: &[Citation],
+    render_options: &RenderOptions,
+) -> io::Result<()> {
+    if citations.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "{}", "\n\n---\nSources:".dimmed())?;
+    for (index, citation) in citations.iter().enumerate() {
+        let title = format!("{}", citation.title.cyan());
+        let title = if render_options.hyperlinks {
+            hyperlink(&title, &citation.url)
+        } else {
+            title
+        };
+        writeln!(out, "{}{}", format!("[{}] ", index + 1).cyan(), title)?;
+        writeln!(out, "{}", format!("    {}", citation.url).dimmed())?;
     }
+    Ok(())
+}
 
-    println!();
-    io::stdout().flush()?;
+/// One-line summary of a finished stream; tokens are estimated at ~4 characters each
+pub fn format_stream_stats(elapsed: Duration, chars: usize, citations: usize) -> String {
+    let secs = elapsed.as_secs_f64();
+    let approx_tokens = chars as f64 / 4.0;
+    let tokens_per_sec = if secs > 0.0 { approx_tokens / secs } else { 0.0 };
+    format!(
+        "[AI] Stream: {:.1}s, {} chars, ~{:.0} tokens/s, {} citation{}",
+        secs,
+        chars,
+        tokens_per_sec,
+        citations,
+        if citations == 1 { "" } else { "s" }
+    )
+}
 
-    Ok(StreamingResult {
-        content: assistant_response,
-    })
+/// Time to first token (the first streamed event) and total time, both from request send
+pub fn format_latency(time_to_first_token: Option<Duration>, total: Duration) -> String {
+    let first = match time_to_first_token {
+        Some(ttft) => format!("{:.2}s", ttft.as_secs_f64()),
+        None => "none".to_string(),
+    };
+    format!(
+        "[AI] Latency: first token {}, total {:.2}s",
+        first,
+        total.as_secs_f64()
+    )
 }
 
+/// Query parameters that only track the click and never change the page
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref", "ref_src", "yclid",
+];
+
+/// Normalize a citation URL for dedup and display: drops the fragment, tracking
+/// query parameters (`utm_*`, `fbclid`, ...) and trailing slashes
+pub fn normalize_citation_url(raw: &str) -> String {
+    let raw = raw.trim();
+    let Ok(mut url) = reqwest::Url::parse(raw) else {
+        return raw.trim_end_matches('/').to_string();
+    };
+
+    url.set_fragment(None);
+
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+
+    let path = url.path().trim_end_matches('/').to_string();
+    url.set_path(&path);
+
+    let mut normalized = url.to_string();
+    if url.query().is_none() && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    normalized
+}
@@ -0,0 +1,84 @@
+use crate::error::Result;
+use crate::models::Message;
+use crate::orchestrator::{run_with_model, OrchestratorContext, QueryOutcome};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+
+/// Split a `--batch` file into prompts: blocks separated by `---` lines when the file has
+/// any, otherwise one prompt per non-empty line
+pub fn parse_batch(contents: &str) -> Vec<String> {
+    let has_separators = contents.lines().any(|line| line.trim() == "---");
+
+    let prompts: Vec<String> = if has_separators {
+        let mut blocks = vec![String::new()];
+        for line in contents.lines() {
+            if line.trim() == "---" {
+                blocks.push(String::new());
+            } else {
+                let block = blocks.last_mut().unwrap();
+                block.push_str(line);
+                block.push('\n');
+            }
+        }
+        blocks
+    } else {
+        contents.lines().map(String::from).collect()
+    };
+
+    prompts
+        .into_iter()
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty())
+        .collect()
+}
+
+/// A fresh conversation for one batch prompt: the system messages, then the prompt
+pub fn batch_messages(system_messages: &[Message], prompt: &str) -> Vec<Message> {
+    let mut messages = system_messages.to_vec();
+    messages.push(Message {
+        role: "user".to_string(),
+        content: Some(prompt.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    });
+    messages
+}
+
+/// A batch prompt ready to send: the message (after any framing) and the model it goes to
+#[derive(Debug, Clone)]
+pub struct BatchPrompt {
+    pub message: String,
+    pub model: String,
+}
+
+/// One line of `--batch --json` output
+#[derive(Debug, Serialize)]
+pub struct BatchRecord {
+    /// Position of the prompt in the batch file, from 0
+    pub index: usize,
+    pub prompt: String,
+    #[serde(flatten)]
+    pub outcome: Option<QueryOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Run each prompt in a fresh conversation, up to `concurrency` at once over the shared
+/// context (and its HTTP client). Results come out in prompt order; `on_start` is called
+/// with a prompt's index just before its request is made.
+pub fn run_prompts<'a>(
+    context: &'a OrchestratorContext,
+    system_messages: &'a [Message],
+    prompts: &'a [BatchPrompt],
+    concurrency: usize,
+    on_start: impl Fn(usize) + Copy + 'a,
+) -> impl Stream<Item = (usize, Result<QueryOutcome>)> + 'a {
+    stream::iter(prompts.iter().enumerate())
+        .map(move |(index, prompt)| async move {
+            on_start(index);
+            let mut messages = batch_messages(system_messages, &prompt.message);
+            let result = run_with_model(context, &mut messages, &prompt.model).await;
+            (index, result)
+        })
+        .buffered(concurrency.max(1))
+}
@@ -1,3 +1,4 @@
+use cmd2ai::config::DEFAULT_MODEL;
 use colored::*;
 use futures::StreamExt;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
@@ -23,7 +24,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // Get model
-    let model = env::var("AI_MODEL").unwrap_or_else(|_| "openai/gpt-5".to_string());
+    let model = env::var("AI_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
 
     println!("{}", format!("Using model: {}", model).green());
     println!("{}", format!("Prompt: {}", prompt).cyan());
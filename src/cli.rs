@@ -1,10 +1,15 @@
 use clap::Parser;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Default)]
 #[command(name = "ai")]
 #[command(about = "AI command-line tool using OpenRouter API", long_about = None)]
 pub struct Args {
-    #[arg(short = 'n', long = "new", help = "Start a new conversation")]
+    #[arg(
+        short = 'n',
+        long = "new",
+        conflicts_with = "force_continue",
+        help = "Start a new conversation"
+    )]
     pub new_conversation: bool,
 
     #[arg(
@@ -17,6 +22,55 @@ pub struct Args {
     #[arg(long = "clear", help = "Clear all conversation history")]
     pub clear_history: bool,
 
+    #[arg(
+        long = "summarize-history",
+        help = "Replace history that would be trimmed with a model-written summary (one extra request)"
+    )]
+    pub summarize_history: bool,
+
+    #[arg(
+        long = "doctor",
+        help = "Check the API key, config, endpoint, session cache and tool programs, then exit"
+    )]
+    pub doctor: bool,
+
+    #[arg(
+        long = "full",
+        requires = "doctor",
+        help = "With --doctor, also send one short (billed) request to the model"
+    )]
+    pub full: bool,
+
+    #[arg(
+        long = "prune-sessions",
+        help = "Delete the oldest session files beyond session.max_files and exit"
+    )]
+    pub prune_sessions: bool,
+
+    #[arg(
+        long = "system",
+        value_name = "PROMPT",
+        help = "Set the system prompt for this conversation (kept when continuing)"
+    )]
+    pub system: Option<String>,
+
+    #[arg(
+        long = "append-system",
+        value_name = "TEXT",
+        help = "Append text to the system prompt for this conversation"
+    )]
+    pub append_system: Option<String>,
+
+    #[arg(long = "no-date", help = "Don't prepend today's date to the system prompt")]
+    pub no_date: bool,
+
+    #[arg(
+        long = "no-system",
+        conflicts_with_all = ["system", "append_system"],
+        help = "Send no system message at all, not even the date (the stored prompt is kept)"
+    )]
+    pub no_system: bool,
+
     #[arg(
         long = "reasoning-effort",
         help = "Set reasoning effort level (high, medium, low)"
@@ -29,12 +83,46 @@ pub struct Args {
     )]
     pub reasoning_max_tokens: Option<u32>,
 
+    #[arg(
+        long = "frequency-penalty",
+        help = "Penalize tokens by how often they already appeared (-2.0 to 2.0)",
+        allow_hyphen_values = true
+    )]
+    pub frequency_penalty: Option<f32>,
+
+    #[arg(
+        long = "presence-penalty",
+        help = "Penalize tokens that already appeared at all (-2.0 to 2.0)",
+        allow_hyphen_values = true
+    )]
+    pub presence_penalty: Option<f32>,
+
     #[arg(
         long = "reasoning-exclude",
         help = "Use reasoning but exclude from response"
     )]
     pub reasoning_exclude: bool,
 
+    #[arg(
+        long = "reasoning-to-stderr",
+        help = "Print reasoning to stderr so stdout only carries the answer"
+    )]
+    pub reasoning_to_stderr: bool,
+
+    #[arg(
+        long = "no-incremental",
+        help = "Render a streamed answer once it is complete instead of as it arrives"
+    )]
+    pub no_incremental: bool,
+
+    #[arg(
+        long = "width",
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Render code blocks exactly N columns wide instead of the terminal width"
+    )]
+    pub width: Option<u16>,
+
     #[arg(
         long = "reasoning-enabled",
         help = "Enable reasoning with default parameters"
@@ -53,21 +141,159 @@ pub struct Args {
     )]
     pub auto_tools: bool,
 
-    #[arg(long = "no-tools", help = "Disable all tools for this query")]
+    #[arg(
+        long = "no-tools",
+        conflicts_with_all = ["use_tools", "auto_tools"],
+        help = "Disable all tools for this query"
+    )]
     pub no_tools: bool,
 
+    #[arg(
+        long = "stream",
+        conflicts_with = "no_stream",
+        help = "Always stream the answer, assembling tool calls from the stream"
+    )]
+    pub stream: bool,
+
+    #[arg(
+        long = "no-stream",
+        help = "Never stream: print each answer once it is complete (e.g. for exact JSON)"
+    )]
+    pub no_stream: bool,
+
+    #[arg(
+        long = "tool-timeout",
+        value_name = "SECS",
+        help = "Use this timeout for every tool in this run, replacing each tool's timeout_secs"
+    )]
+    pub tool_timeout: Option<u64>,
+
+    #[arg(
+        long = "raw-request",
+        value_name = "PATH",
+        help = "Merge this JSON file over every request body (JSON merge patch; null removes a field)"
+    )]
+    pub raw_request: Option<std::path::PathBuf>,
+
     #[arg(
         long = "config-init",
         help = "Initialize a config file with example local tools"
     )]
     pub config_init: bool,
 
+    #[arg(
+        long = "config-schema",
+        help = "Print a JSON Schema for the config file (for editor completion and validation)"
+    )]
+    pub config_schema: bool,
+
+    #[arg(
+        long = "explain-tools",
+        help = "Print the tool definitions sent to the model as JSON and exit"
+    )]
+    pub explain_tools: bool,
+
+    #[arg(
+        long = "list-tools",
+        help = "List the available tools with their descriptions and required arguments, then exit"
+    )]
+    pub list_tools: bool,
+
+    #[arg(
+        long = "models",
+        visible_alias = "model-list",
+        help = "List the models offered by the API endpoint, then exit"
+    )]
+    pub list_models: bool,
+
+    #[arg(
+        long = "check-endpoint",
+        help = "Check that the API endpoint is reachable before sending the request"
+    )]
+    pub check_endpoint: bool,
+
+    #[arg(
+        long = "env-file",
+        value_name = "PATH",
+        help = "Load variables from a .env file (without overriding set ones); also AI_ENV_FILE"
+    )]
+    pub env_file: Option<std::path::PathBuf>,
+
     #[arg(
         long = "api-endpoint",
         help = "Custom API base URL (e.g., http://localhost:11434/v1)"
     )]
     pub api_endpoint: Option<String>,
 
+    #[arg(
+        long = "batch",
+        value_name = "FILE",
+        help = "Run each line (or each ---separated block) of FILE as its own prompt, in fresh conversations"
+    )]
+    pub batch: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "json",
+        requires = "batch",
+        help = "With --batch, print one JSON object per prompt instead of rendered answers"
+    )]
+    pub json: bool,
+
+    #[arg(
+        long = "batch-concurrency",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "batch",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "With --batch, run up to N prompts at once (output stays in file order)"
+    )]
+    pub batch_concurrency: u16,
+
+    #[arg(
+        long = "save-answer",
+        value_name = "PATH",
+        help = "Also write the plain answer (no highlighting or boxes) to a file"
+    )]
+    pub save_answer: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "append-answer",
+        requires = "save_answer",
+        help = "Append to the --save-answer file instead of overwriting it"
+    )]
+    pub append_answer: bool,
+
+    #[arg(
+        long = "prompt",
+        value_name = "NAME",
+        help = "Use the named template from the prompts: config section, with the command as {{input}}"
+    )]
+    pub prompt: Option<String>,
+
+    #[arg(
+        long = "prepend",
+        value_name = "TEXT",
+        help = "Text placed before the command in the user message"
+    )]
+    pub prepend: Option<String>,
+
+    #[arg(
+        long = "append",
+        value_name = "TEXT",
+        help = "Text placed after the command in the user message"
+    )]
+    pub append: Option<String>,
+
+    #[arg(
+        long = "stats",
+        visible_alias = "time",
+        help = "Print time to first token, total latency and throughput after a streamed response"
+    )]
+    pub stats: bool,
+
+    #[arg(long = "copy", help = "Copy the plain answer to the system clipboard")]
+    pub copy: bool,
+
     #[arg(help = "Command to send to AI")]
     pub command: Vec<String>,
 }
@@ -1,19 +1,28 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ApiConfig {
     #[serde(default)]
     pub endpoint: Option<String>,
     #[serde(default)]
     pub stream_timeout: Option<u64>,
-}
-
-impl Default for ApiConfig {
-    fn default() -> Self {
-        Self {
-            endpoint: None,
-            stream_timeout: None,
-        }
-    }
+    /// How long an idle keep-alive connection stays in the pool
+    #[serde(default)]
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// Idle connections kept per host (0 disables keep-alive)
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Ask again when the model answers with no content (default: false)
+    #[serde(default)]
+    pub retry_on_empty: Option<bool>,
+    /// Retries allowed by retry_on_empty (default: 2)
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Force streaming on or off (default: stream unless tools are offered)
+    #[serde(default)]
+    pub stream: Option<bool>,
+    /// Client-side cap on requests per minute (default: off)
+    #[serde(default)]
+    pub rate_limit_rpm: Option<u32>,
 }
 
@@ -1,3 +1,21 @@
+/// Model used when neither AI_MODEL nor model.default_model is set
+pub const DEFAULT_MODEL: &str = "openai/gpt-5";
+pub const DEFAULT_API_ENDPOINT: &str = "https://openrouter.ai/api/v1/chat/completions";
+
+/// Format of the date injected into the system prompt (e.g. "Monday, January 01, 2024")
+pub const DEFAULT_DATE_FORMAT: &str = "%A, %B %d, %Y";
+pub const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 4;
+/// Session files kept in the cache dir before the oldest are pruned; 0 keeps them all, so
+/// history is only deleted when session.max_files is set
+pub const DEFAULT_MAX_SESSION_FILES: usize = 0;
+/// Retries after an empty answer when api.retry_on_empty is on
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+/// Lines of a code block highlighted before the rest is shown plain
+pub const DEFAULT_MAX_HIGHLIGHT_LINES: usize = 2000;
+/// How often streamed output is flushed to the terminal
+pub const DEFAULT_FLUSH_INTERVAL_MS: u64 = 50;
+
 pub fn default_tools_enabled() -> bool {
     true
 }
@@ -6,6 +24,14 @@ pub fn default_local_tools_enabled() -> bool {
     true
 }
 
+pub fn default_builtins_enabled() -> bool {
+    true
+}
+
+pub fn default_strict_schema() -> bool {
+    true
+}
+
 pub fn default_max_file_size_mb() -> u64 {
     10
 }
@@ -26,6 +52,14 @@ pub fn is_default_stdin_json(value: &bool) -> bool {
     *value == default_stdin_json()
 }
 
+pub fn default_tool_network() -> bool {
+    true // Default to true for backward compatibility
+}
+
+pub fn is_default_tool_network(value: &bool) -> bool {
+    *value == default_tool_network()
+}
+
 pub fn default_restrict_to_base_dir() -> bool {
     true // Default to true for security
 }
@@ -0,0 +1,90 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Parse `.env` contents: `KEY=value` lines with optional `export ` prefix, `#` comments,
+/// and single- (literal) or double-quoted (`\n`, `\"`, `\\` escapes) values
+pub fn parse_env_file(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=value", index + 1))?;
+        let key = key.trim();
+        let valid_key = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            return Err(format!(
+                "line {}: invalid variable name '{}'",
+                index + 1,
+                key
+            ));
+        }
+
+        vars.push((key.to_string(), parse_value(value.trim(), index + 1)?));
+    }
+
+    Ok(vars)
+}
+
+fn parse_value(value: &str, line: usize) -> Result<String, String> {
+    if let Some(quoted) = value.strip_prefix('\'') {
+        let end = quoted
+            .find('\'')
+            .ok_or_else(|| format!("line {}: unterminated single quote", line))?;
+        return Ok(quoted[..end].to_string());
+    }
+
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut result = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(other) => result.push(other),
+                    None => break,
+                },
+                other => result.push(other),
+            }
+        }
+        return Err(format!("line {}: unterminated double quote", line));
+    }
+
+    // Unquoted: a " #" starts a trailing comment
+    let value = match value.find(" #") {
+        Some(comment) => &value[..comment],
+        None => value,
+    };
+    Ok(value.trim().to_string())
+}
+
+/// Load variables from an env file into the process environment, keeping variables that
+/// are already set. Returns how many variables were applied.
+pub fn load_env_file(path: &Path) -> Result<usize, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read env file {}: {}", path.display(), e))?;
+    let vars = parse_env_file(&contents)
+        .map_err(|e| format!("Invalid env file {}: {}", path.display(), e))?;
+
+    let mut applied = 0;
+    for (key, value) in vars {
+        if env::var_os(&key).is_none() {
+            env::set_var(&key, value);
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
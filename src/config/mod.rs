@@ -1,61 +1,147 @@
 mod api;
 mod defaults;
+mod env_file;
+mod prompt;
 mod reasoning;
+mod routing;
+mod timezone;
 mod tools;
+mod ui;
 mod validation;
 
 use crate::cli::Args;
 use crate::models::Reasoning;
 use anyhow::{Context, Result};
+use chrono::format::{Item, StrftimeItems};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
 pub use api::ApiConfig;
+pub use defaults::{
+    default_max_output_bytes, default_tool_timeout, DEFAULT_API_ENDPOINT, DEFAULT_DATE_FORMAT,
+    DEFAULT_FLUSH_INTERVAL_MS, DEFAULT_MAX_HIGHLIGHT_LINES, DEFAULT_MAX_RETRIES,
+    DEFAULT_MAX_SESSION_FILES, DEFAULT_MODEL, DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+    DEFAULT_POOL_MAX_IDLE_PER_HOST,
+};
+pub use env_file::{load_env_file, parse_env_file};
+pub use prompt::{expand_prompt_variables, prompt_variable};
 pub use reasoning::ReasoningConfig;
-pub use tools::{LocalToolConfig, LocalToolsConfig, TemplateValidation, ToolsConfig};
-pub use validation::{expand_env_var_in_string, expand_env_vars};
+pub use routing::{compile_routes, route_model, ModelRoute, RoutingConfig, RoutingRule};
+pub use timezone::DateTimezone;
+pub use tools::{LocalToolConfig, LocalToolsConfig, OutputFilter, TemplateValidation, ToolsConfig};
+pub use ui::UiConfig;
+pub use validation::{expand_env_var_in_string, expand_env_vars, unresolved_env_vars};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// JSON Schema (draft 7) of the config file, for editor completion and validation.
+/// Hand-maintained: update it with the config structs.
+pub const CONFIG_SCHEMA: &str = include_str!("schema.json");
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct SessionConfig {
     #[serde(default)]
     pub verbose: Option<bool>,
+    /// Save answers to the session with Markdown formatting stripped (default: false)
+    #[serde(default)]
+    pub store_plain: bool,
+    /// Session files kept before the oldest are deleted; 0 keeps them all (default: 0)
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    /// Summarize history instead of dropping it when trimming (default: false)
+    #[serde(default)]
+    pub summarize: bool,
+    /// Model for the history summary (default: the model answering)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_model: Option<String>,
 }
 
-impl Default for SessionConfig {
-    fn default() -> Self {
-        Self { verbose: None }
+impl SessionConfig {
+    pub fn max_files(&self) -> usize {
+        self.max_files.unwrap_or(DEFAULT_MAX_SESSION_FILES)
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ModelConfig {
     #[serde(default)]
     pub default_model: Option<String>,
     #[serde(default)]
     pub system_prompt: Option<String>,
-}
-
-impl Default for ModelConfig {
-    fn default() -> Self {
-        Self {
-            default_model: None,
-            system_prompt: None,
-        }
-    }
+    /// Models to retry with, in order, when the selected model is unavailable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+    /// Prepend "Today's date is ..." to the system prompt (default true)
+    #[serde(default)]
+    pub inject_date: Option<bool>,
+    /// chrono format string for the injected date (default "%A, %B %d, %Y")
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Timezone for the injected date: "local" (default), "utc", an IANA zone name like
+    /// "Europe/Berlin" or an offset like "+09:00"
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Token id -> bias sent as `logit_bias` (provider-dependent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f32>>,
+    /// Repetition penalties (-2.0..2.0), sent only when set
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+    /// Model context size in tokens; longer prompts drop old history or fail before sending
+    #[serde(default)]
+    pub context_window: Option<usize>,
 }
 
 pub struct Config {
     pub api_key: String,
     pub api_endpoint: String,
     pub model: String,
+    /// Message-based model choice; empty when AI_MODEL picks the model
+    pub routes: Vec<ModelRoute>,
+    pub model_fallbacks: Vec<String>,
     pub system_prompt: Option<String>,
+    pub inject_date: bool,
+    pub date_format: String,
+    pub timezone: DateTimezone,
+    pub logit_bias: Option<HashMap<String, f32>>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    /// Token budget checked (by estimate) before each request
+    pub context_window: Option<usize>,
     pub stream_timeout: u64,
+    /// Forced streaming choice (None: stream unless tools are offered)
+    pub stream: Option<bool>,
+    pub pool_idle_timeout_secs: u64,
+    pub pool_max_idle_per_host: usize,
+    pub retry_on_empty: bool,
+    pub max_retries: u32,
+    /// Requests per minute allowed by the client-side rate limiter (None: unlimited)
+    pub rate_limit_rpm: Option<u32>,
     pub verbose: bool,
+    pub store_plain: bool,
+    pub max_session_files: usize,
+    /// Summarize trimmed history (--summarize-history / session.summarize)
+    pub summarize_history: bool,
+    pub summary_model: Option<String>,
     pub reasoning: Option<Reasoning>,
     pub local_tools_config: LocalToolsConfig,
     pub tools_enabled: bool,
+    pub repair_tool_arguments: bool,
+    /// Replaces every tool's timeout_secs for this run
+    pub tool_timeout: Option<u64>,
+    /// --raw-request overrides merged over every request body
+    pub raw_request: Option<serde_json::Value>,
+    pub max_exposed_tools: Option<usize>,
+    /// Byte size above which a tool result is shortened before the follow-up request
+    pub large_tool_result_bytes: Option<usize>,
+    pub ui_config: UiConfig,
+    /// Fixed render width (--width / AI_WIDTH) instead of the detected terminal width
+    pub render_width: Option<usize>,
+    /// Named user-message templates, selected with --prompt
+    pub prompts: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -72,6 +158,13 @@ pub struct JsonConfig {
     pub tools: ToolsConfig,
     #[serde(default)]
     pub local_tools: LocalToolsConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Template name -> text, with `{{input}}` standing for the command
+    #[serde(default)]
+    pub prompts: HashMap<String, String>,
 }
 
 impl Config {
@@ -79,9 +172,15 @@ impl Config {
         // Load JSON configuration first
         let json_config = JsonConfig::load().unwrap_or_default();
 
-        // Get API key (still required from env var for security)
-        let api_key = env::var("OPENROUTER_API_KEY")
-            .map_err(|_| "OPENROUTER_API_KEY environment variable not set")?;
+        // Get API key (still required from env var for security); the listing flags
+        // never make a chat request, so they work without one
+        let api_key = match env::var("OPENROUTER_API_KEY") {
+            Ok(key) => key,
+            Err(_) if args.explain_tools || args.list_tools || args.list_models || args.doctor => {
+                String::new()
+            }
+            Err(_) => return Err("OPENROUTER_API_KEY environment variable not set".to_string()),
+        };
 
         // Get API endpoint: CLI args > env var > JSON config > default
         let api_endpoint = args
@@ -102,19 +201,54 @@ impl Config {
                     format!("{}/v1/chat/completions", endpoint.trim_end_matches('/'))
                 }
             })
-            .unwrap_or_else(|| "https://openrouter.ai/api/v1/chat/completions".to_string());
+            .unwrap_or_else(|| DEFAULT_API_ENDPOINT.to_string());
 
         // Get model: env var > JSON config > default
-        let model = env::var("AI_MODEL")
-            .ok()
+        let env_model = env::var("AI_MODEL").ok();
+        // An explicit AI_MODEL is not second-guessed by the routing rules
+        let routes = match env_model {
+            Some(_) => Vec::new(),
+            None => compile_routes(&json_config.routing)?,
+        };
+        let model = env_model
             .or(json_config.model.default_model.clone())
-            .unwrap_or_else(|| "openai/gpt-5".to_string());
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
 
         // Get system prompt: env var > JSON config
         let system_prompt = env::var("AI_SYSTEM_PROMPT")
             .ok()
             .or(json_config.model.system_prompt.clone());
 
+        // Get date injection: CLI arg (--no-date) > JSON config > default
+        let inject_date = !args.no_date && json_config.model.inject_date.unwrap_or(true);
+        let date_format = json_config
+            .model
+            .date_format
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string());
+        // chrono panics when formatting with an invalid specifier, so reject it up front
+        if StrftimeItems::new(&date_format).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid model.date_format: '{}'", date_format));
+        }
+        let timezone = match &json_config.model.timezone {
+            Some(value) => DateTimezone::parse(value)?,
+            None => DateTimezone::Local,
+        };
+
+        // Penalties: CLI args > env vars > JSON config
+        let frequency_penalty = resolve_penalty(
+            "frequency_penalty",
+            args.frequency_penalty,
+            "AI_FREQUENCY_PENALTY",
+            json_config.model.frequency_penalty,
+        )?;
+        let presence_penalty = resolve_penalty(
+            "presence_penalty",
+            args.presence_penalty,
+            "AI_PRESENCE_PENALTY",
+            json_config.model.presence_penalty,
+        )?;
+
         // Get stream timeout: env var > JSON config > default
         let stream_timeout = env::var("AI_STREAM_TIMEOUT")
             .ok()
@@ -122,6 +256,32 @@ impl Config {
             .or(json_config.api.stream_timeout)
             .unwrap_or(30);
 
+        // Connection pool settings: JSON config > default
+        let pool_idle_timeout_secs = json_config
+            .api
+            .pool_idle_timeout_secs
+            .unwrap_or(DEFAULT_POOL_IDLE_TIMEOUT_SECS);
+        let pool_max_idle_per_host = json_config
+            .api
+            .pool_max_idle_per_host
+            .unwrap_or(DEFAULT_POOL_MAX_IDLE_PER_HOST);
+
+        // Streaming override: CLI args > JSON config > automatic
+        let stream = if args.stream {
+            Some(true)
+        } else if args.no_stream {
+            Some(false)
+        } else {
+            json_config.api.stream
+        };
+
+        // Retry on empty answers: JSON config > default
+        let retry_on_empty = json_config.api.retry_on_empty.unwrap_or(false);
+        let max_retries = json_config.api.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+
+        // Client-side rate limit: JSON config only, 0 means unlimited
+        let rate_limit_rpm = json_config.api.rate_limit_rpm.filter(|rpm| *rpm > 0);
+
         // Get verbose flag: env var > JSON config > default
         let verbose = env::var("AI_VERBOSE")
             .ok()
@@ -141,9 +301,75 @@ impl Config {
             }
         };
 
+        // Get tool timeout override: CLI arg > env var (no file setting; per-tool
+        // timeout_secs and local_tools.default_timeout_secs cover that)
+        let tool_timeout = match args.tool_timeout {
+            Some(secs) => Some(secs),
+            None => match env::var("AI_TOOL_TIMEOUT") {
+                Ok(v) => Some(v.parse::<u64>().map_err(|_| {
+                    format!("Invalid AI_TOOL_TIMEOUT: '{}' is not a number of seconds", v)
+                })?),
+                Err(_) => None,
+            },
+        };
+        if tool_timeout == Some(0) {
+            return Err("Tool timeout must be at least 1 second".to_string());
+        }
+
+        // Render width: CLI arg > env var > terminal detection
+        let render_width = match args.width {
+            Some(width) => Some(width as usize),
+            None => match env::var("AI_WIDTH") {
+                Ok(v) => match v.parse::<usize>() {
+                    Ok(width) if width > 0 => Some(width),
+                    _ => {
+                        return Err(format!(
+                            "Invalid AI_WIDTH: '{}' is not a positive number of columns",
+                            v
+                        ))
+                    }
+                },
+                Err(_) => None,
+            },
+        };
+
+        // Request body overrides from --raw-request: a JSON object
+        let raw_request = match args.raw_request {
+            Some(ref path) => {
+                let contents = fs::read_to_string(path).map_err(|e| {
+                    format!("Failed to read raw request file {}: {}", path.display(), e)
+                })?;
+                let value: serde_json::Value = serde_json::from_str(&contents)
+                    .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+                let Some(fields) = value.as_object() else {
+                    return Err(format!(
+                        "Raw request file {} must contain a JSON object",
+                        path.display()
+                    ));
+                };
+                // The response parser has to know whether the answer streams
+                if fields.contains_key("stream") {
+                    return Err(format!(
+                        "Raw request file {} sets \"stream\"; use --stream / --no-stream instead",
+                        path.display()
+                    ));
+                }
+                Some(value)
+            }
+            None => None,
+        };
+
+        let repair_tool_arguments = json_config.tools.repair_arguments;
+        let max_exposed_tools = json_config.tools.max_exposed;
+        let large_tool_result_bytes =
+            json_config.tools.summarize_large_results.filter(|&b| b > 0);
+
         // Get local_tools config
         let local_tools_config = json_config.local_tools;
 
+        // Get UI config
+        let ui_config = json_config.ui;
+
         // Build reasoning configuration from CLI args, env vars, and JSON config
         let reasoning = Self::build_reasoning_config(args, &json_config.reasoning);
 
@@ -151,12 +377,39 @@ impl Config {
             api_key,
             api_endpoint,
             model,
+            routes,
+            model_fallbacks: json_config.model.fallbacks.clone(),
             system_prompt,
+            inject_date,
+            date_format,
+            timezone,
+            logit_bias: json_config.model.logit_bias.clone(),
+            frequency_penalty,
+            presence_penalty,
+            context_window: json_config.model.context_window.filter(|tokens| *tokens > 0),
             stream_timeout,
+            stream,
+            pool_idle_timeout_secs,
+            pool_max_idle_per_host,
+            retry_on_empty,
+            max_retries,
+            rate_limit_rpm,
             verbose,
+            store_plain: json_config.session.store_plain,
+            summarize_history: args.summarize_history || json_config.session.summarize,
+            summary_model: json_config.session.summary_model.clone(),
+            max_session_files: json_config.session.max_files(),
             reasoning,
             local_tools_config,
             tools_enabled,
+            repair_tool_arguments,
+            tool_timeout,
+            raw_request,
+            max_exposed_tools,
+            large_tool_result_bytes,
+            ui_config,
+            render_width,
+            prompts: json_config.prompts,
         })
     }
 
@@ -232,8 +485,78 @@ impl Config {
         }
     }
 
-    pub fn get_current_date() -> String {
-        chrono::Local::now().format("%A, %B %d, %Y").to_string()
+    pub fn get_current_date(&self) -> String {
+        self.timezone.format_now(&self.date_format)
+    }
+
+    /// The configured system prompt with `{{cwd}}`, `{{os}}`, `{{date}}` and `{{git_branch}}`
+    /// expanded
+    pub fn expanded_system_prompt(&self) -> Option<String> {
+        let prompt = self.system_prompt.as_deref()?;
+        if !prompt.contains("{{") {
+            return Some(prompt.to_string());
+        }
+        let date = self.get_current_date();
+        Some(expand_prompt_variables(prompt, |key| prompt_variable(key, &date)))
+    }
+
+    /// The user message for `--prompt name`: the template with `{{input}}` replaced by the
+    /// command (and the built-in prompt variables expanded). A template without `{{input}}`
+    /// gets the command appended after a blank line.
+    pub fn render_prompt_template(&self, name: &str, input: &str) -> Result<String, String> {
+        let Some(template) = self.prompts.get(name) else {
+            let mut names: Vec<&str> = self.prompts.keys().map(String::as_str).collect();
+            names.sort();
+            return Err(if names.is_empty() {
+                format!("Unknown prompt '{}': no prompts are configured", name)
+            } else {
+                format!(
+                    "Unknown prompt '{}'. Available prompts: {}",
+                    name,
+                    names.join(", ")
+                )
+            });
+        };
+
+        let template = if template.contains("{{input}}") {
+            template.clone()
+        } else {
+            format!("{}\n\n{{{{input}}}}", template.trim_end())
+        };
+        let date = self.get_current_date();
+        Ok(expand_prompt_variables(&template, |key| match key {
+            "input" => Some(input.to_string()),
+            _ => prompt_variable(key, &date),
+        }))
+    }
+
+    /// The date line prepended to the system prompt, or None when date injection is off
+    pub fn date_prompt(&self) -> Option<String> {
+        self.inject_date
+            .then(|| format!("Today's date is {}.", self.get_current_date()))
+    }
+}
+
+/// Pick a penalty from CLI > env > file and check the OpenAI-compatible -2.0..2.0 range
+fn resolve_penalty(
+    name: &str,
+    cli: Option<f32>,
+    env_var: &str,
+    file: Option<f32>,
+) -> Result<Option<f32>, String> {
+    let env_value = match env::var(env_var) {
+        Ok(v) => Some(
+            v.parse::<f32>()
+                .map_err(|_| format!("Invalid {}: '{}' is not a number", env_var, v))?,
+        ),
+        Err(_) => None,
+    };
+    match cli.or(env_value).or(file) {
+        Some(value) if !(-2.0..=2.0).contains(&value) => Err(format!(
+            "Invalid {}: {} (must be between -2.0 and 2.0)",
+            name, value
+        )),
+        value => Ok(value),
     }
 }
 
@@ -0,0 +1,41 @@
+use regex::Regex;
+use std::env;
+use std::process::Command;
+
+/// Expand `{{key}}` placeholders in a system prompt using `lookup`
+/// Unknown keys are left untouched so literal braces in prompts survive.
+pub fn expand_prompt_variables(prompt: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let re = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
+    re.replace_all(prompt, |caps: &regex::Captures| {
+        lookup(caps[1].trim()).unwrap_or_else(|| caps[0].to_string())
+    })
+    .into_owned()
+}
+
+/// Values for the built-in prompt variables: cwd, os, date and git_branch
+/// git_branch expands to an empty string outside a git repository.
+pub fn prompt_variable(key: &str, date: &str) -> Option<String> {
+    match key {
+        "cwd" => Some(
+            env::current_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default(),
+        ),
+        "os" => Some(env::consts::OS.to_string()),
+        "date" => Some(date.to_string()),
+        "git_branch" => Some(current_git_branch().unwrap_or_default()),
+        _ => None,
+    }
+}
+
+fn current_git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ReasoningConfig {
     #[serde(default)]
     pub enabled: Option<bool>,
@@ -12,14 +12,3 @@ pub struct ReasoningConfig {
     pub exclude: Option<bool>,
 }
 
-impl Default for ReasoningConfig {
-    fn default() -> Self {
-        Self {
-            enabled: None,
-            effort: None,
-            max_tokens: None,
-            exclude: None,
-        }
-    }
-}
-
@@ -0,0 +1,95 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Pick the model from the user's message; the first matching rule wins
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoutingRule {
+    /// Words that select this rule, matched as whole words ignoring case
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Regular expression tried against the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub model: String,
+}
+
+/// A routing rule with its keywords and pattern compiled
+#[derive(Debug, Clone)]
+pub struct ModelRoute {
+    pub model: String,
+    matchers: Vec<Regex>,
+}
+
+impl ModelRoute {
+    pub fn matches(&self, message: &str) -> bool {
+        self.matchers
+            .iter()
+            .any(|matcher| matcher.is_match(message))
+    }
+}
+
+/// Compile the `routing.rules`, naming the rule (from 1) that is incomplete or has a bad pattern
+pub fn compile_routes(config: &RoutingConfig) -> Result<Vec<ModelRoute>, String> {
+    config
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let number = index + 1;
+            if rule.model.trim().is_empty() {
+                return Err(format!("routing rule {} has no model", number));
+            }
+
+            let mut matchers = Vec::new();
+            let keywords: Vec<String> = rule
+                .keywords
+                .iter()
+                .map(|keyword| keyword.trim())
+                .filter(|keyword| !keyword.is_empty())
+                .map(regex::escape)
+                .collect();
+            if !keywords.is_empty() {
+                let keywords = format!(r"(?i)\b(?:{})\b", keywords.join("|"));
+                matchers
+                    .push(Regex::new(&keywords).map_err(|e| {
+                        format!("routing rule {}: invalid keywords: {}", number, e)
+                    })?);
+            }
+            if let Some(ref pattern) = rule.pattern {
+                matchers.push(Regex::new(pattern).map_err(|e| {
+                    format!(
+                        "routing rule {}: invalid pattern '{}': {}",
+                        number, pattern, e
+                    )
+                })?);
+            }
+            if matchers.is_empty() {
+                return Err(format!(
+                    "routing rule {} has no keywords or pattern",
+                    number
+                ));
+            }
+
+            Ok(ModelRoute {
+                model: rule.model.trim().to_string(),
+                matchers,
+            })
+        })
+        .collect()
+}
+
+/// The first route matching `message`, with its position (from 1) in `routing.rules`
+pub fn route_model<'a>(routes: &'a [ModelRoute], message: &str) -> Option<(usize, &'a str)> {
+    routes
+        .iter()
+        .enumerate()
+        .find(|(_, route)| route.matches(message))
+        .map(|(index, route)| (index + 1, route.model.as_str()))
+}
@@ -0,0 +1,80 @@
+use chrono::{FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+
+/// Timezone used for the date injected into the system prompt (`model.timezone`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateTimezone {
+    /// The machine's local timezone (default)
+    #[default]
+    Local,
+    Utc,
+    /// A fixed UTC offset such as `+09:00` or `-05:30`
+    Fixed(FixedOffset),
+    /// An IANA zone such as `Europe/Berlin`, following its daylight saving rules
+    Named(Tz),
+}
+
+impl DateTimezone {
+    /// Parse `"local"`, `"utc"`, an IANA name (`Europe/Berlin`) or a fixed offset
+    /// (`+09:00`, `UTC-5`, `+0530`)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "local" | "" => return Ok(Self::Local),
+            "utc" | "gmt" | "z" => return Ok(Self::Utc),
+            _ => {}
+        }
+
+        if let Ok(zone) = value.parse::<Tz>() {
+            return Ok(Self::Named(zone));
+        }
+
+        let offset = match value.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("utc") => &value[3..],
+            _ => value,
+        };
+        parse_offset(offset).map(Self::Fixed).ok_or_else(|| {
+            format!(
+                "Invalid model.timezone '{}': use \"local\", \"utc\", a zone like \
+                 \"Europe/Berlin\" or an offset like \"+09:00\"",
+                value
+            )
+        })
+    }
+
+    /// Format the current time in this timezone
+    pub fn format_now(&self, format: &str) -> String {
+        match self {
+            Self::Local => Local::now().format(format).to_string(),
+            Self::Utc => Utc::now().format(format).to_string(),
+            Self::Fixed(offset) => Utc::now().with_timezone(offset).format(format).to_string(),
+            Self::Named(zone) => Utc::now().with_timezone(zone).format(format).to_string(),
+        }
+    }
+}
+
+/// Parse `+HH`, `+HH:MM` or `+HHMM` (sign required)
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match offset.chars().next()? {
+        '+' => (1, &offset[1..]),
+        '-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = if digits.len() <= 2 {
+        (digits.parse::<i32>().ok()?, 0)
+    } else {
+        let split = digits.len() - 2;
+        (
+            digits[..split].parse::<i32>().ok()?,
+            digits[split..].parse::<i32>().ok()?,
+        )
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
@@ -2,22 +2,36 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::config::defaults::{
-    default_allow_absolute, default_local_tools_enabled, default_max_file_size_mb,
-    default_max_output_bytes, default_restrict_to_base_dir, default_tool_timeout,
-    default_tools_enabled, default_validation_kind, is_default_allow_absolute,
+    default_allow_absolute, default_builtins_enabled, default_local_tools_enabled,
+    default_max_file_size_mb, default_max_output_bytes, default_restrict_to_base_dir,
+    default_strict_schema, default_tool_timeout, default_tools_enabled, default_validation_kind, is_default_allow_absolute,
     is_default_restrict_to_base_dir, is_default_stdin_json, default_stdin_json,
+    default_tool_network, is_default_tool_network,
 };
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ToolsConfig {
     #[serde(default = "default_tools_enabled")]
     pub enabled: bool,
+    /// Try to fix malformed JSON in tool-call arguments before reporting a parse error
+    #[serde(default)]
+    pub repair_arguments: bool,
+    /// Cap on how many tool definitions are sent to the model (unset = no cap)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_exposed: Option<usize>,
+    /// Tool results over this many bytes are cut to their beginning and end before they
+    /// go back to the model (unset = sent whole)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summarize_large_results: Option<usize>,
 }
 
 impl Default for ToolsConfig {
     fn default() -> Self {
         Self {
             enabled: default_tools_enabled(),
+            repair_arguments: false,
+            max_exposed: None,
+            summarize_large_results: None,
         }
     }
 }
@@ -28,8 +42,27 @@ pub struct LocalToolsConfig {
     pub enabled: bool,
     #[serde(default)]
     pub base_dir: Option<String>,
+    /// Register built-in tools such as read_file (false = only the tools defined below)
+    #[serde(default = "default_builtins_enabled")]
+    pub builtins_enabled: bool,
     #[serde(default = "default_max_file_size_mb")]
     pub max_file_size_mb: u64,
+    /// Reject tool arguments not declared in the schema (false = ignore unknown properties)
+    #[serde(default = "default_strict_schema")]
+    pub strict_schema: bool,
+    /// Where inline scripts are written before running (default: system temp dir)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<String>,
+    /// Treat `${VAR}` references to unset variables in tool env/args as errors
+    /// (false = pass them through literally, with a warning in verbose mode)
+    #[serde(default)]
+    pub strict_env: bool,
+    /// Timeout for tools that don't set `timeout_secs`
+    #[serde(default = "default_tool_timeout")]
+    pub default_timeout_secs: u64,
+    /// Output limit for tools that don't set `max_output_bytes`
+    #[serde(default = "default_max_output_bytes")]
+    pub default_max_output_bytes: u64,
     #[serde(default)]
     pub tools: Vec<LocalToolConfig>,
 }
@@ -39,7 +72,13 @@ impl Default for LocalToolsConfig {
         Self {
             enabled: default_local_tools_enabled(),
             base_dir: None,
+            builtins_enabled: default_builtins_enabled(),
             max_file_size_mb: default_max_file_size_mb(),
+            strict_schema: default_strict_schema(),
+            temp_dir: None,
+            strict_env: false,
+            default_timeout_secs: default_tool_timeout(),
+            default_max_output_bytes: default_max_output_bytes(),
             tools: Vec::new(),
         }
     }
@@ -66,6 +105,11 @@ pub struct LocalToolConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub input_schema: Option<serde_json::Value>,
 
+    // Example argument objects shown to the model (must conform to input_schema)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<serde_json::Value>,
+
     // Script-specific fields
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -87,12 +131,25 @@ pub struct LocalToolConfig {
     #[serde(default)]
     pub args: Vec<String>,
 
-    // Common optional settings
-    #[serde(default = "default_tool_timeout")]
-    pub timeout_secs: u64,
+    // Argument keys appended as positional args, in this order, after `args`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args_from: Option<Vec<String>>,
 
-    #[serde(default = "default_max_output_bytes")]
-    pub max_output_bytes: u64,
+    // Boolean argument keys mapped to flags, included only when the argument is true
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub flag_args: HashMap<String, String>,
+
+    // Common optional settings; unset limits use local_tools.default_timeout_secs and
+    // local_tools.default_max_output_bytes
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -107,6 +164,20 @@ pub struct LocalToolConfig {
     #[serde(skip_serializing_if = "is_default_stdin_json")]
     pub stdin_json: bool,
 
+    // Whether command/script tools may use the network; false runs them in a new
+    // network namespace (Linux only; elsewhere a warning is printed and the tool still runs)
+    #[serde(default = "default_tool_network")]
+    #[serde(skip_serializing_if = "is_default_tool_network")]
+    pub network: bool,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>, // Max simultaneous calls of this tool (default: unlimited)
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_filter: Option<OutputFilter>, // Trim stdout before it goes back to the model
+
     // Security settings for command tools with templated arguments
     #[serde(default = "default_restrict_to_base_dir")]
     #[serde(skip_serializing_if = "is_default_restrict_to_base_dir")]
@@ -121,6 +192,20 @@ pub struct LocalToolConfig {
     pub template_validations: Option<HashMap<String, TemplateValidation>>,
 }
 
+/// Extraction applied to a tool's stdout; set exactly one of the two fields
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct OutputFilter {
+    /// jq-style path into JSON output, e.g. `.items[].name` or `.data["total count"]`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+
+    /// Regex applied to the output; every match (or its first capture group) is kept
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TemplateValidation {
     #[serde(default = "default_validation_kind")]
@@ -137,5 +222,9 @@ pub struct TemplateValidation {
     #[serde(default = "default_allow_absolute")]
     #[serde(skip_serializing_if = "is_default_allow_absolute")]
     pub allow_absolute: bool, // Allow absolute paths (only for path kind)
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub expand_arrays: bool, // Pass each array element as its own argv entry
 }
 
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UiConfig {
+    /// Use ASCII borders instead of box-drawing characters (None = auto-detect from locale)
+    #[serde(default)]
+    pub ascii_boxes: Option<bool>,
+    /// Keep short code block borders when stdout is not a terminal (default: omit them)
+    #[serde(default)]
+    pub piped_borders: bool,
+    /// Prefix highlighted code lines with line numbers
+    #[serde(default)]
+    pub code_line_numbers: bool,
+    /// Make citation titles clickable OSC-8 links (None = auto-detect from the terminal)
+    #[serde(default)]
+    pub hyperlinks: Option<bool>,
+    /// Line printed before each answer; `{{model}}` and `{{time}}` are filled in (default: none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer_header: Option<String>,
+    /// Lines of a code block highlighted before the rest is shown plain (default: 2000,
+    /// 0 = no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_highlight_lines: Option<usize>,
+    /// Milliseconds between terminal flushes while streaming (default: 50)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_interval_ms: Option<u64>,
+}
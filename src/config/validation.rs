@@ -1,29 +1,59 @@
 use std::collections::HashMap;
 use std::env;
 
-/// Expand environment variables in a string using ${VAR_NAME} syntax
-pub fn expand_env_var_in_string(value: &str) -> String {
-    let mut result = value.to_string();
-    let re = regex::Regex::new(r"\$\{([^}]+)\}").unwrap();
+/// Expand environment variables in a string using shell-style syntax:
+/// `${VAR}` (left as-is when unset), `${VAR:-default}` (default when unset or empty) and
+/// `${VAR:?message}` (error with the message when unset or empty)
+pub fn expand_env_var_in_string(value: &str) -> Result<String, String> {
+    let re = regex::Regex::new(r"\$\{([^}:]+)(?:(:-|:\?)([^}]*))?\}").unwrap();
+    let mut result = String::with_capacity(value.len());
+    let mut last = 0;
 
     for cap in re.captures_iter(value) {
+        let whole = cap.get(0).unwrap();
+        result.push_str(&value[last..whole.start()]);
+        last = whole.end();
+
         let var_name = &cap[1];
-        let replacement = env::var(var_name).unwrap_or_else(|_| format!("${{{}}}", var_name));
-        result = result.replace(&cap[0], &replacement);
+        let set_value = env::var(var_name).ok();
+        let non_empty = set_value.clone().filter(|v| !v.is_empty());
+        let operand = cap.get(3).map_or("", |m| m.as_str());
+
+        let replacement = match cap.get(2).map(|m| m.as_str()) {
+            Some(":-") => non_empty.unwrap_or_else(|| operand.to_string()),
+            Some(_) => non_empty.ok_or_else(|| {
+                if operand.is_empty() {
+                    format!("Environment variable '{}' is required but not set", var_name)
+                } else {
+                    format!("Environment variable '{}' is required: {}", var_name, operand)
+                }
+            })?,
+            None => set_value.unwrap_or_else(|| whole.as_str().to_string()),
+        };
+        result.push_str(&replacement);
     }
 
-    result
+    result.push_str(&value[last..]);
+    Ok(result)
+}
+
+/// Names referenced as plain `${VAR}` whose variable is unset (and would be left as-is)
+pub fn unresolved_env_vars(value: &str) -> Vec<String> {
+    let re = regex::Regex::new(r"\$\{([^}:]+)\}").unwrap();
+    re.captures_iter(value)
+        .map(|cap| cap[1].to_string())
+        .filter(|name| env::var_os(name).is_none())
+        .collect()
 }
 
 /// Expand environment variables in a HashMap
-pub fn expand_env_vars(env: &HashMap<String, String>) -> HashMap<String, String> {
+pub fn expand_env_vars(env: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
     let mut expanded = HashMap::new();
 
     for (key, value) in env {
-        let expanded_value = expand_env_var_in_string(value);
+        let expanded_value = expand_env_var_in_string(value)?;
         expanded.insert(key.clone(), expanded_value);
     }
 
-    expanded
+    Ok(expanded)
 }
-
@@ -0,0 +1,224 @@
+use crate::api::{build_client, check_endpoint};
+use crate::cli::Args;
+use crate::config::{Config, JsonConfig, LocalToolsConfig};
+use crate::local_tools::locate_program;
+use crate::models::Message;
+use colored::*;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Budget for the endpoint reachability check
+const DOCTOR_ENDPOINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One line of the `--doctor` checklist
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    /// How to fix a failed check
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run every check. Nothing is billed unless `full` is set, which sends one tiny request.
+pub async fn run_doctor(args: &Args, full: bool) -> Vec<Check> {
+    let mut checks = vec![check_api_key(), check_config_file(), check_cache_dir()];
+
+    let config = match Config::from_env_and_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            checks.push(Check::fail(
+                "Settings",
+                e,
+                "Fix the value named above in the config file, environment or flags",
+            ));
+            return checks;
+        }
+    };
+
+    checks.push(check_endpoint_reachable(&config).await);
+    checks.extend(check_tools(&config.local_tools_config));
+    if full {
+        checks.push(check_completion(config).await);
+    }
+    checks
+}
+
+fn check_api_key() -> Check {
+    match env::var("OPENROUTER_API_KEY") {
+        Ok(key) if !key.trim().is_empty() => Check::pass("API key", "OPENROUTER_API_KEY is set"),
+        _ => Check::fail(
+            "API key",
+            "OPENROUTER_API_KEY is not set",
+            "export OPENROUTER_API_KEY=... in your shell profile, or put it in a .env file \
+             loaded with --env-file",
+        ),
+    }
+}
+
+fn check_config_file() -> Check {
+    let Some(path) = JsonConfig::get_config_paths()
+        .into_iter()
+        .find(|path| path.exists())
+    else {
+        return Check::pass("Config file", "none found, using defaults");
+    };
+
+    match JsonConfig::load() {
+        Ok(_) => Check::pass("Config file", path.display().to_string()),
+        Err(e) => Check::fail(
+            "Config file",
+            format!("{:#}", e),
+            "Fix the file, or compare it with one generated by --config-init",
+        ),
+    }
+}
+
+fn check_cache_dir() -> Check {
+    let Some(home) = dirs::home_dir() else {
+        return Check::fail(
+            "Session cache",
+            "home directory not found",
+            "Set HOME so sessions can be saved under ~/.cache/cmd2ai",
+        );
+    };
+    let cache_dir: PathBuf = home.join(".cache").join("cmd2ai");
+    let probe = cache_dir.join(".doctor-write-test");
+
+    let writable = fs::create_dir_all(&cache_dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe));
+    match writable {
+        Ok(()) => Check::pass("Session cache", cache_dir.display().to_string()),
+        Err(e) => Check::fail(
+            "Session cache",
+            format!("{} is not writable: {}", cache_dir.display(), e),
+            format!("Check the permissions of {}", cache_dir.display()),
+        ),
+    }
+}
+
+async fn check_endpoint_reachable(config: &Config) -> Check {
+    let client = match build_client(config) {
+        Ok(client) => client,
+        Err(e) => {
+            return Check::fail(
+                "Endpoint",
+                e.to_string(),
+                "Check the api: section of the config",
+            )
+        }
+    };
+
+    match check_endpoint(
+        &client,
+        &config.api_endpoint,
+        DOCTOR_ENDPOINT_TIMEOUT,
+        false,
+    )
+    .await
+    {
+        Ok(()) => Check::pass("Endpoint", format!("{} is reachable", config.api_endpoint)),
+        Err(e) => Check::fail(
+            "Endpoint",
+            e.to_string(),
+            "Check your network, or set api.endpoint / AI_API_ENDPOINT / --api-endpoint",
+        ),
+    }
+}
+
+/// The interpreter or command of each enabled custom tool must be on PATH
+pub fn check_tools(config: &LocalToolsConfig) -> Vec<Check> {
+    if !config.enabled {
+        return Vec::new();
+    }
+
+    config
+        .tools
+        .iter()
+        .filter(|tool| tool.enabled)
+        .filter_map(|tool| {
+            let (kind, program) = match (&tool.command, &tool.interpreter) {
+                (Some(command), _) => ("command", command),
+                (None, Some(interpreter)) => ("interpreter", interpreter),
+                // Settings for a built-in tool
+                (None, None) => return None,
+            };
+            let name = format!("Tool '{}'", tool.name);
+            Some(match locate_program(program) {
+                Some(path) => Check::pass(&name, format!("{} {}", kind, path.display())),
+                None => Check::fail(
+                    &name,
+                    format!("{} '{}' not found on PATH", kind, program),
+                    format!(
+                        "Install {} or set the tool's {} to a full path",
+                        program, kind
+                    ),
+                ),
+            })
+        })
+        .collect()
+}
+
+/// `--full`: one short, billed request to prove the key and model work end to end
+async fn check_completion(mut config: Config) -> Check {
+    let model = config.model.clone();
+    config.tools_enabled = false;
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: Some("Reply with the single word OK.".to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    }];
+
+    match crate::query(config, messages).await {
+        Ok(outcome) if !outcome.content.trim().is_empty() => {
+            Check::pass("Completion", format!("{} answered", model))
+        }
+        Ok(_) => Check::fail(
+            "Completion",
+            format!("{} returned an empty answer", model),
+            "Try another model with --model or AI_MODEL",
+        ),
+        Err(e) => Check::fail(
+            "Completion",
+            e.to_string(),
+            "Check the API key, your credit and the model id (see --models)",
+        ),
+    }
+}
+
+/// Checklist line, with the hint indented below a failure
+pub fn format_check(check: &Check) -> String {
+    if check.passed {
+        format!("{} {}: {}", "✓".green(), check.name, check.detail)
+    } else {
+        let mut line = format!("{} {}: {}", "✗".red(), check.name, check.detail);
+        if let Some(ref hint) = check.hint {
+            line.push_str(&format!("\n    {}", hint.dimmed()));
+        }
+        line
+    }
+}
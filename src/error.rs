@@ -6,14 +6,17 @@ pub enum Cmd2AiError {
         status: u16,
         message: String,
     },
-    #[allow(dead_code)]
     ConfigError(String),
-    #[allow(dead_code)]
     ToolError(String),
-    #[allow(dead_code)]
     SessionError(String),
     NetworkError(reqwest::Error),
     Timeout,
+    /// Ctrl-C during a request; carries whatever answer text had streamed in
+    Interrupted {
+        partial: String,
+    },
+    /// The model declined to answer; carries its refusal message
+    Refused(String),
     IoError(std::io::Error),
     JsonError(serde_json::Error),
     YamlError(serde_yaml::Error),
@@ -31,6 +34,8 @@ impl fmt::Display for Cmd2AiError {
             Cmd2AiError::SessionError(msg) => write!(f, "Session error: {}", msg),
             Cmd2AiError::NetworkError(e) => write!(f, "Network error: {}", e),
             Cmd2AiError::Timeout => write!(f, "Request timeout"),
+            Cmd2AiError::Interrupted { .. } => write!(f, "Interrupted"),
+            Cmd2AiError::Refused(msg) => write!(f, "Model refused the request: {}", msg),
             Cmd2AiError::IoError(e) => write!(f, "IO error: {}", e),
             Cmd2AiError::JsonError(e) => write!(f, "JSON error: {}", e),
             Cmd2AiError::YamlError(e) => write!(f, "YAML error: {}", e),
@@ -39,6 +44,33 @@ impl fmt::Display for Cmd2AiError {
     }
 }
 
+impl Cmd2AiError {
+    /// Whether an API error means the requested model doesn't exist or can't serve requests,
+    /// so retrying with a fallback model may succeed
+    pub fn is_model_unavailable(&self) -> bool {
+        let Cmd2AiError::ApiError { status, message } = self else {
+            return false;
+        };
+        if *status == 404 {
+            return true;
+        }
+
+        let message = message.to_lowercase();
+        let model_related = message.contains("model");
+        let unavailable = [
+            "not found",
+            "not a valid model",
+            "no such model",
+            "does not exist",
+            "unavailable",
+            "no endpoints",
+        ]
+        .iter()
+        .any(|phrase| message.contains(phrase));
+        matches!(status, 400 | 503) && model_related && unavailable
+    }
+}
+
 impl std::error::Error for Cmd2AiError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -1,9 +1,30 @@
 pub mod api;
+pub mod batch;
 pub mod cli;
 pub mod config;
+pub mod doctor;
 pub mod error;
 pub mod local_tools;
 pub mod models;
+pub mod orchestrator;
 pub mod session;
 pub mod ui;
+pub mod util;
 
+pub use orchestrator::QueryOutcome;
+
+use crate::config::Config;
+use crate::models::Message;
+use crate::orchestrator::OrchestratorContext;
+
+/// Send `messages` to the configured model and return the answer without rendering
+/// anything to stdout, for embedding cmd2ai in other programs.
+///
+/// Local tools run when enabled in `config`; warnings still go to stderr.
+pub async fn query(config: Config, mut messages: Vec<Message>) -> error::Result<QueryOutcome> {
+    let context = OrchestratorContext {
+        quiet: true,
+        ..OrchestratorContext::new(config, cli::Args::default())?
+    };
+    orchestrator::run(&context, &mut messages).await
+}
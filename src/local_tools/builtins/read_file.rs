@@ -23,8 +23,14 @@ pub fn handle_read_file(args: &Value, settings: &LocalSettings) -> Result<String
         );
     }
 
-    // Safely resolve the path
-    let resolved_path = safe_resolve_path(path_str, &settings.base_dir)?;
+    // Safely resolve the path (canonicalization fails for missing files, so report those plainly)
+    let resolved_path = safe_resolve_path(path_str, &settings.base_dir).map_err(|e| {
+        if settings.base_dir.join(path_str).exists() {
+            e
+        } else {
+            format!("File not found: {}", path_str)
+        }
+    })?;
 
     if settings.verbose {
         eprintln!(
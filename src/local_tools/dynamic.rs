@@ -1,10 +1,106 @@
 use crate::config::LocalToolConfig;
-use serde_json::Value;
+use jsonschema::{Draft, JSONSchema};
+use regex::Regex;
+use serde_json::{json, Value};
 use std::future::Future;
 use std::pin::Pin;
 
-use super::executor::execute_dynamic_tool;
-use super::registry::{LocalSettings, LocalTool};
+use super::executor::{execute_dynamic_tool, get_validation_policy};
+use super::output_filter::validate_output_filter;
+use super::registry::{LocalSettings, LocalTool, ToolHandler, ToolSource};
+
+/// Collect the unique `{{key}}` placeholder names used in command args, in order of appearance
+pub fn template_placeholders(args: &[String]) -> Vec<String> {
+    let re = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
+    let mut keys: Vec<String> = Vec::new();
+    for arg in args {
+        for cap in re.captures_iter(arg) {
+            let key = cap[1].to_string();
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+/// Build an input schema from the `{{key}}` placeholders in a tool's args
+/// Every placeholder becomes a required property, `args_from` keys optional ones and
+/// `flag_args` keys optional booleans; path-like keys (by template_validations or the
+/// `*path*` naming heuristic) are described as base_dir-relative paths
+pub fn derive_input_schema(tool_config: &LocalToolConfig) -> Value {
+    let mut properties = serde_json::Map::new();
+    let keys = template_placeholders(&tool_config.args);
+    let mut all_keys = keys.clone();
+    for key in tool_config.args_from.iter().flatten() {
+        if !all_keys.contains(key) {
+            all_keys.push(key.clone());
+        }
+    }
+
+    for key in &all_keys {
+        let validation = get_validation_policy(key, tool_config);
+        let mut property = match validation.kind.as_str() {
+            "path" => json!({
+                "type": "string",
+                "description": format!("Path for '{}' (relative to base directory)", key)
+            }),
+            "number" => json!({ "type": "number" }),
+            _ => json!({ "type": "string" }),
+        };
+        if validation.expand_arrays {
+            property = json!({ "type": "array", "items": property });
+        }
+        properties.insert(key.clone(), property);
+    }
+
+    let mut flag_keys: Vec<&String> = tool_config.flag_args.keys().collect();
+    flag_keys.sort();
+    for key in flag_keys {
+        properties
+            .entry(key.clone())
+            .or_insert_with(|| json!({ "type": "boolean" }));
+    }
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": keys,
+        "additionalProperties": false
+    })
+}
+
+/// Check that every configured example is a valid argument object for the tool's schema
+fn validate_examples(
+    tool_name: &str,
+    input_schema: &Value,
+    examples: &[Value],
+) -> Result<(), String> {
+    if examples.is_empty() {
+        return Ok(());
+    }
+
+    let schema = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(input_schema)
+        .map_err(|e| format!("Tool '{}' has an invalid input_schema: {}", tool_name, e))?;
+
+    for (index, example) in examples.iter().enumerate() {
+        if let Err(errors) = schema.validate(example) {
+            let messages: Vec<String> = errors
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+            return Err(format!(
+                "Tool '{}' example #{} does not match input_schema: {}",
+                tool_name,
+                index + 1,
+                messages.join("; ")
+            ));
+        }
+    }
+
+    Ok(())
+}
 
 /// Convert a LocalToolConfig with type field into a LocalTool
 pub fn create_dynamic_tool(
@@ -29,12 +125,14 @@ pub fn create_dynamic_tool(
         .clone()
         .ok_or_else(|| format!("Tool '{}' is missing 'description' field", tool_config.name))?;
 
-    let input_schema = tool_config.input_schema.clone().ok_or_else(|| {
-        format!(
-            "Tool '{}' is missing 'input_schema' field",
-            tool_config.name
-        )
-    })?;
+    // An explicit schema always wins; otherwise derive one from the templated args
+    // (tools without placeholders take no input and get an empty object schema)
+    let input_schema = tool_config
+        .input_schema
+        .clone()
+        .unwrap_or_else(|| derive_input_schema(tool_config));
+
+    validate_examples(&tool_config.name, &input_schema, &tool_config.examples)?;
 
     // Validate schema-specific requirements
     if tool_type == "script" {
@@ -50,28 +148,29 @@ pub fn create_dynamic_tool(
                 tool_config.name
             ));
         }
-    } else if tool_type == "command" {
-        if tool_config.command.is_none() {
-            return Err(format!(
-                "Tool '{}' (type: command) requires 'command' field",
-                tool_config.name
-            ));
-        }
+    } else if tool_type == "command" && tool_config.command.is_none() {
+        return Err(format!(
+            "Tool '{}' (type: command) requires 'command' field",
+            tool_config.name
+        ));
+    }
+
+    if tool_config.max_concurrency == Some(0) {
+        return Err(format!(
+            "Tool '{}' has max_concurrency 0 (must be at least 1, or omitted for no limit)",
+            tool_config.name
+        ));
+    }
+
+    if let Some(ref filter) = tool_config.output_filter {
+        validate_output_filter(filter).map_err(|e| format!("Tool '{}': {}", tool_config.name, e))?;
     }
 
     // Create a handler that calls the executor
     let tool_config_clone = tool_config.clone();
     let settings_clone = settings.clone();
 
-    let handler: Box<
-        dyn for<'a> Fn(
-                &'a Value,
-                &'a LocalSettings,
-            )
-                -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>
-            + Send
-            + Sync,
-    > = Box::new(
+    let handler: ToolHandler = Box::new(
         move |args: &Value,
               _settings: &LocalSettings|
               -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
@@ -84,8 +183,10 @@ pub fn create_dynamic_tool(
 
     Ok(LocalTool {
         name: tool_config.name.clone(),
+        source: ToolSource::Dynamic,
         description,
         input_schema,
+        examples: tool_config.examples.clone(),
         handler,
     })
 }
@@ -1,16 +1,67 @@
-use crate::config::{expand_env_var_in_string, expand_env_vars, LocalToolConfig, TemplateValidation};
+use crate::config::{
+    default_max_output_bytes, default_tool_timeout, expand_env_var_in_string, expand_env_vars,
+    unresolved_env_vars, LocalToolConfig, TemplateValidation,
+};
 use colored::Colorize;
 use regex::Regex;
 use serde_json::Value;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::time::timeout;
+use uuid::Uuid;
 
+use super::output_filter::apply_output_filter;
 use super::paths::{canonicalize_within_base_dir, is_option_like, safe_resolve_path};
 use super::registry::LocalSettings;
+use crate::util::truncate_for_display;
+
+/// Prefix of the inline script files written by this process
+fn script_file_prefix() -> String {
+    format!("cmd2ai-{}-", std::process::id())
+}
+
+/// Create the inline script directory, readable only by the current user on Unix
+fn create_script_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Deletes an inline script file when dropped, so every exit path (errors, timeouts) cleans up
+struct TempScript(Option<PathBuf>);
+
+impl Drop for TempScript {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.0 {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Remove inline script files this process left in `script_dir` (e.g. after Ctrl-C)
+/// Files from other cmd2ai processes sharing the directory are left alone.
+pub fn cleanup_temp_scripts(script_dir: &Path) -> std::io::Result<()> {
+    if !script_dir.exists() {
+        return Ok(());
+    }
+    let prefix = script_file_prefix();
+    for entry in fs::read_dir(script_dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
 
 /// Execute a dynamic tool (script or command)
 pub async fn execute_dynamic_tool(
@@ -41,6 +92,7 @@ async fn execute_script(
     settings: &LocalSettings,
 ) -> Result<String, String> {
     let start_time = Instant::now();
+    let (timeout_secs, max_output_bytes) = resolved_limits(tool_config, settings);
     let interpreter = tool_config.interpreter.as_ref().ok_or_else(|| {
         format!(
             "Tool '{}' (type: script) requires 'interpreter' field",
@@ -49,22 +101,28 @@ async fn execute_script(
     })?;
 
     // Determine script source: inline or file path
+    let mut temp_script = TempScript(None);
     let script_path = if let Some(ref inline_script) = tool_config.script {
         // Write inline script to temporary file
-        let temp_dir = settings.base_dir.join(".cmd2ai-tools").join("tmp");
-        fs::create_dir_all(&temp_dir)
+        let temp_dir = &settings.script_dir;
+        create_script_dir(temp_dir)
             .map_err(|e| format!("Failed to create temp directory: {}", e))?;
 
+        // Unique name so concurrent runs of the same tool don't overwrite each other
         let temp_file = temp_dir.join(format!(
-            "{}.{}",
+            "{}{}-{}.{}",
+            script_file_prefix(),
             tool_config.name.replace('/', "_"),
+            Uuid::new_v4().simple(),
             get_script_extension(interpreter)
         ));
 
         fs::write(&temp_file, inline_script)
             .map_err(|e| format!("Failed to write script file: {}", e))?;
+        temp_script.0 = Some(temp_file.clone());
 
-        // Set executable permissions (Unix-like systems)
+        // Set executable permissions (Unix-like systems). Windows has no executable bit;
+        // the script is always run through the interpreter, so nothing to do there.
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
@@ -125,7 +183,8 @@ async fn execute_script(
     };
 
     // Expand environment variables
-    let env_vars = expand_env_vars(&tool_config.env);
+    check_unresolved_env(tool_config, settings, tool_config.env.values())?;
+    let env_vars = expand_env_vars(&tool_config.env)?;
 
     // Log pre-execution info
     if settings.verbose {
@@ -142,18 +201,14 @@ async fn execute_script(
                 interpreter,
                 script_path.display(),
                 working_dir.display(),
-                tool_config.timeout_secs,
+                timeout_secs,
                 env_info
             )
             .dimmed()
         );
         let args_json = serde_json::to_string(arguments)
             .unwrap_or_else(|_| "<invalid>".to_string());
-        let truncated = if args_json.len() > 100 {
-            format!("{}...", &args_json[..100])
-        } else {
-            args_json
-        };
+        let truncated = truncate_for_display(&args_json, 100);
         eprintln!(
             "{}",
             format!("[tools] stdin: {}", truncated).dimmed()
@@ -161,9 +216,22 @@ async fn execute_script(
     }
 
     // Prepare command
-    let mut cmd = Command::new(interpreter);
-    cmd.arg(&script_path)
+    let program = resolve_interpreter(interpreter);
+    if settings.verbose && program != *interpreter {
+        eprintln!(
+            "{}",
+            format!(
+                "[tools] Interpreter '{}' not found on PATH, using '{}'",
+                interpreter, program
+            )
+            .dimmed()
+        );
+    }
+    let mut cmd = Command::new(&program);
+    cmd.args(interpreter_script_args(interpreter))
+        .arg(&script_path)
         .current_dir(&working_dir)
+        .kill_on_drop(true) // don't leave the script running after a timeout
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -174,8 +242,7 @@ async fn execute_script(
     }
 
     // Spawn process
-    let mut child = cmd
-        .spawn()
+    let mut child = spawn_tool(&mut cmd, tool_config)
         .map_err(|e| format!("Failed to spawn script process: {}", e))?;
 
     // Write arguments as JSON to stdin
@@ -194,13 +261,13 @@ async fn execute_script(
     }
 
     // Wait for process with timeout
-    let timeout_duration = Duration::from_secs(tool_config.timeout_secs);
+    let timeout_duration = Duration::from_secs(timeout_secs);
     let output = timeout(timeout_duration, child.wait_with_output())
         .await
         .map_err(|_| {
             format!(
                 "Script execution timed out after {} seconds",
-                tool_config.timeout_secs
+                timeout_secs
             )
         })?
         .map_err(|e| format!("Failed to wait for process: {}", e))?;
@@ -212,11 +279,7 @@ async fn execute_script(
         let exit_code = output.status.code().unwrap_or(-1);
         let stderr_preview = if !output.stderr.is_empty() {
             let stderr_str = String::from_utf8_lossy(&output.stderr);
-            let truncated = if stderr_str.len() > 200 {
-                format!("{}...", &stderr_str[..200])
-            } else {
-                stderr_str.to_string()
-            };
+            let truncated = truncate_for_display(&stderr_str, 200);
             format!(", stderr={}", truncated)
         } else {
             String::new()
@@ -245,16 +308,18 @@ async fn execute_script(
     }
 
     // Check output size
-    if output.stdout.len() > tool_config.max_output_bytes as usize {
+    if output.stdout.len() > max_output_bytes as usize {
         return Err(format!(
             "Script output too large: {} bytes (max: {} bytes)",
             output.stdout.len(),
-            tool_config.max_output_bytes
+            max_output_bytes
         ));
     }
 
     // Return stdout
-    String::from_utf8(output.stdout).map_err(|e| format!("Script output is not valid UTF-8: {}", e))
+    String::from_utf8(output.stdout)
+        .map(|stdout| filter_output(tool_config, stdout))
+        .map_err(|e| format!("Script output is not valid UTF-8: {}", e))
 }
 
 async fn execute_command(
@@ -263,6 +328,7 @@ async fn execute_command(
     settings: &LocalSettings,
 ) -> Result<String, String> {
     let start_time = Instant::now();
+    let (timeout_secs, max_output_bytes) = resolved_limits(tool_config, settings);
     let command = tool_config.command.as_ref().ok_or_else(|| {
         format!(
             "Tool '{}' (type: command) requires 'command' field",
@@ -290,14 +356,19 @@ async fn execute_command(
     };
 
     // Expand environment variables
-    let env_vars = expand_env_vars(&tool_config.env);
+    check_unresolved_env(
+        tool_config,
+        settings,
+        tool_config.env.values().chain(&tool_config.args),
+    )?;
+    let env_vars = expand_env_vars(&tool_config.env)?;
 
     // Template arguments: replace {{key}} with values from arguments JSON
     let env_expanded_args: Vec<String> = tool_config
         .args
         .iter()
         .map(|arg| expand_env_var_in_string(arg))
-        .collect();
+        .collect::<Result<_, _>>()?;
     let templated_args = template_args(
         &env_expanded_args,
         arguments,
@@ -330,7 +401,7 @@ async fn execute_command(
                 "[tools] run: {} (cwd={}, timeout={}s{})",
                 cmd_line,
                 working_dir.display(),
-                tool_config.timeout_secs,
+                timeout_secs,
                 env_info
             )
             .dimmed()
@@ -338,11 +409,7 @@ async fn execute_command(
         if tool_config.stdin_json {
             let args_json = serde_json::to_string(arguments)
                 .unwrap_or_else(|_| "<invalid>".to_string());
-            let truncated = if args_json.len() > 100 {
-                format!("{}...", &args_json[..100])
-            } else {
-                args_json
-            };
+            let truncated = truncate_for_display(&args_json, 100);
             eprintln!(
                 "{}",
                 format!("[tools] stdin: {}", truncated).dimmed()
@@ -370,8 +437,7 @@ async fn execute_command(
     }
 
     // Spawn process
-    let mut child = cmd
-        .spawn()
+    let mut child = spawn_tool(&mut cmd, tool_config)
         .map_err(|e| format!("Failed to spawn command process: {}", e))?;
 
     // Write arguments as JSON to stdin (only if stdin_json is true)
@@ -392,13 +458,13 @@ async fn execute_command(
     }
 
     // Wait for process with timeout
-    let timeout_duration = Duration::from_secs(tool_config.timeout_secs);
+    let timeout_duration = Duration::from_secs(timeout_secs);
     let output = timeout(timeout_duration, child.wait_with_output())
         .await
         .map_err(|_| {
             format!(
                 "Command execution timed out after {} seconds",
-                tool_config.timeout_secs
+                timeout_secs
             )
         })?
         .map_err(|e| format!("Failed to wait for process: {}", e))?;
@@ -410,11 +476,7 @@ async fn execute_command(
         let exit_code = output.status.code().unwrap_or(-1);
         let stderr_preview = if !output.stderr.is_empty() {
             let stderr_str = String::from_utf8_lossy(&output.stderr);
-            let truncated = if stderr_str.len() > 200 {
-                format!("{}...", &stderr_str[..200])
-            } else {
-                stderr_str.to_string()
-            };
+            let truncated = truncate_for_display(&stderr_str, 200);
             format!(", stderr={}", truncated)
         } else {
             String::new()
@@ -443,19 +505,129 @@ async fn execute_command(
     }
 
     // Check output size
-    if output.stdout.len() > tool_config.max_output_bytes as usize {
+    if output.stdout.len() > max_output_bytes as usize {
         return Err(format!(
             "Command output too large: {} bytes (max: {} bytes)",
             output.stdout.len(),
-            tool_config.max_output_bytes
+            max_output_bytes
         ));
     }
 
     // Return stdout
     String::from_utf8(output.stdout)
+        .map(|stdout| filter_output(tool_config, stdout))
         .map_err(|e| format!("Command output is not valid UTF-8: {}", e))
 }
 
+/// Report `${VAR}`s that would reach the process unexpanded: an error with
+/// `local_tools.strict_env`, otherwise a warning in verbose mode
+fn check_unresolved_env<'a>(
+    tool_config: &LocalToolConfig,
+    settings: &LocalSettings,
+    values: impl Iterator<Item = &'a String>,
+) -> Result<(), String> {
+    let mut missing: Vec<String> = values.flat_map(|value| unresolved_env_vars(value)).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    missing.sort();
+    missing.dedup();
+
+    let message = format!(
+        "Tool '{}' references unset environment variables: {}",
+        tool_config.name,
+        missing.join(", ")
+    );
+    if settings.strict_env {
+        return Err(message);
+    }
+    if settings.verbose {
+        eprintln!("{}", format!("[tools] Warning: {}", message).dimmed());
+    }
+    Ok(())
+}
+
+/// The tool's timeout and output limit, falling back to the built-in defaults
+/// (the registry fills in `local_tools.default_*` before tools are created).
+/// A `--tool-timeout` override replaces the timeout, whether it is higher or lower.
+fn resolved_limits(tool_config: &LocalToolConfig, settings: &LocalSettings) -> (u64, u64) {
+    (
+        settings
+            .timeout_override
+            .or(tool_config.timeout_secs)
+            .unwrap_or_else(default_tool_timeout),
+        tool_config
+            .max_output_bytes
+            .unwrap_or_else(default_max_output_bytes),
+    )
+}
+
+/// Apply the tool's `output_filter`, if any, to its successful stdout
+fn filter_output(tool_config: &LocalToolConfig, stdout: String) -> String {
+    match &tool_config.output_filter {
+        Some(filter) => apply_output_filter(filter, stdout),
+        None => stdout,
+    }
+}
+
+/// Spawn a tool process, cut off from the network when the tool sets `network: false`.
+/// The registry already probed for isolation (and warned) when it loaded the tool.
+fn spawn_tool(cmd: &mut Command, tool_config: &LocalToolConfig) -> std::io::Result<Child> {
+    if !tool_config.network && network_isolation_supported() {
+        #[cfg(target_os = "linux")]
+        // SAFETY: isolate_network only makes the unshare syscall, which is safe after fork
+        unsafe {
+            cmd.pre_exec(isolate_network);
+        }
+    }
+    cmd.spawn()
+}
+
+/// Move the calling process into a fresh network namespace (only a down loopback device).
+/// Unprivileged users need a user namespace for that; root can create one directly.
+#[cfg(target_os = "linux")]
+fn isolate_network() -> std::io::Result<()> {
+    // SAFETY: unshare takes no pointers and only affects the calling process
+    unsafe {
+        if libc::unshare(libc::CLONE_NEWNET) == 0
+            || libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) == 0
+        {
+            return Ok(());
+        }
+    }
+    Err(std::io::Error::last_os_error())
+}
+
+/// Whether `isolate_network` works here (it fails e.g. when user namespaces are disabled
+/// or inside some containers); probed once by starting `/bin/sh` with it, which blocks,
+/// so the registry runs the probe while loading rather than from a tool call
+pub fn network_isolation_supported() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        use std::sync::OnceLock;
+
+        static SUPPORTED: OnceLock<bool> = OnceLock::new();
+        *SUPPORTED.get_or_init(|| {
+            let mut probe = std::process::Command::new("/bin/sh");
+            probe
+                .args(["-c", "exit 0"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            // SAFETY: see spawn_tool
+            unsafe {
+                probe.pre_exec(isolate_network);
+            }
+            probe.status().is_ok_and(|status| status.success())
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
 /// Template arguments: replace {{key}} with values from arguments JSON
 /// This function validates and sanitizes templated values, especially paths,
 /// to prevent argument injection and path traversal attacks.
@@ -468,64 +640,95 @@ fn template_args(
     let re = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
     let mut has_path_placeholders = false;
     let mut templated_args = Vec::new();
-    let mut args_with_placeholders = Vec::new(); // Track which args had placeholders BEFORE substitution
-
-    for (arg_idx, arg) in args.iter().enumerate() {
-        let mut result = arg.clone();
-        let mut had_placeholder = false;
-        
-        // Collect all matches with their byte positions first
-        // This prevents cascading replacements where a replacement value
-        // contains a placeholder pattern that gets replaced again
-        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
-        
+    let mut args_with_placeholders = Vec::new(); // Output index of the first entry of each templated arg
+
+    for arg in args {
+        let had_placeholder = re.is_match(arg);
+
+        // An array value for a key with expand_arrays repeats the whole arg once per element
+        let mut expand: Option<(&str, &Vec<Value>)> = None;
         for cap in re.captures_iter(arg) {
-            had_placeholder = true;
-            let key = &cap[1];
-            let placeholder = &cap[0];
-            let start = cap.get(0).unwrap().start();
-            let end = cap.get(0).unwrap().end();
-            
-            // Get value from arguments JSON
-            if let Some(value) = arguments.get(key) {
-                let value_str = match value {
-                    Value::String(s) => s.clone(),
-                    Value::Number(n) => n.to_string(),
-                    Value::Bool(b) => b.to_string(),
-                    Value::Null => String::new(),
-                    _ => serde_json::to_string(value).unwrap_or_else(|_| placeholder.to_string()),
-                };
-                
-                // Determine validation policy for this key
-                let validation = get_validation_policy(key, tool_config);
-                
-                // Validate and transform the value based on policy
-                let validated_value = validate_and_transform_value(
-                    key,
-                    &value_str,
-                    &validation,
-                    tool_config,
-                    settings,
-                )?;
-                
-                if validation.kind == "path" {
-                    has_path_placeholders = true;
+            let key = cap.get(1).unwrap().as_str();
+            if let Some(Value::Array(items)) = arguments.get(key) {
+                if get_validation_policy(key, tool_config).expand_arrays {
+                    match expand {
+                        Some((other, _)) if other != key => {
+                            return Err(format!(
+                                "Argument '{}' combines array placeholders '{}' and '{}'; only one can be expanded per argument",
+                                arg, other, key
+                            ));
+                        }
+                        _ => expand = Some((key, items)),
+                    }
                 }
-                
-                replacements.push((start, end, validated_value));
             }
-            // If key not found, leave placeholder as-is (validation should catch missing required fields)
         }
-        
-        // Replace from end to start to preserve positions
-        replacements.sort_by(|a, b| b.0.cmp(&a.0));
-        for (start, end, replacement) in replacements {
-            result.replace_range(start..end, &replacement);
+
+        let first_output_idx = templated_args.len();
+        match expand {
+            Some((key, items)) => {
+                for item in items {
+                    templated_args.push(substitute_placeholders(
+                        &re,
+                        arg,
+                        arguments,
+                        Some((key, item)),
+                        tool_config,
+                        settings,
+                        &mut has_path_placeholders,
+                    )?);
+                }
+            }
+            None => templated_args.push(substitute_placeholders(
+                &re,
+                arg,
+                arguments,
+                None,
+                tool_config,
+                settings,
+                &mut has_path_placeholders,
+            )?),
         }
-        
-        templated_args.push(result);
-        if had_placeholder {
-            args_with_placeholders.push(arg_idx);
+
+        // An empty expanded array produces no entries, so there's nothing to guard
+        if had_placeholder && templated_args.len() > first_output_idx {
+            args_with_placeholders.push(first_output_idx);
+        }
+    }
+
+    // Boolean flags go before the first templated arg so they stay ahead of any "--"
+    let flags = flag_arguments(arguments, tool_config)?;
+    if !flags.is_empty() {
+        let insert_at = args_with_placeholders
+            .first()
+            .copied()
+            .unwrap_or(templated_args.len());
+        for idx in args_with_placeholders.iter_mut() {
+            *idx += flags.len();
+        }
+        templated_args.splice(insert_at..insert_at, flags);
+    }
+
+    // Append positional arguments from args_from; missing or null keys are skipped,
+    // arrays become one positional arg per element when the key has expand_arrays
+    for key in tool_config.args_from.iter().flatten() {
+        let validation = get_validation_policy(key, tool_config);
+        let values: Vec<&Value> = match arguments.get(key) {
+            None | Some(Value::Null) => continue,
+            Some(Value::Array(items)) if validation.expand_arrays => items.iter().collect(),
+            Some(value) => vec![value],
+        };
+
+        if validation.kind == "path" {
+            has_path_placeholders = true;
+        }
+
+        for value in values {
+            let value_str = argument_value_string(value);
+            let validated_value =
+                validate_and_transform_value(key, &value_str, &validation, tool_config, settings)?;
+            args_with_placeholders.push(templated_args.len());
+            templated_args.push(validated_value);
         }
     }
 
@@ -556,8 +759,97 @@ fn template_args(
     }
 }
 
+/// Replace every `{{key}}` in one arg with its validated value; `element` overrides the
+/// value of the key being expanded from an array
+fn substitute_placeholders(
+    re: &Regex,
+    arg: &str,
+    arguments: &Value,
+    element: Option<(&str, &Value)>,
+    tool_config: &LocalToolConfig,
+    settings: &LocalSettings,
+    has_path_placeholders: &mut bool,
+) -> Result<String, String> {
+    let mut result = arg.to_string();
+
+    // Collect all matches with their byte positions first
+    // This prevents cascading replacements where a replacement value
+    // contains a placeholder pattern that gets replaced again
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+    for cap in re.captures_iter(arg) {
+        let key = &cap[1];
+        let start = cap.get(0).unwrap().start();
+        let end = cap.get(0).unwrap().end();
+
+        let value = match element {
+            Some((expanded_key, item)) if expanded_key == key => Some(item),
+            _ => arguments.get(key),
+        };
+
+        // Get value from arguments JSON
+        if let Some(value) = value {
+            let value_str = argument_value_string(value);
+
+            // Determine validation policy for this key
+            let validation = get_validation_policy(key, tool_config);
+
+            // Validate and transform the value based on policy
+            let validated_value =
+                validate_and_transform_value(key, &value_str, &validation, tool_config, settings)?;
+
+            if validation.kind == "path" {
+                *has_path_placeholders = true;
+            }
+
+            replacements.push((start, end, validated_value));
+        }
+        // If key not found, leave placeholder as-is (validation should catch missing required fields)
+    }
+
+    // Replace from end to start to preserve positions
+    replacements.sort_by_key(|r| std::cmp::Reverse(r.0));
+    for (start, end, replacement) in replacements {
+        result.replace_range(start..end, &replacement);
+    }
+
+    Ok(result)
+}
+
+/// String form of a JSON argument value as passed on the command line
+fn argument_value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// Collect the flags from `flag_args` whose boolean argument is true, ordered by key
+fn flag_arguments(arguments: &Value, tool_config: &LocalToolConfig) -> Result<Vec<String>, String> {
+    let mut keys: Vec<&String> = tool_config.flag_args.keys().collect();
+    keys.sort();
+
+    let mut flags = Vec::new();
+    for key in keys {
+        match arguments.get(key) {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => {}
+            Some(Value::Bool(true)) => flags.push(tool_config.flag_args[key].clone()),
+            Some(other) => {
+                return Err(format!(
+                    "Invalid flag argument '{}': expected a boolean, got {}",
+                    key, other
+                ))
+            }
+        }
+    }
+    Ok(flags)
+}
+
 /// Get validation policy for a template key
-fn get_validation_policy(key: &str, tool_config: &LocalToolConfig) -> TemplateValidation {
+pub(super) fn get_validation_policy(key: &str, tool_config: &LocalToolConfig) -> TemplateValidation {
     // Check if explicit validation is configured
     if let Some(ref validations) = tool_config.template_validations {
         if let Some(validation) = validations.get(key) {
@@ -573,6 +865,7 @@ fn get_validation_policy(key: &str, tool_config: &LocalToolConfig) -> TemplateVa
             allow_patterns: None,
             deny_patterns: None,
             allow_absolute: false,
+            expand_arrays: false,
         }
     } else {
         // Default to string validation
@@ -581,6 +874,7 @@ fn get_validation_policy(key: &str, tool_config: &LocalToolConfig) -> TemplateVa
             allow_patterns: None,
             deny_patterns: None,
             allow_absolute: false,
+            expand_arrays: false,
         }
     }
 }
@@ -626,7 +920,7 @@ fn validate_and_transform_value(
                 Ok(value.to_string())
             }
         }
-        "string" | _ => {
+        _ => {
             // Apply regex pattern validation if configured
             if let Some(ref allow_patterns) = validation.allow_patterns {
                 let mut matched = false;
@@ -665,17 +959,108 @@ fn validate_and_transform_value(
 }
 
 
+/// Interpreter file name without directory or `.exe` suffix, lowercased
+fn interpreter_name(interpreter: &str) -> String {
+    let name = Path::new(interpreter)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| interpreter.to_lowercase());
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
 /// Get script file extension based on interpreter
-fn get_script_extension(interpreter: &str) -> &str {
-    if interpreter.contains("python") {
+fn get_script_extension(interpreter: &str) -> &'static str {
+    let name = interpreter_name(interpreter);
+    // PowerShell before "sh", which it contains
+    if name.contains("powershell") || name == "pwsh" {
+        "ps1"
+    } else if name == "cmd" {
+        "cmd"
+    } else if name.contains("python") || name == "py" {
         "py"
-    } else if interpreter.contains("node") || interpreter.contains("bun") {
+    } else if name.contains("node") || name.contains("bun") {
         "js"
-    } else if interpreter.contains("bash") || interpreter.contains("sh") {
+    } else if name.contains("bash") || name.contains("sh") {
         "sh"
-    } else if interpreter.contains("ruby") {
+    } else if name.contains("ruby") {
         "rb"
     } else {
         "txt"
     }
 }
+
+/// Arguments the interpreter needs before the script path
+fn interpreter_script_args(interpreter: &str) -> &'static [&'static str] {
+    let name = interpreter_name(interpreter);
+    if name.contains("powershell") || name == "pwsh" {
+        // Unsigned temp scripts are blocked by the default execution policy
+        &["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"]
+    } else if name == "cmd" {
+        &["/C"]
+    } else {
+        &[]
+    }
+}
+
+/// Find an interpreter on PATH, trying PATHEXT extensions on Windows
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| ext.to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    std::env::split_paths(&path).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Where a tool's interpreter or command would be found, applying the same fallbacks as
+/// when the tool runs (`python3` -> `python`, ...); None when it is missing
+pub fn locate_program(program: &str) -> Option<PathBuf> {
+    let resolved = resolve_interpreter(program);
+    let path = Path::new(&resolved);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    find_in_path(&resolved)
+}
+
+/// Resolve the interpreter to launch. Windows installs rarely provide `python3`
+/// (the launcher is `py`) and `nodejs` is a Debian-ism, so fall back to the
+/// common names when the configured one isn't on PATH.
+fn resolve_interpreter(interpreter: &str) -> String {
+    let has_dir = Path::new(interpreter).components().count() > 1;
+    if has_dir || find_in_path(interpreter).is_some() {
+        return interpreter.to_string();
+    }
+
+    let fallbacks: &[&str] = match interpreter_name(interpreter).as_str() {
+        "python3" => &["python", "py"],
+        "python" => &["python3", "py"],
+        "nodejs" => &["node"],
+        "node" => &["nodejs"],
+        _ => &[],
+    };
+    fallbacks
+        .iter()
+        .find(|name| find_in_path(name).is_some())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| interpreter.to_string())
+}
@@ -1,9 +1,11 @@
 pub mod builtins;
 mod dynamic;
 mod executor;
+mod output_filter;
 pub mod paths;
 mod registry;
 mod tools;
 
-pub use registry::{LocalSettings, LocalToolRegistry};
-pub use tools::{call_local_tool, format_tools_for_llm};
+pub use executor::{cleanup_temp_scripts, locate_program, network_isolation_supported};
+pub use registry::{LocalSettings, LocalToolRegistry, ToolSource};
+pub use tools::{call_local_tool, format_tool_list, format_tools_for_llm, limit_exposed_tools};
@@ -0,0 +1,141 @@
+use crate::config::OutputFilter;
+use regex::Regex;
+use serde_json::Value;
+
+/// One step of a jq-style path such as `.items[].name`
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    /// `[]`: continue with every element of an array
+    Iterate,
+}
+
+/// Check a filter when the tool is registered, so mistakes show up before the first call
+pub fn validate_output_filter(filter: &OutputFilter) -> Result<(), String> {
+    match (&filter.json_path, &filter.regex) {
+        (Some(path), None) => parse_json_path(path).map(|_| ()),
+        (None, Some(pattern)) => Regex::new(pattern)
+            .map(|_| ())
+            .map_err(|e| format!("invalid output_filter regex '{}': {}", pattern, e)),
+        _ => Err("output_filter needs exactly one of 'json_path' or 'regex'".to_string()),
+    }
+}
+
+/// Apply a tool's output filter to its stdout. When the filter does not match, the raw
+/// output is returned with a note so the model still gets something useful.
+pub fn apply_output_filter(filter: &OutputFilter, output: String) -> String {
+    let filtered = match (&filter.json_path, &filter.regex) {
+        (Some(path), _) => extract_json_path(path, &output),
+        (None, Some(pattern)) => extract_regex(pattern, &output),
+        (None, None) => return output,
+    };
+
+    match filtered {
+        Ok(filtered) => filtered,
+        Err(reason) => format!(
+            "{}\n[output_filter not applied ({}); showing raw output]",
+            output.trim_end(),
+            reason
+        ),
+    }
+}
+
+/// Parse `.a.b`, `.a[0]`, `.a[]` and `.["odd key"]`; `.` alone is the whole document
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let invalid = |why: &str| format!("invalid output_filter json_path '{}': {}", path, why);
+
+    let mut rest = path
+        .trim()
+        .strip_prefix('.')
+        .ok_or_else(|| invalid("must start with '.'"))?;
+    let mut segments = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+            let inner = after[..end].trim();
+            let segment = if inner.is_empty() {
+                PathSegment::Iterate
+            } else if let Some(key) = inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                PathSegment::Key(key.to_string())
+            } else {
+                inner
+                    .parse()
+                    .map(PathSegment::Index)
+                    .map_err(|_| invalid("index must be a non-negative number or \"key\""))?
+            };
+            segments.push(segment);
+            rest = &after[end + 1..];
+        } else {
+            // `.key` (the leading dot of the first key was stripped above)
+            let rest_key = rest.strip_prefix('.').unwrap_or(rest);
+            let end = rest_key.find(['.', '[']).unwrap_or(rest_key.len());
+            let key = &rest_key[..end];
+            if key.is_empty() {
+                return Err(invalid("empty key"));
+            }
+            segments.push(PathSegment::Key(key.to_string()));
+            rest = &rest_key[end..];
+        }
+    }
+
+    Ok(segments)
+}
+
+fn extract_json_path(path: &str, output: &str) -> Result<String, String> {
+    let segments = parse_json_path(path)?;
+    let document: Value =
+        serde_json::from_str(output).map_err(|e| format!("output is not JSON: {}", e))?;
+
+    let mut current = vec![&document];
+    for segment in &segments {
+        let mut next = Vec::new();
+        for value in current {
+            match segment {
+                PathSegment::Key(key) => {
+                    next.push(value.get(key).ok_or_else(|| format!("no key '{}'", key))?)
+                }
+                PathSegment::Index(index) => next.push(
+                    value
+                        .get(index)
+                        .ok_or_else(|| format!("no element [{}]", index))?,
+                ),
+                PathSegment::Iterate => next.extend(
+                    value
+                        .as_array()
+                        .ok_or_else(|| "[] used on a value that is not an array".to_string())?,
+                ),
+            }
+        }
+        current = next;
+    }
+
+    // Without `[]` the path names a single value; with it, collect the matches
+    let result = if segments.contains(&PathSegment::Iterate) {
+        Value::Array(current.into_iter().cloned().collect())
+    } else {
+        current[0].clone()
+    };
+
+    Ok(match result {
+        Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+/// Every match of the pattern, one per line, using the first capture group when there is one
+fn extract_regex(pattern: &str, output: &str) -> Result<String, String> {
+    let re = Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e))?;
+    let matches: Vec<&str> = re
+        .captures_iter(output)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+        .map(|m| m.as_str())
+        .collect();
+
+    if matches.is_empty() {
+        Err(format!("regex '{}' did not match", pattern))
+    } else {
+        Ok(matches.join("\n"))
+    }
+}
@@ -1,19 +1,29 @@
 use crate::config::LocalToolsConfig;
+use crate::util::truncate_for_display;
 use colored::Colorize;
 use jsonschema::{Draft, JSONSchema};
+use regex::Regex;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use tokio::sync::Semaphore;
 
 use super::builtins;
 use super::dynamic;
+use super::executor::network_isolation_supported;
 
 #[derive(Debug, Clone)]
 pub struct LocalSettings {
     pub base_dir: PathBuf,
     pub max_file_size_bytes: u64,
+    /// Directory for inline script files
+    pub script_dir: PathBuf,
+    /// Fail tool calls whose env or args reference unset `${VAR}`s (`local_tools.strict_env`)
+    pub strict_env: bool,
+    /// Replaces every tool's timeout for this run (`--tool-timeout` / `AI_TOOL_TIMEOUT`)
+    pub timeout_override: Option<u64>,
     pub verbose: bool,
 }
 
@@ -22,10 +32,7 @@ impl LocalSettings {
         let base_dir = config
             .base_dir
             .as_ref()
-            .map(|s| {
-                // Expand environment variables
-                crate::config::expand_env_var_in_string(s)
-            })
+            .and_then(|s| expand_setting("base_dir", s))
             .and_then(|s| {
                 if s.is_empty() {
                     None
@@ -33,19 +40,43 @@ impl LocalSettings {
                     Some(PathBuf::from(s))
                 }
             })
-            .or_else(|| dirs::home_dir())
+            .or_else(dirs::home_dir)
             .unwrap_or_else(|| PathBuf::from("."));
 
         let max_file_size_bytes = config.max_file_size_mb * 1024 * 1024;
 
+        // Keep generated scripts out of base_dir so they don't show up in the user's project
+        let script_dir = config
+            .temp_dir
+            .as_ref()
+            .and_then(|s| expand_setting("temp_dir", s))
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("cmd2ai-scripts"));
+
         Self {
             base_dir,
             max_file_size_bytes,
+            script_dir,
+            strict_env: config.strict_env,
+            timeout_override: None,
             verbose,
         }
     }
 }
 
+/// Expand environment variables in a path setting; on a `${VAR:?}` error, warn and
+/// fall back to the default as if the setting were absent
+fn expand_setting(name: &str, value: &str) -> Option<String> {
+    match crate::config::expand_env_var_in_string(value) {
+        Ok(expanded) => Some(expanded),
+        Err(e) => {
+            eprintln!("Warning: Ignoring local_tools.{}: {}", name, e);
+            None
+        }
+    }
+}
+
 /// Type alias for tool handler functions
 pub type ToolHandler = Box<
     dyn for<'a> Fn(
@@ -57,16 +88,66 @@ pub type ToolHandler = Box<
         + Sync,
 >;
 
+/// Where a registered tool comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolSource {
+    Builtin,
+    Dynamic,
+}
+
+impl std::fmt::Display for ToolSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolSource::Builtin => write!(f, "builtin"),
+            ToolSource::Dynamic => write!(f, "dynamic"),
+        }
+    }
+}
+
 pub struct LocalTool {
     pub name: String,
+    pub source: ToolSource,
     pub description: String,
     pub input_schema: Value,
+    pub examples: Vec<Value>,
     pub handler: ToolHandler,
 }
 
+/// Maximum function name length accepted by OpenAI-compatible APIs
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// A configured tool that was not registered, and why
+#[derive(Debug, Clone)]
+pub struct SkippedTool {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Check that a tool name is a valid function name (`^[a-zA-Z0-9_-]+$`, at most 64 chars)
+pub fn validate_tool_name(name: &str) -> Result<(), String> {
+    let pattern = Regex::new(r"^[a-zA-Z0-9_-]+$").unwrap();
+    if !pattern.is_match(name) {
+        return Err(format!(
+            "invalid tool name '{}' (only letters, digits, '_' and '-' are allowed)",
+            name
+        ));
+    }
+    if name.len() > MAX_TOOL_NAME_LEN {
+        return Err(format!(
+            "tool name '{}' is longer than {} characters",
+            name, MAX_TOOL_NAME_LEN
+        ));
+    }
+    Ok(())
+}
+
 pub struct LocalToolRegistry {
     tools: HashMap<String, LocalTool>,
     settings: LocalSettings,
+    skipped: Vec<SkippedTool>,
+    strict_schema: bool,
+    /// Per-tool `max_concurrency` limits; tools without an entry are unlimited
+    concurrency_limits: HashMap<String, Semaphore>,
 }
 
 impl LocalToolRegistry {
@@ -74,6 +155,9 @@ impl LocalToolRegistry {
         let mut registry = Self {
             tools: HashMap::new(),
             settings,
+            skipped: Vec::new(),
+            strict_schema: config.strict_schema,
+            concurrency_limits: HashMap::new(),
         };
 
         // Register built-in tools
@@ -86,6 +170,13 @@ impl LocalToolRegistry {
     }
 
     fn register_builtin_tools(&mut self, config: &LocalToolsConfig) {
+        if !config.builtins_enabled {
+            if self.settings.verbose {
+                eprintln!("{}", "[tools] Built-in tools disabled".dimmed());
+            }
+            return;
+        }
+
         // Check if each tool is enabled in config
         let is_enabled = |name: &str| -> bool {
             config
@@ -105,6 +196,7 @@ impl LocalToolRegistry {
                 "read_file".to_string(),
                 LocalTool {
                     name: "read_file".to_string(),
+                    source: ToolSource::Builtin,
                     description: "Read and return the contents of a file. Limited to files within the base directory and under the size limit.".to_string(),
                     input_schema: json!({
                         "type": "object",
@@ -117,6 +209,7 @@ impl LocalToolRegistry {
                         "required": ["path"],
                         "additionalProperties": false
                     }),
+                    examples: Vec::new(),
                     handler: Box::new(|args, settings| {
                         let args = args.clone();
                         let settings = settings.clone();
@@ -131,6 +224,8 @@ impl LocalToolRegistry {
     }
 
     fn register_dynamic_tools(&mut self, config: &LocalToolsConfig) {
+        let builtin_names: HashSet<String> = self.tools.keys().cloned().collect();
+
         for tool_config in &config.tools {
             // Skip if not enabled
             if !tool_config.enabled {
@@ -148,8 +243,15 @@ impl LocalToolRegistry {
                 continue;
             }
 
+            // Reject names the API would refuse with an opaque 400
+            if let Err(e) = validate_tool_name(&tool_config.name) {
+                eprintln!("Warning: Skipping tool: {}", e);
+                self.skip(&tool_config.name, e);
+                continue;
+            }
+
             // Skip if tool with same name already exists (built-in takes precedence)
-            if self.tools.contains_key(&tool_config.name) {
+            if builtin_names.contains(&tool_config.name) {
                 if self.settings.verbose {
                     eprintln!(
                         "{}",
@@ -160,9 +262,30 @@ impl LocalToolRegistry {
                         .dimmed()
                     );
                 }
+                self.skip(&tool_config.name, "conflicts with a built-in tool".to_string());
+                continue;
+            }
+
+            // Skip later duplicates of a dynamic tool (first definition wins)
+            if self.tools.contains_key(&tool_config.name) {
+                eprintln!(
+                    "Warning: Skipping duplicate tool '{}' (already defined earlier in config)",
+                    tool_config.name
+                );
+                self.skip(&tool_config.name, "duplicate tool name".to_string());
                 continue;
             }
 
+            // Fill in the global limits for tools that don't set their own
+            let mut tool_config = tool_config.clone();
+            tool_config
+                .timeout_secs
+                .get_or_insert(config.default_timeout_secs);
+            tool_config
+                .max_output_bytes
+                .get_or_insert(config.default_max_output_bytes);
+            let tool_config = &tool_config;
+
             // Create dynamic tool
             match dynamic::create_dynamic_tool(tool_config, &self.settings) {
                 Ok(tool) => {
@@ -173,7 +296,21 @@ impl LocalToolRegistry {
                                 .dimmed()
                         );
                     }
+                    if !tool_config.network && !network_isolation_supported() {
+                        eprintln!(
+                            "{}",
+                            format!(
+                                "Warning: Tool '{}' sets network: false, but network isolation is not available here; running it with network access",
+                                tool_config.name
+                            )
+                            .yellow()
+                        );
+                    }
                     self.tools.insert(tool_config.name.clone(), tool);
+                    if let Some(limit) = tool_config.max_concurrency {
+                        self.concurrency_limits
+                            .insert(tool_config.name.clone(), Semaphore::new(limit));
+                    }
                 }
                 Err(e) => {
                     // Log error but don't fail - just skip this tool
@@ -181,11 +318,24 @@ impl LocalToolRegistry {
                         "Warning: Failed to register dynamic tool '{}': {}",
                         tool_config.name, e
                     );
+                    self.skip(&tool_config.name, e);
                 }
             }
         }
     }
 
+    fn skip(&mut self, name: &str, reason: String) {
+        self.skipped.push(SkippedTool {
+            name: name.to_string(),
+            reason,
+        });
+    }
+
+    /// Tools from the config that were not registered (invalid name, duplicate, bad definition)
+    pub fn skipped_tools(&self) -> &[SkippedTool] {
+        &self.skipped
+    }
+
     pub fn get(&self, name: &str) -> Option<&LocalTool> {
         self.tools.get(name)
     }
@@ -198,6 +348,38 @@ impl LocalToolRegistry {
         &self.settings
     }
 
+    /// The semaphore limiting concurrent calls of a tool, if it sets `max_concurrency`
+    pub fn concurrency_limit(&self, name: &str) -> Option<&Semaphore> {
+        self.concurrency_limits.get(name)
+    }
+
+    fn log_unknown_arguments(&self, tool_name: &str, input_schema: &Value, arguments: &Value) {
+        if !self.settings.verbose {
+            return;
+        }
+        let (Some(properties), Some(args)) =
+            (input_schema["properties"].as_object(), arguments.as_object())
+        else {
+            return;
+        };
+        let unknown: Vec<&str> = args
+            .keys()
+            .filter(|key| !properties.contains_key(*key))
+            .map(|key| key.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            eprintln!(
+                "{}",
+                format!(
+                    "[tools] Ignoring unknown arguments for '{}': {}",
+                    tool_name,
+                    unknown.join(", ")
+                )
+                .dimmed()
+            );
+        }
+    }
+
     pub fn validate_arguments(&self, tool_name: &str, arguments: &Value) -> Result<(), String> {
         let tool = self
             .tools
@@ -207,11 +389,7 @@ impl LocalToolRegistry {
         if self.settings.verbose {
             let args_str = serde_json::to_string(arguments)
                 .unwrap_or_else(|_| "<invalid json>".to_string());
-            let truncated = if args_str.len() > 200 {
-                format!("{}...", &args_str[..200])
-            } else {
-                args_str
-            };
+            let truncated = truncate_for_display(&args_str, 200);
             eprintln!(
                 "{}",
                 format!("[tools] Validating arguments for '{}': {}", tool_name, truncated)
@@ -219,18 +397,43 @@ impl LocalToolRegistry {
             );
         }
 
+        // With strict_schema off, extra keys from over-eager models are ignored
+        // instead of failing the call
+        let relaxed_schema;
+        let input_schema = if self.strict_schema {
+            &tool.input_schema
+        } else {
+            self.log_unknown_arguments(tool_name, &tool.input_schema, arguments);
+            relaxed_schema = relax_additional_properties(&tool.input_schema);
+            &relaxed_schema
+        };
+
         // Compile the JSON schema
         let schema = JSONSchema::options()
             .with_draft(Draft::Draft7)
-            .compile(&tool.input_schema)
+            .compile(input_schema)
             .map_err(|e| format!("Invalid tool schema: {}", e))?;
 
         // Validate arguments against schema
+        // The message goes back to the model as a tool result, so name both the offending
+        // argument and the schema rule it broke
         if let Err(errors) = schema.validate(arguments) {
             let error_messages: Vec<String> = errors
-                .map(|e| format!("{}: {}", e.instance_path, e.to_string()))
+                .map(|e| {
+                    let instance_path = e.instance_path.to_string();
+                    let location = if instance_path.is_empty() {
+                        "/".to_string()
+                    } else {
+                        instance_path
+                    };
+                    format!("at '{}' (schema path '{}'): {}", location, e.schema_path, e)
+                })
                 .collect();
-            let error_msg = error_messages.join("; ");
+            let error_msg = format!(
+                "Invalid arguments for tool '{}': {}. Correct the arguments and call the tool again.",
+                tool_name,
+                error_messages.join("; ")
+            );
             if self.settings.verbose {
                 eprintln!(
                     "{}",
@@ -251,3 +454,21 @@ impl LocalToolRegistry {
         Ok(())
     }
 }
+
+/// Copy of a schema with `additionalProperties: false` removed at every level
+fn relax_additional_properties(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, value)| {
+                    !(key.as_str() == "additionalProperties" && value == &&Value::Bool(false))
+                })
+                .map(|(key, value)| (key.clone(), relax_additional_properties(value)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(relax_additional_properties).collect())
+        }
+        other => other.clone(),
+    }
+}
@@ -1,17 +1,21 @@
 use super::registry::LocalToolRegistry;
+use crate::util::truncate_for_display;
 use colored::Colorize;
 use serde_json::{json, Value};
 
 pub fn format_tools_for_llm(registry: &LocalToolRegistry) -> Vec<Value> {
-    registry
-        .list()
+    // Sort by name so the payload is stable across runs
+    let mut tools = registry.list();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    tools
         .iter()
         .map(|tool| {
             json!({
                 "type": "function",
                 "function": {
                     "name": tool.name,
-                    "description": tool.description,
+                    "description": describe_with_examples(&tool.description, &tool.examples),
                     "parameters": tool.input_schema,
                 }
             })
@@ -19,6 +23,93 @@ pub fn format_tools_for_llm(registry: &LocalToolRegistry) -> Vec<Value> {
         .collect()
 }
 
+/// Human-readable listing of the registered tools for `--list-tools`, sorted by name:
+/// one `name (source)` line per tool followed by its description and required arguments
+pub fn format_tool_list(registry: &LocalToolRegistry) -> String {
+    let mut tools = registry.list();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut output = String::new();
+    for tool in tools {
+        output.push_str(&format!("{} ({})\n", tool.name, tool.source));
+        output.push_str(&format!("    {}\n", tool.description));
+        let required: Vec<&str> = tool.input_schema["required"]
+            .as_array()
+            .map(|keys| keys.iter().filter_map(|k| k.as_str()).collect())
+            .unwrap_or_default();
+        if !required.is_empty() {
+            output.push_str(&format!("    required: {}\n", required.join(", ")));
+        }
+    }
+    output
+}
+
+/// Keep at most `max` tool definitions, preferring tools whose name or description
+/// mentions words from the user's query. Ties keep the existing (alphabetical) order.
+/// Returns the kept tools and the names of the omitted ones.
+pub fn limit_exposed_tools(
+    tools: Vec<Value>,
+    max: usize,
+    query: &str,
+) -> (Vec<Value>, Vec<String>) {
+    if tools.len() <= max {
+        return (tools, Vec::new());
+    }
+
+    let query_words: Vec<String> = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() >= 4) // skip "the", "and", ... which match almost anything
+        .map(String::from)
+        .collect();
+
+    let tool_name = |tool: &Value| {
+        tool["function"]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    };
+    let relevance = |tool: &Value| {
+        let haystack = format!(
+            "{} {}",
+            tool["function"]["name"].as_str().unwrap_or_default(),
+            tool["function"]["description"].as_str().unwrap_or_default()
+        )
+        .to_lowercase();
+        query_words
+            .iter()
+            .filter(|w| haystack.contains(w.as_str()))
+            .count()
+    };
+
+    let mut ranked: Vec<(usize, Value)> = tools.into_iter().map(|t| (relevance(&t), t)).collect();
+    // Stable sort keeps the incoming order for equally relevant tools
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    let omitted = ranked[max..].iter().map(|(_, t)| tool_name(t)).collect();
+    let mut kept: Vec<Value> = ranked.into_iter().take(max).map(|(_, t)| t).collect();
+    kept.sort_by_key(|t| tool_name(t));
+
+    (kept, omitted)
+}
+
+/// Append example arguments to a tool description so the model sees concrete calls
+fn describe_with_examples(description: &str, examples: &[Value]) -> String {
+    if examples.is_empty() {
+        return description.to_string();
+    }
+
+    let rendered: Vec<String> = examples
+        .iter()
+        .map(|e| format!("- {}", serde_json::to_string(e).unwrap_or_default()))
+        .collect();
+    format!(
+        "{}\n\nExample arguments:\n{}",
+        description,
+        rendered.join("\n")
+    )
+}
+
 pub async fn call_local_tool(
     registry: &LocalToolRegistry,
     tool_name: &str,
@@ -28,11 +119,7 @@ pub async fn call_local_tool(
     if settings.verbose {
         let args_str = serde_json::to_string(arguments)
             .unwrap_or_else(|_| "<invalid json>".to_string());
-        let truncated = if args_str.len() > 200 {
-            format!("{}...", &args_str[..200])
-        } else {
-            args_str
-        };
+        let truncated = truncate_for_display(&args_str, 200);
         eprintln!(
             "{}",
             format!("[tools] Calling tool '{}' with args: {}", tool_name, truncated).dimmed()
@@ -47,6 +134,17 @@ pub async fn call_local_tool(
         .get(tool_name)
         .ok_or_else(|| format!("Tool '{}' not found", tool_name))?;
 
+    // Wait for a free slot when the tool limits concurrent calls
+    let _permit = match registry.concurrency_limit(tool_name) {
+        Some(limit) => Some(
+            limit
+                .acquire()
+                .await
+                .map_err(|e| format!("Tool '{}' is unavailable: {}", tool_name, e))?,
+        ),
+        None => None,
+    };
+
     // Call the handler (now async)
     let handler = &tool.handler;
     handler(arguments, registry.settings()).await
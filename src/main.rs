@@ -1,27 +1,33 @@
-mod api;
-mod cli;
-mod config;
-mod error;
-mod local_tools;
-mod models;
-mod orchestrator;
-mod session;
-mod ui;
-
 use clap::Parser;
 use colored::*;
+use futures::StreamExt;
 use std::process;
 
-use cli::Args;
-use config::Config;
-use local_tools::LocalSettings;
-use local_tools::LocalToolRegistry;
-use models::Message;
-use orchestrator::{run, OrchestratorContext};
-use session::{
-    clear_all_sessions, create_new_session, find_recent_session, save_session,
-    trim_conversation_history,
+use cmd2ai::api::{check_endpoint, fetch_models, format_model_list};
+use cmd2ai::batch::{parse_batch, run_prompts, BatchPrompt, BatchRecord};
+use cmd2ai::cli::Args;
+use cmd2ai::config::{
+    load_env_file, route_model, Config, JsonConfig, CONFIG_SCHEMA, DEFAULT_API_ENDPOINT,
+};
+use cmd2ai::doctor::{format_check, run_doctor};
+use cmd2ai::error::Cmd2AiError;
+use cmd2ai::local_tools::cleanup_temp_scripts;
+use cmd2ai::local_tools::{format_tool_list, format_tools_for_llm};
+use cmd2ai::models::Message;
+use cmd2ai::orchestrator::{run, trim_history, OrchestratorContext};
+use cmd2ai::session::{
+    apply_system_prompt, clear_all_sessions, create_new_session, find_recent_session,
+    merge_consecutive_messages, prune_sessions, save_session,
 };
+use cmd2ai::ui::display_content;
+use cmd2ai::util::{
+    copy_to_clipboard, strip_markdown, truncate_for_display, wrap_command, write_answer,
+};
+
+/// Timeout for the `--check-endpoint` preflight request
+const ENDPOINT_CHECK_TIMEOUT_SECS: u64 = 5;
+/// Timeout for the `--models` listing (OpenRouter's list is a few hundred KB)
+const MODEL_LIST_TIMEOUT_SECS: u64 = 30;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -41,6 +47,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Handle --prune-sessions option (reads only the config file, so no API key is needed)
+    if args.prune_sessions {
+        // Deleting files on a misread limit would be worse than not pruning
+        let max_files = match JsonConfig::load() {
+            Ok(json_config) => json_config.session.max_files(),
+            Err(e) => {
+                eprintln!("{} {:#}", "Error:".red(), e);
+                process::exit(1);
+            }
+        };
+        if max_files == 0 {
+            println!("session.max_files is 0, so all sessions are kept.");
+            return Ok(());
+        }
+        match prune_sessions(max_files) {
+            Ok(removed) => {
+                println!(
+                    "{}",
+                    format!("Removed {} old session file(s).", removed).green()
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{}", format!("Error pruning sessions: {}", e).red());
+                process::exit(1);
+            }
+        }
+    }
+
+    // Handle --config-schema option
+    if args.config_schema {
+        print!("{}", CONFIG_SCHEMA);
+        return Ok(());
+    }
+
     // Handle --config-init option
     if args.config_init {
         let example_config = include_str!("../config.example.yaml");
@@ -69,15 +110,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    if args.command.is_empty() {
+    let listing_only = args.explain_tools
+        || args.list_tools
+        || args.list_models
+        || args.doctor
+        || args.batch.is_some();
+    if args.command.is_empty() && !listing_only {
         print_usage();
         process::exit(1);
     }
 
-    let command = args.command.join(" ");
+    let typed = args.command.join(" ");
+    let mut command = wrap_command(&typed, args.prepend.as_deref(), args.append.as_deref());
+
+    // Load the env file first so its variables (API key, AI_MODEL, ...) feed the config
+    let env_file = args
+        .env_file
+        .clone()
+        .or_else(|| std::env::var_os("AI_ENV_FILE").map(std::path::PathBuf::from));
+    if let Some(path) = env_file {
+        if let Err(e) = load_env_file(&path) {
+            eprintln!("{} {}", "Error:".red(), e);
+            process::exit(1);
+        }
+    }
+
+    // Handle --doctor option (after the env file, which may provide the API key)
+    if args.doctor {
+        let checks = run_doctor(&args, args.full).await;
+        for check in &checks {
+            println!("{}", format_check(check));
+        }
+        let failed = checks.iter().filter(|c| !c.passed).count();
+        if failed > 0 {
+            println!("\n{} of {} checks failed.", failed, checks.len());
+            process::exit(1);
+        }
+        println!("\n{}", "All checks passed.".green());
+        return Ok(());
+    }
 
     // Load configuration
-    let config = match Config::from_env_and_args(&args) {
+    let mut config = match Config::from_env_and_args(&args) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("{} {}", "Error:".red(), e);
@@ -85,44 +159,112 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let _final_model = config.model.clone();
+    // Routing looks at what the user typed, before --prepend/--append or --prompt wrap it
+    if let Some((rule, model)) = route_model(&config.routes, &typed) {
+        if config.verbose {
+            eprintln!(
+                "{}",
+                format!("[AI] Routing rule {} selected model: {}", rule, model).dimmed()
+            );
+        }
+        config.model = model.to_string();
+    }
+
+    if let Some(name) = args.prompt.as_deref() {
+        command = match config.render_prompt_template(name, &command) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                process::exit(1);
+            }
+        };
+    }
+
+    // A blank message (e.g. `ai "$UNSET_VAR"`) would only waste a request
+    if command.trim().is_empty() && !listing_only {
+        eprintln!(
+            "{} Nothing to send: the message is empty or whitespace. Check the quoting of \
+             your command, or add text with --prepend/--append.",
+            "Error:".red()
+        );
+        process::exit(1);
+    }
+
+    // The same setup `cmd2ai::query` uses: client, local tools (unless disabled),
+    // rate limiter and render options
+    let context = match OrchestratorContext::new(config, args) {
+        Ok(context) => context,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            process::exit(1);
+        }
+    };
+
+    // Handle --list-tools option (needs the resolved config, but never calls the API)
+    if context.args.list_tools {
+        match context.local_tools_registry.as_ref() {
+            Some(registry) if !registry.list().is_empty() => {
+                print!("{}", format_tool_list(registry))
+            }
+            _ => eprintln!("{}", "No tools are enabled.".yellow()),
+        }
+        return Ok(());
+    }
+
+    // Handle --explain-tools option (needs the resolved config, but never calls the API)
+    if context.args.explain_tools {
+        let tools = context
+            .local_tools_registry
+            .as_ref()
+            .map(format_tools_for_llm)
+            .unwrap_or_default();
+        if tools.is_empty() {
+            eprintln!("{}", "No tools are enabled.".yellow());
+        }
+        match serde_json::to_string_pretty(&tools) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("{} Failed to serialize tools: {}", "Error:".red(), e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
     // Load or create session
-    let mut session = if args.new_conversation {
+    let mut session = if context.args.new_conversation {
         create_new_session()
     } else {
         let existing_session = find_recent_session();
 
-        if args.force_continue && existing_session.is_some() {
-            existing_session.unwrap()
-        } else {
-            existing_session.unwrap_or_else(create_new_session)
+        match existing_session {
+            Some(session) if context.args.force_continue => session,
+            other => other.unwrap_or_else(create_new_session),
         }
     };
 
-    // Build messages array
-    let mut messages = session.messages.clone();
-
-    // Add system message if this is a new conversation or no system message exists
-    if messages.is_empty() || messages.first().map(|m| &m.role) != Some(&"system".to_string()) {
-        let date_prompt = format!("Today's date is {}.", Config::get_current_date());
-        let system_content = if let Some(prompt) = &config.system_prompt {
-            format!("{}\n\n{}", date_prompt, prompt)
-        } else {
-            date_prompt
-        };
-
-        messages.insert(
-            0,
-            Message {
-                role: "system".to_string(),
-                content: Some(system_content),
-                tool_calls: None,
-                tool_call_id: None,
-            },
+    // Resolve the system prompt (session > config, --system/--append-system override)
+    let date_prompt = context.config.date_prompt();
+    let config_prompt = context.config.expanded_system_prompt();
+    if !context.args.no_system {
+        apply_system_prompt(
+            &mut session,
+            config_prompt.as_deref(),
+            context.args.system.as_deref(),
+            context.args.append_system.as_deref(),
+            date_prompt.as_deref(),
         );
     }
 
+    // Build messages array; --no-system leaves out the system turn, but the session keeps
+    // its prompt for the next run
+    let mut messages: Vec<Message> = session
+        .messages
+        .iter()
+        .filter(|m| !(context.args.no_system && m.role == "system"))
+        .cloned()
+        .collect();
+
     // Add user message
     messages.push(Message {
         role: "user".to_string(),
@@ -131,13 +273,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tool_call_id: None,
     });
 
-    // Trim history if needed
-    trim_conversation_history(&mut messages);
-
-    // Log reasoning configuration before moving it
-    if config.verbose && config.reasoning.is_some() {
+    // Log reasoning configuration
+    if context.config.verbose && context.config.reasoning.is_some() {
         eprintln!("{}", "[AI] Reasoning: enabled".dimmed());
-        if let Some(ref reasoning) = config.reasoning {
+        if let Some(ref reasoning) = context.config.reasoning {
             if let Some(ref effort) = reasoning.effort {
                 eprintln!("{}", format!("[AI] Reasoning effort: {}", effort).dimmed());
             }
@@ -153,46 +292,141 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Get available tools unless explicitly disabled
-    let local_tools_enabled =
-        config.tools_enabled && config.local_tools_config.enabled && !args.no_tools;
+    let script_dir = context
+        .local_tools_registry
+        .as_ref()
+        .map(|registry| registry.settings().script_dir.clone());
+    let save_answer = context.args.save_answer.clone();
+    let append_answer = context.args.append_answer;
+    let copy_answer = context.args.copy;
+    let store_plain = context.config.store_plain;
+    let no_system = context.args.no_system;
+    let max_session_files = context.config.max_session_files;
 
-    // Create local tools registry if enabled
-    let local_tools_registry = if local_tools_enabled {
-        let settings = LocalSettings::from_config(&config.local_tools_config, config.verbose);
-        Some(LocalToolRegistry::new(&config.local_tools_config, settings))
-    } else {
-        None
-    };
+    // Handle --models option
+    if context.args.list_models {
+        let timeout = std::time::Duration::from_secs(MODEL_LIST_TIMEOUT_SECS);
+        match fetch_models(&context.client, &context.config.api_endpoint, timeout).await {
+            Ok(models) => print!("{}", format_model_list(&models)),
+            Err(e) => {
+                eprintln!("{} Failed to list models: {}", "Error:".red(), e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
-    // Create orchestrator context
-    let context = OrchestratorContext {
-        config,
-        args,
-        local_tools_registry,
-    };
+    // Preflight: on request, or automatically for custom endpoints in verbose mode
+    let config = &context.config;
+    let custom_endpoint = config.api_endpoint != DEFAULT_API_ENDPOINT;
+    if context.args.check_endpoint || (config.verbose && custom_endpoint) {
+        let timeout = std::time::Duration::from_secs(ENDPOINT_CHECK_TIMEOUT_SECS);
+        if let Err(e) =
+            check_endpoint(&context.client, &config.api_endpoint, timeout, config.verbose).await
+        {
+            eprintln!("{} {}", "Error:".red(), e);
+            process::exit(1);
+        }
+    }
+
+    let batch = context.args.batch.clone();
+
+    // Handle --batch option: every prompt gets a fresh conversation and nothing is saved
+    if let Some(path) = batch {
+        let mut batch_session = create_new_session();
+        if !context.args.no_system {
+            apply_system_prompt(
+                &mut batch_session,
+                config_prompt.as_deref(),
+                context.args.system.as_deref(),
+                context.args.append_system.as_deref(),
+                date_prompt.as_deref(),
+            );
+        }
+        let context = OrchestratorContext {
+            quiet: context.args.json || context.args.batch_concurrency > 1,
+            ..context
+        };
+        let all_ok = run_batch_file(&context, &path, &batch_session.messages).await;
+        if let Some(script_dir) = script_dir {
+            let _ = cleanup_temp_scripts(&script_dir);
+        }
+        process::exit(if all_ok { 0 } else { 1 });
+    }
+
+    // Trim history if needed (summarizing it first with --summarize-history), then collapse
+    // adjacent same-role messages (e.g. two system messages) that some providers reject
+    trim_history(&context, &mut messages).await;
+    merge_consecutive_messages(&mut messages);
 
     // Run orchestrator (pass mutable reference so it can modify messages with tool calls)
-    let assistant_response = match run(context, &mut messages).await {
-        Ok(response) => response,
+    // Ctrl-C inside the stream is handled there (partial text is returned); this catches
+    // interrupts during the non-streaming phases
+    let result = tokio::select! {
+        biased;
+        result = run(&context, &mut messages) => result,
+        _ = tokio::signal::ctrl_c() => Err(Cmd2AiError::Interrupted { partial: String::new() }),
+    };
+
+    let (assistant_response, request_id, interrupted) = match result {
+        Ok(outcome) => (outcome.content, outcome.request_id, false),
+        Err(Cmd2AiError::Interrupted { partial }) => {
+            eprintln!("{}", "Interrupted.".yellow());
+            (partial, None, true)
+        }
         Err(e) => {
             eprintln!("{} {}", "Error:".red(), e);
             process::exit(1);
         }
     };
 
+    // Write the plain answer alongside the rendered output
+    if let Some(path) = save_answer.filter(|_| !interrupted) {
+        if let Err(e) = write_answer(&path, &assistant_response, append_answer) {
+            eprintln!(
+                "{} Failed to save answer to {}: {}",
+                "Error:".red(),
+                path.display(),
+                e
+            );
+        }
+    }
+
+    if copy_answer && !interrupted && !assistant_response.is_empty() {
+        if let Err(e) = copy_to_clipboard(&assistant_response) {
+            eprintln!(
+                "{}",
+                format!("Warning: Could not copy answer: {}", e).yellow()
+            );
+        }
+    }
+
     // Save session with assistant's response
     if !assistant_response.is_empty() {
+        // session.store_plain keeps Markdown noise out of the history sent back to the model
+        let stored_response = if store_plain {
+            strip_markdown(&assistant_response)
+        } else {
+            assistant_response
+        };
+        // The system message left out by --no-system stays in the session
+        let stored_system = session
+            .messages
+            .first()
+            .filter(|m| no_system && m.role == "system")
+            .cloned();
         session.messages = messages;
+        session.messages.splice(0..0, stored_system);
         session.messages.push(Message {
             role: "assistant".to_string(),
-            content: Some(assistant_response),
+            content: Some(stored_response),
             tool_calls: None,
             tool_call_id: None,
         });
         session.last_updated = chrono::Local::now();
+        session.last_request_id = request_id;
 
-        if let Err(e) = save_session(&session) {
+        if let Err(e) = save_session(&session, max_session_files) {
             // Note: config is moved into context, so we can't access verbose here
             // This is acceptable as session save errors are non-critical
                 eprintln!(
@@ -202,9 +436,156 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    if interrupted {
+        // Inline scripts may have been left behind mid-execution
+        if let Some(script_dir) = script_dir {
+            let _ = cleanup_temp_scripts(&script_dir);
+        }
+        process::exit(130);
+    }
+
     Ok(())
 }
 
+/// Run every prompt of a `--batch` file, up to --batch-concurrency at a time, printing
+/// rendered answers under a header (or one JSON line each with --json) in file order.
+/// Returns false if any prompt failed.
+async fn run_batch_file(
+    context: &OrchestratorContext,
+    path: &std::path::Path,
+    system_messages: &[Message],
+) -> bool {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!(
+                "{} Failed to read batch file {}: {}",
+                "Error:".red(),
+                path.display(),
+                e
+            );
+            return false;
+        }
+    };
+    let prompts = parse_batch(&contents);
+    if prompts.is_empty() {
+        eprintln!("{} No prompts in {}", "Error:".red(), path.display());
+        return false;
+    }
+
+    // --prepend/--append and --prompt frame each prompt as they would a command, and routing
+    // rules pick its model from the prompt as written
+    let mut framed = Vec::with_capacity(prompts.len());
+    for (index, prompt) in prompts.iter().enumerate() {
+        let model = match route_model(&context.config.routes, prompt) {
+            Some((rule, model)) => {
+                if context.config.verbose {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "[AI] Prompt {}: routing rule {} selected model: {}",
+                            index + 1,
+                            rule,
+                            model
+                        )
+                        .dimmed()
+                    );
+                }
+                model.to_string()
+            }
+            None => context.config.model.clone(),
+        };
+        let mut message = wrap_command(
+            prompt,
+            context.args.prepend.as_deref(),
+            context.args.append.as_deref(),
+        );
+        if let Some(name) = context.args.prompt.as_deref() {
+            match context.config.render_prompt_template(name, &message) {
+                Ok(rendered) => message = rendered,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    return false;
+                }
+            }
+        }
+        framed.push(BatchPrompt { message, model });
+    }
+
+    // One prompt at a time streams its answer live; in parallel, answers are rendered
+    // whole once they (and every prompt before them) are done
+    let concurrency = usize::from(context.args.batch_concurrency);
+    let live = !context.args.json && concurrency == 1;
+    let total = prompts.len();
+
+    let mut results = run_prompts(context, system_messages, &framed, concurrency, |index| {
+        if live {
+            print_batch_header(index, total, &prompts[index]);
+        }
+    });
+
+    let mut all_ok = true;
+    loop {
+        let (index, result) = tokio::select! {
+            biased;
+            next = results.next() => match next {
+                Some(next) => next,
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("{}", "Interrupted.".yellow());
+                return false;
+            }
+        };
+
+        if result.is_err() {
+            all_ok = false;
+        }
+        if context.args.json {
+            let (outcome, error) = match result {
+                Ok(outcome) => (Some(outcome), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let record = BatchRecord {
+                index,
+                prompt: prompts[index].clone(),
+                outcome,
+                error,
+            };
+            match serde_json::to_string(&record) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("{} Failed to serialize result: {}", "Error:".red(), e),
+            }
+            continue;
+        }
+
+        if !live {
+            print_batch_header(index, total, &prompts[index]);
+        }
+        match result {
+            Ok(outcome) if !live => display_content(&outcome.content, &context.render_options),
+            Ok(_) => {}
+            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+        }
+    }
+    all_ok
+}
+
+fn print_batch_header(index: usize, total: usize, prompt: &str) {
+    let title = prompt.lines().next().unwrap_or_default();
+    println!(
+        "{}",
+        format!(
+            "=== [{}/{}] {}",
+            index + 1,
+            total,
+            truncate_for_display(title, 60)
+        )
+        .cyan()
+        .bold()
+    );
+}
+
 fn print_usage() {
     eprintln!("{}", "Usage: ai [OPTIONS] <command>".red());
     eprintln!(
@@ -219,6 +600,40 @@ fn print_usage() {
         "{}",
         "      --clear                Clear all conversation history".dimmed()
     );
+    eprintln!(
+        "{}",
+        "      --doctor [--full]      Diagnose setup problems (--full also tries the model)"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --batch <FILE> [--json] Run each prompt in FILE in a fresh conversation".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --batch-concurrency <N> With --batch, run up to N prompts at once".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --prune-sessions       Delete the oldest session files beyond session.max_files"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --system <PROMPT>      Set the system prompt for this conversation".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --append-system <TEXT> Append to the system prompt for this conversation".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --no-date              Don't prepend today's date to the system prompt".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --no-system            Send no system message at all, not even the date".dimmed()
+    );
     eprintln!(
         "{}",
         "      --reasoning-effort     Set reasoning effort level (high, medium, low)".dimmed()
@@ -235,18 +650,95 @@ fn print_usage() {
         "{}",
         "      --reasoning-enabled    Enable reasoning with default parameters".dimmed()
     );
+    eprintln!(
+        "{}",
+        "      --frequency-penalty    Penalize frequently repeated tokens (-2.0 to 2.0)".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --presence-penalty     Penalize tokens that already appeared (-2.0 to 2.0)".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --reasoning-to-stderr  Print reasoning to stderr, the answer to stdout".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --width <N>            Render code blocks N columns wide (AI_WIDTH)".dimmed()
+    );
     eprintln!(
         "{}",
         "      --no-tools             Disable all tools for this query".dimmed()
     );
+    eprintln!(
+        "{}",
+        "      --tool-timeout <SECS>  Timeout for every tool in this run (AI_TOOL_TIMEOUT)".dimmed()
+    );
     eprintln!(
         "{}",
         "      --config-init          Initialize a config file with example local tools".dimmed()
     );
+    eprintln!(
+        "{}",
+        "      --config-schema        Print a JSON Schema for the config file".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --list-tools           List available tools and their arguments, then exit".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --models               List the models offered by the API endpoint, then exit".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --explain-tools        Print the tool definitions sent to the model and exit"
+            .dimmed()
+    );
     eprintln!(
         "{}",
         "      --api-endpoint         Custom API base URL (e.g., http://localhost:11434/v1)"
             .dimmed()
     );
+    eprintln!(
+        "{}",
+        "      --env-file <PATH>      Load variables from a .env file (existing ones win)".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --check-endpoint       Check the API endpoint is reachable before the request"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --save-answer <PATH>   Also write the plain answer to a file (--append-answer to add)"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --prompt <NAME>        Use a named template from the prompts: config section"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --prepend <TEXT>       Put text before the command (e.g. \"Answer concisely:\")"
+            .dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --append <TEXT>        Put text after the command".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --stats                Print timing and throughput after the response".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --no-incremental       Render a streamed answer once it is complete".dimmed()
+    );
+    eprintln!(
+        "{}",
+        "      --copy                 Copy the plain answer to the clipboard".dimmed()
+    );
     eprintln!("{}", "  -h, --help                 Print help".dimmed());
 }
@@ -3,6 +3,6 @@ mod session;
 mod tool;
 
 pub use reasoning::Reasoning;
-pub use session::{Message, Session};
-pub use tool::ToolCall;
+pub use session::{validate_message_sequence, Message, Session};
+pub use tool::{FunctionCall, ToolCall};
 
@@ -1,3 +1,4 @@
+use crate::error::{Cmd2AiError, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -16,5 +17,87 @@ pub struct Session {
     pub session_id: String,
     pub last_updated: chrono::DateTime<chrono::Local>,
     pub messages: Vec<Message>,
+    /// Effective system prompt (without the date line), kept across continues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Provider request/generation id of the latest answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_request_id: Option<String>,
 }
 
+
+/// Check the history the way providers do before sending it: one system message, first;
+/// known roles; and every `tool` message answering a call of the assistant message right
+/// before it (each call answered exactly once). Errors name the offending message, from 1.
+pub fn validate_message_sequence(messages: &[Message]) -> Result<()> {
+    let invalid = |index: usize, message: &Message, problem: String| {
+        Err(Cmd2AiError::SessionError(format!(
+            "Invalid conversation history: message {} ({}) {}; start a new conversation with --new",
+            index + 1,
+            message.role,
+            problem
+        )))
+    };
+
+    // Calls of the latest assistant tool turn that still await a result
+    let mut pending: Vec<&str> = Vec::new();
+    let mut pending_from = 0;
+
+    for (index, message) in messages.iter().enumerate() {
+        if message.role != "tool" && !pending.is_empty() {
+            return invalid(
+                pending_from,
+                &messages[pending_from],
+                format!("has no tool result for call '{}'", pending[0]),
+            );
+        }
+
+        match message.role.as_str() {
+            "system" if index > 0 => {
+                let problem = if messages[index - 1].role == "system" {
+                    "is a second system message".to_string()
+                } else {
+                    "comes after the conversation started".to_string()
+                };
+                return invalid(index, message, problem);
+            }
+            "system" | "user" => {}
+            "assistant" => {
+                if let Some(ref calls) = message.tool_calls {
+                    pending = calls.iter().map(|call| call.id.as_str()).collect();
+                    pending_from = index;
+                }
+            }
+            "tool" => {
+                let Some(ref id) = message.tool_call_id else {
+                    return invalid(index, message, "has no tool_call_id".to_string());
+                };
+                match pending.iter().position(|call| call == id) {
+                    Some(position) => {
+                        pending.remove(position);
+                    }
+                    None => {
+                        return invalid(
+                            index,
+                            message,
+                            format!(
+                                "answers tool call '{}', which the assistant message before it did not make",
+                                id
+                            ),
+                        );
+                    }
+                }
+            }
+            other => return invalid(index, message, format!("has unknown role '{}'", other)),
+        }
+    }
+
+    if let Some(call) = pending.first() {
+        return invalid(
+            pending_from,
+            &messages[pending_from],
+            format!("has no tool result for call '{}'", call),
+        );
+    }
+    Ok(())
+}
@@ -1,34 +1,377 @@
-use crate::api::{make_api_request, process_streaming_response, RequestBody};
-use crate::api::response::{extract_content, extract_reasoning, parse_tool_calls};
+use crate::api::{
+    build_client, fetch_models_cached, make_api_request, process_streaming_response,
+    request_id_from_headers, suggest_models, Citation, RateLimiter, RequestBody, StreamSettings,
+    Usage,
+};
+use crate::api::response::{
+    extract_citations, extract_content, extract_finish_reason, extract_reasoning, extract_refusal,
+    extract_usage, parse_tool_calls, repair_tool_arguments,
+};
+use crate::api::streaming::StreamingResult;
 use crate::cli::Args;
-use crate::config::Config;
+use crate::config::{Config, DEFAULT_FLUSH_INTERVAL_MS};
 use crate::error::{Cmd2AiError, Result};
-use crate::local_tools::{call_local_tool, format_tools_for_llm, LocalToolRegistry};
-use crate::models::Message;
-use crate::ui::{display_content, display_reasoning, display_tool_error, display_tool_result};
+use crate::local_tools::{
+    call_local_tool, format_tools_for_llm, limit_exposed_tools, LocalSettings, LocalToolRegistry,
+};
+use crate::models::{validate_message_sequence, FunctionCall, Message, ToolCall};
+use crate::session::{
+    estimate_tokens, fit_context_window, history_transcript, insert_history_summary,
+    messages_to_trim, trim_conversation_history, MAX_SUMMARY_INPUT_TOKENS,
+};
+use crate::ui::{
+    display_content, display_reasoning, display_tool_error, display_tool_result, RenderOptions,
+    Spinner,
+};
+use crate::util::{merge_json, shorten_middle, truncate_for_display};
 use colored::*;
+use serde::Serialize;
 use serde_json::Value;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Budget for fetching the model list when suggesting alternatives to an unknown model
+const MODEL_SUGGESTION_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct OrchestratorContext {
     pub config: Config,
+    /// HTTP client reused for every request in the run (see `api::build_client`)
+    pub client: reqwest::Client,
     pub args: Args,
     pub local_tools_registry: Option<LocalToolRegistry>,
+    pub render_options: RenderOptions,
+    /// Paces API requests when api.rate_limit_rpm is set
+    pub rate_limiter: Option<RateLimiter>,
+    /// Render nothing to stdout (answer, reasoning, tool boxes); used by `cmd2ai::query`
+    pub quiet: bool,
+}
+
+impl OrchestratorContext {
+    /// Everything a run needs for `config` and `args`: the HTTP client, the local tools
+    /// registry (unless tools are disabled or `--no-tools` is given), the rate limiter and
+    /// the render options. Used by both the `ai` binary and `cmd2ai::query`.
+    pub fn new(config: Config, args: Args) -> Result<Self> {
+        let client = build_client(&config)?;
+        let local_tools_registry =
+            if config.tools_enabled && config.local_tools_config.enabled && !args.no_tools {
+                let settings = LocalSettings {
+                    timeout_override: config.tool_timeout,
+                    ..LocalSettings::from_config(&config.local_tools_config, config.verbose)
+                };
+                Some(LocalToolRegistry::new(&config.local_tools_config, settings))
+            } else {
+                None
+            };
+        let render_options = RenderOptions {
+            reasoning_to_stderr: args.reasoning_to_stderr,
+            width: config.render_width,
+            ..RenderOptions::from_config(&config.ui_config)
+        };
+        let rate_limiter = config.rate_limit_rpm.map(RateLimiter::per_minute);
+
+        Ok(Self {
+            config,
+            client,
+            args,
+            local_tools_registry,
+            render_options,
+            rate_limiter,
+            quiet: false,
+        })
+    }
+
+    /// Where rendered output goes: stdout, or nowhere when quiet
+    fn output(&self) -> Box<dyn Write> {
+        if self.quiet {
+            Box::new(io::sink())
+        } else {
+            Box::new(io::stdout())
+        }
+    }
+
+    fn stream_settings(&self, request_started: Instant, model: &str) -> StreamSettings {
+        StreamSettings {
+            timeout_secs: self.config.stream_timeout,
+            reasoning_exclude: self.args.reasoning_exclude,
+            verbose: self.config.verbose,
+            show_stats: self.args.stats,
+            request_started,
+            answer_header: self.render_options.answer_header(model),
+            flush_interval: Duration::from_millis(
+                self.config
+                    .ui_config
+                    .flush_interval_ms
+                    .unwrap_or(DEFAULT_FLUSH_INTERVAL_MS),
+            ),
+            incremental: !self.args.no_incremental,
+            quiet: self.quiet,
+        }
+    }
+
+    /// Request body as sent: `body`, with the --raw-request overrides merged over it
+    fn request_json(&self, body: &RequestBody) -> Result<Value> {
+        let mut json = serde_json::to_value(body)?;
+        if let Some(ref raw_request) = self.config.raw_request {
+            merge_json(&mut json, raw_request);
+        }
+        Ok(json)
+    }
+
+    /// A tool result as sent back: shortened to its head and tail when it is over
+    /// tools.summarize_large_results, so the follow-up request fits the context
+    fn result_for_model(&self, name: &str, result: String) -> String {
+        match self.config.large_tool_result_bytes {
+            Some(max_bytes) if result.len() > max_bytes => {
+                if self.config.verbose {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "[tools] Result of '{}' is {} bytes; sending {} bytes of its \
+                             beginning and end",
+                            name,
+                            result.len(),
+                            max_bytes
+                        )
+                        .dimmed()
+                    );
+                }
+                shorten_middle(&result, max_bytes)
+            }
+            _ => result,
+        }
+    }
+
+    /// Spinner for the wait before the first token; not drawn when quiet, or in verbose
+    /// mode where it would tangle with the logs
+    fn waiting_spinner(&self) -> Spinner {
+        Spinner::start(!self.quiet && !self.config.verbose, "Waiting for response...")
+    }
+
+    fn show_tool_result(&self, name: &str, result: &str) {
+        if !self.quiet {
+            display_tool_result(name, result, &self.render_options);
+        }
+    }
+
+    fn show_tool_error(&self, name: &str, error: &str) {
+        if !self.quiet {
+            display_tool_error(name, error, &self.render_options);
+        }
+    }
+}
+
+/// A tool call made on the model's behalf during a run
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInvocation {
+    pub id: String,
+    pub name: String,
+    /// Parsed arguments, or null when they could not be parsed
+    pub arguments: Value,
+    /// Text sent back to the model (the error message for failed calls)
+    pub result: String,
+    pub is_error: bool,
 }
 
-pub async fn run(context: OrchestratorContext, messages: &mut Vec<Message>) -> Result<String> {
-    let final_model = context.config.model.clone();
+/// Everything a run produced: the final answer plus what led to it
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryOutcome {
+    pub content: String,
+    /// Reasoning tokens returned by the model, if any
+    pub reasoning: Option<String>,
+    /// Tools called before the final answer, in call order
+    pub tool_calls: Vec<ToolInvocation>,
+    pub citations: Vec<Citation>,
+    /// Token usage of the final response, when the provider reports it
+    pub usage: Option<Usage>,
+    pub finish_reason: Option<String>,
+    /// Generation/request id reported by the provider, useful for support tickets
+    pub request_id: Option<String>,
+}
+
+impl QueryOutcome {
+    fn from_stream(result: StreamingResult, request_id: Option<String>) -> Self {
+        Self {
+            content: result.content,
+            reasoning: result.reasoning,
+            tool_calls: Vec::new(),
+            citations: result.citations,
+            usage: result.usage,
+            finish_reason: result.finish_reason,
+            request_id: result.generation_id.or(request_id),
+        }
+    }
+}
+
+/// After a model-not-found error, print the closest ids from the endpoint's model list.
+/// Best effort: nothing is printed when the list cannot be fetched.
+async fn suggest_similar_models(context: &OrchestratorContext, model: &str) {
+    let Ok(models) = fetch_models_cached(
+        &context.client,
+        &context.config.api_endpoint,
+        MODEL_SUGGESTION_TIMEOUT,
+    )
+    .await
+    else {
+        return;
+    };
+
+    let message = match suggest_models(&models, model, 3).as_slice() {
+        [] => return,
+        [only] => format!("Model '{}' not found; did you mean '{}'?", model, only),
+        several => format!(
+            "Model '{}' not found; did you mean one of: {}?",
+            model,
+            several.join(", ")
+        ),
+    };
+    eprintln!("{}", message.yellow());
+}
+
+/// Ask for a short summary of the conversation `history` in one non-streamed request
+pub async fn summarize_history(
+    context: &OrchestratorContext,
+    history: &[Message],
+) -> Result<String> {
+    let transcript = history_transcript(history, MAX_SUMMARY_INPUT_TOKENS);
+    let model = context
+        .config
+        .summary_model
+        .clone()
+        .unwrap_or_else(|| context.config.model.clone());
+    let request_body = RequestBody {
+        model,
+        messages: vec![
+            Message {
+                role: "system".to_string(),
+                content: Some(
+                    "Summarize the conversation you are given so it can stand in for it later. \
+                     Keep facts, decisions, names, numbers, code identifiers and open questions; \
+                     leave out pleasantries. Answer with the summary only, in at most 200 words."
+                        .to_string(),
+                ),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+            Message {
+                role: "user".to_string(),
+                content: Some(transcript),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        ],
+        stream: false,
+        reasoning: None,
+        tools: None,
+        logit_bias: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+    };
+
+    let response = make_api_request(
+        &context.client,
+        &context.config.api_endpoint,
+        &context.request_json(&request_body)?,
+        context.rate_limiter.as_ref(),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(Cmd2AiError::ApiError { status, message });
+    }
 
-    // Get available tools unless explicitly disabled
-    let _local_tools_enabled = context.config.tools_enabled
-        && context.config.local_tools_config.enabled
-        && !context.args.no_tools;
+    let response_json: Value = response.json().await?;
+    match extract_content(&response_json)? {
+        Some(summary) if !summary.trim().is_empty() => Ok(summary),
+        _ => Err(Cmd2AiError::Other(
+            "The summary request returned no text".to_string(),
+        )),
+    }
+}
 
+/// Trim the history, first replacing what is dropped with a summary when
+/// --summarize-history / session.summarize is on. A failed summary falls back to a plain trim.
+pub async fn trim_history(context: &OrchestratorContext, messages: &mut Vec<Message>) {
+    let dropped = messages_to_trim(messages);
+    let summary = if context.config.summarize_history && !dropped.is_empty() {
+        if context.config.verbose {
+            eprintln!(
+                "{}",
+                format!("[AI] Summarizing {} older message(s)...", dropped.len()).dimmed()
+            );
+        }
+        match summarize_history(context, &dropped).await {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: Could not summarize older history ({}); dropping it instead",
+                        e
+                    )
+                    .yellow()
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    trim_conversation_history(messages);
+    if let Some(summary) = summary {
+        insert_history_summary(messages, &summary);
+    }
+}
+
+pub async fn run(
+    context: &OrchestratorContext,
+    messages: &mut Vec<Message>,
+) -> Result<QueryOutcome> {
+    run_with_model(context, messages, &context.config.model).await
+}
+
+/// `run`, asking `model` first instead of the configured model (e.g. the model a routing
+/// rule picked for one `--batch` prompt)
+pub async fn run_with_model(
+    context: &OrchestratorContext,
+    messages: &mut Vec<Message>,
+    model: &str,
+) -> Result<QueryOutcome> {
     // Collect tools from local tools
     let mut all_tools = Vec::new();
 
     // Add local tools
-    if let Some(ref registry) = context.local_tools_registry {
-        let local_tools = format_tools_for_llm(registry);
+    if let Some(registry) = context.local_tools_registry.as_ref() {
+        let mut local_tools = format_tools_for_llm(registry);
+        // Large tool lists bloat the prompt and hurt tool selection
+        if let Some(max) = context.config.max_exposed_tools {
+            // Rank by the prompt actually sent: batch prompts, expanded --prompt templates
+            // and library callers have no command line to go by
+            let query = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == "user")
+                .and_then(|m| m.content.as_deref())
+                .unwrap_or_default();
+            let (kept, omitted) = limit_exposed_tools(local_tools, max, query);
+            if !omitted.is_empty() {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: Exposing {} of {} tools (tools.max_exposed); omitted: {}",
+                        kept.len(),
+                        kept.len() + omitted.len(),
+                        omitted.join(", ")
+                    )
+                    .yellow()
+                );
+            }
+            local_tools = kept;
+        }
         if !local_tools.is_empty() {
             if context.config.verbose {
                 let tool_names: Vec<String> = registry.list().iter().map(|t| t.name.clone()).collect();
@@ -41,7 +384,7 @@ pub async fn run(context: OrchestratorContext, messages: &mut Vec<Message>) -> R
                     )
                     .dimmed()
                 );
-            } else {
+            } else if !context.quiet {
                 println!(
                     "{}",
                     format!("Available local tools: {}", local_tools.len()).cyan()
@@ -58,16 +401,10 @@ pub async fn run(context: OrchestratorContext, messages: &mut Vec<Message>) -> R
     };
 
     // Use non-streaming when tools are available for proper tool handling
-    // OpenRouter's streaming API doesn't properly stream tool call arguments
-    let use_streaming = tools.is_none();
-
-    let request_body = RequestBody {
-        model: final_model.clone(),
-        messages: messages.to_vec(),
-        stream: use_streaming,
-        reasoning: context.config.reasoning.clone(),
-        tools: tools.clone(),
-    };
+    // OpenRouter's streaming API doesn't properly stream tool call arguments;
+    // --stream/--no-stream (api.stream) override the automatic choice; a forced stream
+    // still offers the tools and assembles their calls from the deltas
+    let use_streaming = context.config.stream.unwrap_or(tools.is_none());
 
     // Debug: Print tools being sent
     if context.config.verbose && tools.is_some() {
@@ -77,47 +414,172 @@ pub async fn run(context: OrchestratorContext, messages: &mut Vec<Message>) -> R
         );
     }
 
-    if context.config.verbose {
-        eprintln!("{}", format!("[AI] Using model: {}", final_model).dimmed());
-    }
-
-    // Make API request
-    if context.config.verbose {
-        eprintln!("{}", "[AI] Making API request...".dimmed());
-    }
-    let response = make_api_request(&context.config.api_key, &context.config.api_endpoint, &request_body).await?;
+    // An empty answer is usually a provider hiccup; api.retry_on_empty asks again
+    let mut retry = 0;
+    let outcome = loop {
+        let outcome = request_answer(context, messages, model, &tools, use_streaming).await?;
+        if !should_retry_empty(context, &outcome, retry) {
+            break outcome;
+        }
+        retry += 1;
+    };
 
-    if context.config.verbose {
+    // Left empty so it is not saved to the session as if it were an answer
+    if !use_streaming && outcome.content.is_empty() && outcome.tool_calls.is_empty() {
         eprintln!(
             "{}",
-            format!("[AI] Response status: {}", response.status()).dimmed()
+            "Warning: No tool calls and no content in response".yellow()
         );
     }
 
-    if !response.status().is_success() {
+    if context.config.verbose {
+        if let Some(ref id) = outcome.request_id {
+            eprintln!("{}", format!("[AI] Request id: {}", id).dimmed());
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Send the conversation (falling back through model.fallbacks when a model is unavailable)
+/// and process the answer, running any requested tools
+async fn request_answer(
+    context: &OrchestratorContext,
+    messages: &mut Vec<Message>,
+    primary_model: &str,
+    tools: &Option<Vec<Value>>,
+    use_streaming: bool,
+) -> Result<QueryOutcome> {
+    let primary_model = primary_model.to_string();
+    // A malformed history would only come back as an opaque provider 400
+    validate_message_sequence(messages)?;
+    fit_to_context_window(context, messages)?;
+
+    // Primary model first, then model.fallbacks when a model is unavailable
+    let mut candidates = vec![primary_model.clone()];
+    candidates.extend(
+        context
+            .config
+            .model_fallbacks
+            .iter()
+            .filter(|m| **m != primary_model)
+            .cloned(),
+    );
+
+    let mut attempt = 0;
+    let (response, final_model, mut spinner, request_started) = loop {
+        let model = candidates[attempt].clone();
+        let request_body = RequestBody {
+            model: model.clone(),
+            messages: messages.to_vec(),
+            stream: use_streaming,
+            reasoning: context.config.reasoning.clone(),
+            tools: tools.clone(),
+            logit_bias: context.config.logit_bias.clone(),
+            frequency_penalty: context.config.frequency_penalty,
+            presence_penalty: context.config.presence_penalty,
+        };
+
+        if context.config.verbose {
+            eprintln!("{}", format!("[AI] Using model: {}", model).dimmed());
+        }
+
+        // Make API request
+        if context.config.verbose {
+            eprintln!("{}", "[AI] Making API request...".dimmed());
+        }
+        let spinner = context.waiting_spinner();
+        let request_started = Instant::now();
+        let response = make_api_request(
+            &context.client,
+            &context.config.api_endpoint,
+            &context.request_json(&request_body)?,
+            context.rate_limiter.as_ref(),
+        )
+        .await?;
+
+        if context.config.verbose {
+            eprintln!(
+                "{}",
+                format!("[AI] Response status: {}", response.status()).dimmed()
+            );
+        }
+
+        if response.status().is_success() {
+            break (response, model, spinner, request_started);
+        }
+        drop(spinner);
+
         let status = response.status().as_u16();
         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(Cmd2AiError::ApiError {
+        let error = Cmd2AiError::ApiError {
             status,
             message: error_text,
-        });
+        };
+
+        // Fallbacks get their turn before any "did you mean" lookup
+        if error.is_model_unavailable() && attempt + 1 >= candidates.len() {
+            suggest_similar_models(context, &model).await;
+        }
+
+        attempt += 1;
+        if attempt < candidates.len() && error.is_model_unavailable() {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: Model '{}' is unavailable, trying '{}'",
+                    model, candidates[attempt]
+                )
+                .yellow()
+            );
+            continue;
+        }
+        return Err(error);
+    };
+
+    if final_model != primary_model {
+        eprintln!(
+            "{}",
+            format!("[AI] Answered by fallback model: {}", final_model).dimmed()
+        );
     }
 
+    let header_request_id = request_id_from_headers(response.headers());
+
     // Process response based on whether we're streaming or not
-    let assistant_response = if use_streaming {
-        // Streaming path - no tools available
-        let streaming_result = process_streaming_response(
+    if use_streaming {
+        // Streaming path - tool calls (with --stream) are assembled from the deltas
+        let mut streaming_result = process_streaming_response(
             response,
-            context.config.stream_timeout,
-            context.args.reasoning_exclude,
-            context.config.verbose,
+            &mut context.output(),
+            &context.stream_settings(request_started, &final_model),
+            &context.render_options,
+            spinner,
         )
         .await?;
 
-        streaming_result.content
+        let tool_calls = std::mem::take(&mut streaming_result.tool_calls);
+        if !tool_calls.is_empty() {
+            let content = Some(streaming_result.content.clone()).filter(|c| !c.is_empty());
+            let outcome = answer_tool_calls(
+                context,
+                &tool_calls,
+                content,
+                messages,
+                &final_model,
+                streaming_result.reasoning.clone(),
+            )
+            .await?;
+            if let Some(outcome) = outcome {
+                return Ok(outcome);
+            }
+        }
+
+        Ok(QueryOutcome::from_stream(streaming_result, header_request_id))
     } else {
         // Non-streaming path - handle tools properly
         let response_text = response.text().await?;
+        spinner.stop();
         if context.config.verbose {
             eprintln!(
                 "{}",
@@ -129,16 +591,80 @@ pub async fn run(context: OrchestratorContext, messages: &mut Vec<Message>) -> R
         let response_json: Value = serde_json::from_str(&response_text)?;
 
         // Process the non-streaming response with tool handling
-        process_non_streaming_response(
-            &context,
+        let mut outcome = process_non_streaming_response(
+            context,
             response_json,
             messages,
             &final_model,
         )
-        .await?
+        .await?;
+        outcome.request_id = outcome.request_id.or(header_request_id);
+        Ok(outcome)
+    }
+}
+
+/// Drop old history that would overflow model.context_window, or fail before sending
+fn fit_to_context_window(context: &OrchestratorContext, messages: &mut Vec<Message>) -> Result<()> {
+    let Some(context_window) = context.config.context_window else {
+        return Ok(());
     };
+    let before = estimate_tokens(messages);
+    let dropped = fit_context_window(messages, context_window).map_err(Cmd2AiError::Other)?;
 
-    Ok(assistant_response)
+    if dropped > 0 && context.config.verbose {
+        eprintln!(
+            "{}",
+            format!(
+                "[AI] Dropped {} old message{} to fit model.context_window ({}): ~{} -> ~{} tokens",
+                dropped,
+                if dropped == 1 { "" } else { "s" },
+                context_window,
+                before,
+                estimate_tokens(messages)
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Whether an answer with no content should be requested again (api.retry_on_empty).
+/// A completion that stopped normally with nothing to say is retried; one cut off by the
+/// token limit would only be cut off again, so it is reported instead.
+fn should_retry_empty(context: &OrchestratorContext, outcome: &QueryOutcome, retry: u32) -> bool {
+    if !context.config.retry_on_empty
+        || !outcome.content.trim().is_empty()
+        || !outcome.tool_calls.is_empty()
+    {
+        return false;
+    }
+
+    let finish_reason = outcome.finish_reason.as_deref().unwrap_or("none");
+    let warning = if finish_reason == "length" {
+        "Warning: Response hit the token limit before any content (finish_reason: length); \
+         not retrying"
+            .to_string()
+    } else if retry >= context.config.max_retries {
+        format!(
+            "Warning: Response still empty after {} retries (finish_reason: {})",
+            retry, finish_reason
+        )
+    } else {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: Empty response (finish_reason: {}), retrying ({}/{})",
+                finish_reason,
+                retry + 1,
+                context.config.max_retries
+            )
+            .yellow()
+        );
+        return true;
+    };
+
+    eprintln!("{}", warning.yellow());
+    false
 }
 
 async fn process_non_streaming_response(
@@ -146,107 +672,190 @@ async fn process_non_streaming_response(
     response_json: Value,
     messages: &mut Vec<Message>,
     final_model: &str,
-) -> Result<String> {
+) -> Result<QueryOutcome> {
+    let generation_id = response_json
+        .get("id")
+        .and_then(|id| id.as_str())
+        .map(String::from);
+
     // Check for reasoning content first
-    if let Ok(Some(reasoning_content)) = extract_reasoning(&response_json) {
-        if !context.args.reasoning_exclude && !reasoning_content.is_empty() {
-            display_reasoning(&reasoning_content);
+    let reasoning = extract_reasoning(&response_json)
+        .ok()
+        .flatten()
+        .filter(|r| !r.is_empty());
+    if let Some(ref reasoning_content) = reasoning {
+        if !context.args.reasoning_exclude && !context.quiet {
+            display_reasoning(reasoning_content, &context.render_options);
         }
     }
 
     // Check if there are tool calls
     if let Ok(Some(tool_calls)) = parse_tool_calls(&response_json) {
         if !tool_calls.is_empty() {
-            if context.config.verbose {
-                println!("{}", "Executing tools...".cyan());
+            let content = response_json
+                .pointer("/choices/0/message/content")
+                .and_then(|c| c.as_str())
+                .map(String::from);
+            let outcome = answer_tool_calls(
+                context,
+                &tool_calls,
+                content,
+                messages,
+                final_model,
+                reasoning.clone(),
+            )
+            .await?;
+            if let Some(outcome) = outcome {
+                return Ok(outcome);
             }
+        }
+    }
 
-            let tool_results = execute_tool_calls(context, &tool_calls).await?;
-
-            // If we executed tools, we need to send the results back and get a new response
-            if !tool_results.is_empty() {
-                // Add the assistant's message with tool calls to the conversation
-                let first_choice = response_json
-                    .get("choices")
-                    .and_then(|c| c.as_array())
-                    .and_then(|c| c.first())
-                    .ok_or_else(|| Cmd2AiError::Other("No choices in response".to_string()))?;
-
-                let message = first_choice
-                    .get("message")
-                    .ok_or_else(|| Cmd2AiError::Other("No message in response".to_string()))?;
-
-                // Convert tool_calls array to proper ToolCall objects
-                let tool_calls_typed: Vec<crate::models::ToolCall> = tool_calls
-                    .iter()
-                    .filter_map(|tc| serde_json::from_value(tc.clone()).ok())
-                    .collect();
-
-                messages.push(Message {
-                    role: "assistant".to_string(),
-                    content: message
-                        .get("content")
-                        .and_then(|c| c.as_str())
-                        .map(|s| s.to_string()),
-                    tool_calls: if tool_calls_typed.is_empty() {
-                        None
-                    } else {
-                        Some(tool_calls_typed)
-                    },
-                    tool_call_id: None,
-                });
+    // No tool calls - extract and display content
+    answer_from_response(context, final_model, &response_json, reasoning, generation_id)
+}
 
-                // Add tool results to the conversation
-                for result in tool_results {
-                    messages.push(result);
-                }
+/// Run the tool calls of an answer and ask again with their results. `content` is the
+/// text that came with the calls; `reasoning` ends up on the outcome when the follow-up
+/// has none. Returns None when no call could be answered.
+async fn answer_tool_calls(
+    context: &OrchestratorContext,
+    tool_calls: &[Value],
+    content: Option<String>,
+    messages: &mut Vec<Message>,
+    final_model: &str,
+    reasoning: Option<String>,
+) -> Result<Option<QueryOutcome>> {
+    if context.config.verbose && !context.quiet {
+        println!("{}", "Executing tools...".cyan());
+    }
 
-                // Make another API call to get the final response - NOW WITH STREAMING!
-                let followup_request = RequestBody {
-                    model: final_model.to_string(),
-                    messages: messages.to_vec(),
-                    stream: true, // Enable streaming for the final answer
-                    reasoning: context.config.reasoning.clone(),
-                    tools: None, // Don't send tools again for the final response
-                };
+    let (tool_results, invocations) = execute_tool_calls(context, tool_calls).await?;
+    if tool_results.is_empty() {
+        return Ok(None);
+    }
 
-                if context.config.verbose {
-                    eprintln!("{}", "[AI] Making follow-up request with tool results (streaming enabled)...".dimmed());
-                }
+    // Add the assistant's message with tool calls to the conversation
+    let tool_calls_typed: Vec<ToolCall> = tool_calls.iter().filter_map(logged_tool_call).collect();
+    messages.push(Message {
+        role: "assistant".to_string(),
+        content,
+        tool_calls: if tool_calls_typed.is_empty() {
+            None
+        } else {
+            Some(tool_calls_typed)
+        },
+        tool_call_id: None,
+    });
 
-                let followup_response = make_api_request(
-                    &context.config.api_key,
-                    &context.config.api_endpoint,
-                    &followup_request,
-                )
-                .await?;
-
-                if !followup_response.status().is_success() {
-                    let status = followup_response.status().as_u16();
-                    let error_text = followup_response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                    return Err(Cmd2AiError::ApiError {
-                        status,
-                        message: error_text,
-                    });
-                }
+    // Add tool results to the conversation; large results can push the
+    // follow-up past model.context_window
+    messages.extend(tool_results);
+    fit_to_context_window(context, messages)?;
+    validate_message_sequence(messages)?;
 
-                // Process the follow-up STREAMING response for better UX
-                let followup_result = process_streaming_response(
-                    followup_response,
-                    context.config.stream_timeout,
-                    context.args.reasoning_exclude,
-                    context.config.verbose,
-                )
-                .await?;
+    // Make another API call to get the final response - NOW WITH STREAMING!
+    // The final answer streams unless --no-stream / api.stream: false
+    let stream_followup = context.config.stream != Some(false);
+    let followup_request = RequestBody {
+        model: final_model.to_string(),
+        messages: messages.to_vec(),
+        stream: stream_followup,
+        reasoning: context.config.reasoning.clone(),
+        tools: None, // Don't send tools again for the final response
+        logit_bias: context.config.logit_bias.clone(),
+        frequency_penalty: context.config.frequency_penalty,
+        presence_penalty: context.config.presence_penalty,
+    };
 
-                // Return the final streamed response
-                return Ok(followup_result.content);
-            }
+    if context.config.verbose {
+        eprintln!(
+            "{}",
+            format!(
+                "[AI] Making follow-up request with tool results (streaming {})...",
+                if stream_followup { "enabled" } else { "disabled" }
+            )
+            .dimmed()
+        );
+    }
+
+    // Only the follow-up is asked again on an empty answer; the tools already ran
+    let mut retry = 0;
+    let mut outcome = loop {
+        let spinner = context.waiting_spinner();
+        let request_started = Instant::now();
+        let followup_response = make_api_request(
+            &context.client,
+            &context.config.api_endpoint,
+            &context.request_json(&followup_request)?,
+            context.rate_limiter.as_ref(),
+        )
+        .await?;
+
+        let followup_header_id = request_id_from_headers(followup_response.headers());
+
+        if !followup_response.status().is_success() {
+            drop(spinner);
+            let status = followup_response.status().as_u16();
+            let error_text = followup_response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(Cmd2AiError::ApiError {
+                status,
+                message: error_text,
+            });
+        }
+
+        let outcome = if stream_followup {
+            // Process the follow-up STREAMING response for better UX
+            let followup_result = process_streaming_response(
+                followup_response,
+                &mut context.output(),
+                &context.stream_settings(request_started, final_model),
+                &context.render_options,
+                spinner,
+            )
+            .await?;
+            QueryOutcome::from_stream(followup_result, followup_header_id)
+        } else {
+            let response_text = followup_response.text().await?;
+            drop(spinner);
+            let response_json: Value = serde_json::from_str(&response_text)?;
+            let generation_id = response_json
+                .get("id")
+                .and_then(|id| id.as_str())
+                .map(String::from);
+            let mut outcome =
+                answer_from_response(context, final_model, &response_json, None, generation_id)?;
+            outcome.request_id = outcome.request_id.or(followup_header_id);
+            outcome
+        };
+        if !should_retry_empty(context, &outcome, retry) {
+            break outcome;
+        }
+        retry += 1;
+    };
+
+    // Return the final streamed response
+    outcome.reasoning = outcome.reasoning.or(reasoning);
+    outcome.tool_calls = invocations;
+    Ok(Some(outcome))
+}
+
+/// The assistant's answer in a complete (non-streamed) response, displayed unless quiet
+fn answer_from_response(
+    context: &OrchestratorContext,
+    model: &str,
+    response_json: &Value,
+    reasoning: Option<String>,
+    generation_id: Option<String>,
+) -> Result<QueryOutcome> {
+    let content = extract_content(response_json).ok().flatten();
+    if content.as_deref().unwrap_or_default().is_empty() {
+        if let Some(refusal) = extract_refusal(response_json) {
+            return Err(Cmd2AiError::Refused(refusal));
         }
     }
 
-    // No tool calls - extract and display content
-    if let Ok(Some(content)) = extract_content(&response_json) {
+    if let Some(content) = content {
         if context.config.verbose {
             eprintln!(
                 "{}",
@@ -254,8 +863,21 @@ async fn process_non_streaming_response(
             );
         }
 
-        display_content(&content);
-        Ok(content)
+        if !context.quiet {
+            if let Some(header) = context.render_options.answer_header(model) {
+                println!("{}", header.dimmed());
+            }
+            display_content(&content, &context.render_options);
+        }
+        Ok(QueryOutcome {
+            content,
+            reasoning,
+            citations: extract_citations(response_json),
+            usage: extract_usage(response_json),
+            finish_reason: extract_finish_reason(response_json),
+            request_id: generation_id,
+            ..QueryOutcome::default()
+        })
     } else {
         if context.config.verbose {
             eprintln!(
@@ -263,37 +885,49 @@ async fn process_non_streaming_response(
                 "[AI] tool_calls array is empty and no content provided.".dimmed()
             );
         }
-        Ok("No tool calls and no content in response".to_string())
+        Ok(QueryOutcome {
+            reasoning,
+            usage: extract_usage(response_json),
+            finish_reason: extract_finish_reason(response_json),
+            request_id: generation_id,
+            ..QueryOutcome::default()
+        })
     }
 }
 
+/// A tool call as recorded in the assistant turn. Calls without an id or function name
+/// cannot be answered, so they are left out here and get no tool message either.
+fn logged_tool_call(tool_call: &Value) -> Option<ToolCall> {
+    let id = tool_call.get("id")?.as_str()?;
+    let function = tool_call.get("function")?;
+    let name = function.get("name")?.as_str()?;
+    let arguments = function.get("arguments").and_then(|a| a.as_str());
+    Some(ToolCall {
+        id: id.to_string(),
+        tool_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.unwrap_or("{}").to_string(),
+        },
+    })
+}
+
 async fn execute_tool_calls(
     context: &OrchestratorContext,
     tool_calls: &[Value],
-) -> Result<Vec<Message>> {
+) -> Result<(Vec<Message>, Vec<ToolInvocation>)> {
     let mut tool_results = Vec::new();
+    let mut invocations = Vec::new();
 
     for tool_call in tool_calls {
         // Check for required fields and report errors for malformed tool calls
         let id = tool_call.get("id").and_then(|i| i.as_str());
         let function = tool_call.get("function");
 
+        // Without an id (or below, a function name) the call is not in the assistant
+        // turn, so an error message answering it would break the message sequence
         if id.is_none() {
             eprintln!("{}", "Warning: Tool call missing 'id' field, skipping".yellow());
-            // Generate a temporary ID for error reporting
-            let temp_id = format!(
-                "error_{}",
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos()
-            );
-            tool_results.push(Message {
-                role: "tool".to_string(),
-                content: Some("Error: Tool call missing required 'id' field".to_string()),
-                tool_calls: None,
-                tool_call_id: Some(temp_id),
-            });
             continue;
         }
         let id = id.unwrap();
@@ -303,15 +937,6 @@ async fn execute_tool_calls(
                 "{}",
                 format!("Warning: Tool call {} missing 'function' field, skipping", id).yellow()
             );
-            tool_results.push(Message {
-                role: "tool".to_string(),
-                content: Some(format!(
-                    "Error: Tool call {} missing required 'function' field",
-                    id
-                )),
-                tool_calls: None,
-                tool_call_id: Some(id.to_string()),
-            });
             continue;
         }
         let function = function.unwrap();
@@ -325,15 +950,6 @@ async fn execute_tool_calls(
                 format!("Warning: Tool call {} missing 'function.name' field, skipping", id)
                     .yellow()
             );
-            tool_results.push(Message {
-                role: "tool".to_string(),
-                content: Some(format!(
-                    "Error: Tool call {} missing required 'function.name' field",
-                    id
-                )),
-                tool_calls: None,
-                tool_call_id: Some(id.to_string()),
-            });
             continue;
         }
         let name = name.unwrap();
@@ -344,54 +960,84 @@ async fn execute_tool_calls(
                 format!("Warning: Tool call {} missing 'function.arguments' field, skipping", id)
                     .yellow()
             );
+            let error_text = format!(
+                "Error: Tool call {} missing required 'function.arguments' field",
+                id
+            );
             tool_results.push(Message {
                 role: "tool".to_string(),
-                content: Some(format!(
-                    "Error: Tool call {} missing required 'function.arguments' field",
-                    id
-                )),
+                content: Some(error_text.clone()),
                 tool_calls: None,
                 tool_call_id: Some(id.to_string()),
             });
+            // Answered, so it is an invocation like any other
+            invocations.push(ToolInvocation {
+                id: id.to_string(),
+                name: name.to_string(),
+                arguments: Value::Null,
+                result: error_text,
+                is_error: true,
+            });
             continue;
         }
         let arguments_str = arguments_str.unwrap();
 
         if context.config.verbose {
-            let args_preview = if arguments_str.len() > 100 {
-                format!("{}...", &arguments_str[..100])
-            } else {
-                arguments_str.to_string()
-            };
+            let args_preview = truncate_for_display(arguments_str, 100);
             eprintln!(
                 "{}",
                 format!("[tools] Selected tool: '{}' with args: {}", name, args_preview).dimmed()
             );
         }
 
-        println!("{}", format!("Calling tool: {}...", name).cyan());
+        if !context.quiet {
+            println!("{}", format!("Calling tool: {}...", name).cyan());
+        }
+
+        // Parse arguments, optionally repairing common model mistakes first
+        let parsed = match serde_json::from_str::<Value>(arguments_str) {
+            Err(err) if context.config.repair_tool_arguments => {
+                match repair_tool_arguments(arguments_str) {
+                    Some(repaired) => {
+                        if context.config.verbose {
+                            eprintln!(
+                                "{}",
+                                format!("[tools] Repaired malformed arguments for '{}'", name)
+                                    .dimmed()
+                            );
+                        }
+                        Ok(repaired)
+                    }
+                    None => Err(err),
+                }
+            }
+            other => other,
+        };
+
+        let recorded_arguments = parsed.as_ref().map_or(Value::Null, |a| a.clone());
+        let mut succeeded = false;
 
-        // Parse arguments
-        match serde_json::from_str::<Value>(arguments_str) {
+        match parsed {
             Ok(arguments) => {
                 // Execute local tool
                 if let Some(ref registry) = context.local_tools_registry {
                     if registry.get(name).is_some() {
                         match call_local_tool(registry, name, &arguments).await {
                             Ok(result_text) => {
-                                display_tool_result(name, &result_text);
+                                context.show_tool_result(name, &result_text);
+                                succeeded = true;
 
                                 // Keep the original result_text for the message (not the formatted version)
                                 tool_results.push(Message {
                                     role: "tool".to_string(),
-                                    content: Some(result_text),
+                                    content: Some(context.result_for_model(name, result_text)),
                                     tool_calls: None,
                                     tool_call_id: Some(id.to_string()),
                                 });
                             }
                             Err(e) => {
                                 let error_text = format!("Error: {}", e);
-                                display_tool_error(name, &error_text);
+                                context.show_tool_error(name, &error_text);
 
                                 tool_results.push(Message {
                                     role: "tool".to_string(),
@@ -403,8 +1049,9 @@ async fn execute_tool_calls(
                         }
                     } else {
                         // Display tool not found error in a boxed format
-                        let error_text = format!("Error: Tool '{}' not found", name);
-                        display_tool_error(name, &error_text);
+                        // List the real tools so the model can correct itself on the next turn
+                        let error_text = unknown_tool_error(name, Some(registry));
+                        context.show_tool_error(name, &error_text);
 
                         tool_results.push(Message {
                             role: "tool".to_string(),
@@ -415,12 +1062,12 @@ async fn execute_tool_calls(
                     }
                 } else {
                     // Display tool not found error (local tools disabled) in a boxed format
-                    let error_text = format!("Error: Tool '{}' not found (local tools disabled)", name);
-                    display_tool_error(name, &error_text);
+                    let error_text = unknown_tool_error(name, None);
+                    context.show_tool_error(name, &error_text);
 
                     tool_results.push(Message {
                         role: "tool".to_string(),
-                        content: Some(format!("Error: Tool '{}' not found", name)),
+                        content: Some(error_text),
                         tool_calls: None,
                         tool_call_id: Some(id.to_string()),
                     });
@@ -430,7 +1077,7 @@ async fn execute_tool_calls(
                 // Display argument parsing error in a boxed format
                 let error_text =
                     format!("Error: failed to parse arguments for tool '{}' : {}", name, err);
-                display_tool_error(name, &error_text);
+                context.show_tool_error(name, &error_text);
 
                 tool_results.push(Message {
                     role: "tool".to_string(),
@@ -440,8 +1087,36 @@ async fn execute_tool_calls(
                 });
             }
         }
+
+        invocations.push(ToolInvocation {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments: recorded_arguments,
+            result: tool_results
+                .last()
+                .and_then(|m| m.content.clone())
+                .unwrap_or_default(),
+            is_error: !succeeded,
+        });
     }
 
-    Ok(tool_results)
+    Ok((tool_results, invocations))
 }
 
+/// Build the error returned to the model when it asks for a tool that isn't registered
+fn unknown_tool_error(name: &str, registry: Option<&LocalToolRegistry>) -> String {
+    let mut available: Vec<&str> = registry
+        .map(|r| r.list().iter().map(|t| t.name.as_str()).collect())
+        .unwrap_or_default();
+    available.sort_unstable();
+
+    if available.is_empty() {
+        format!("Error: Tool '{}' not found. No tools are available.", name)
+    } else {
+        format!(
+            "Error: Tool '{}' not found. Available tools: {}",
+            name,
+            available.join(", ")
+        )
+    }
+}
@@ -7,16 +7,41 @@ use std::path::{Path, PathBuf};
 
 pub const SESSION_EXPIRY_MINUTES: i64 = 30;
 
-pub struct FilesystemSessionStore;
+pub struct FilesystemSessionStore {
+    /// Overrides ~/.cache/cmd2ai
+    cache_dir: Option<PathBuf>,
+    /// Prune down to this many session files after each save
+    max_files: Option<usize>,
+}
 
 impl FilesystemSessionStore {
     pub fn new() -> Self {
-        Self
+        Self {
+            cache_dir: None,
+            max_files: None,
+        }
+    }
+
+    /// Store sessions in `dir` instead of ~/.cache/cmd2ai
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    /// Keep at most `max_files` session files, deleting the oldest after each save
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files);
+        self
     }
 
     fn get_cache_dir(&self) -> PathBuf {
-        let home = env::var("HOME").expect("HOME environment variable not set");
-        let cache_dir = Path::new(&home).join(".cache").join("cmd2ai");
+        let cache_dir = match &self.cache_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let home = env::var("HOME").expect("HOME environment variable not set");
+                Path::new(&home).join(".cache").join("cmd2ai")
+            }
+        };
         if !cache_dir.exists() {
             fs::create_dir_all(&cache_dir).expect("Failed to create cache directory");
         }
@@ -48,7 +73,7 @@ impl SessionStore for FilesystemSessionStore {
                 .collect();
 
             // Sort by last_updated (most recent first)
-            sessions.sort_by(|a, b| b.1.last_updated.cmp(&a.1.last_updated));
+            sessions.sort_by_key(|s| std::cmp::Reverse(s.1.last_updated));
 
             // Return the most recent session if it's not expired
             if let Some((path, session)) = sessions.first() {
@@ -72,9 +97,40 @@ impl SessionStore for FilesystemSessionStore {
         let session_file = cache_dir.join(format!("session-{}.json", session.session_id));
         let content = serde_json::to_string_pretty(session)?;
         fs::write(session_file, content)?;
+
+        if let Some(max_files) = self.max_files {
+            self.prune_sessions(max_files)?;
+        }
         Ok(())
     }
 
+    fn prune_sessions(&self, max_files: usize) -> Result<usize, Box<dyn std::error::Error>> {
+        let cache_dir = self.get_cache_dir();
+        let mut sessions: Vec<(PathBuf, chrono::DateTime<Local>)> = fs::read_dir(&cache_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "json"
+                    || !path.file_name()?.to_str()?.starts_with("session-")
+                {
+                    return None;
+                }
+                let content = fs::read_to_string(&path).ok()?;
+                let session: Session = serde_json::from_str(&content).ok()?;
+                Some((path, session.last_updated))
+            })
+            .collect();
+
+        // Newest first; everything past the limit goes
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.1));
+        let mut removed = 0;
+        for (path, _) in sessions.iter().skip(max_files) {
+            fs::remove_file(path)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
     fn clear_all_sessions(&self) -> Result<(), Box<dyn std::error::Error>> {
         let cache_dir = self.get_cache_dir();
         if let Ok(entries) = fs::read_dir(&cache_dir) {
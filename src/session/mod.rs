@@ -10,7 +10,16 @@ use uuid::Uuid;
 
 pub const MAX_CONVERSATION_PAIRS: usize = 3; // Keep last 3 exchanges (6 messages)
 
-/// Trim conversation history to keep only the last N exchanges
+/// Most history (estimated tokens) sent to a --summarize-history request; older turns
+/// past it are dropped unsummarized
+pub const MAX_SUMMARY_INPUT_TOKENS: usize = 8000;
+
+/// Prefix of the message that stands in for summarized history
+pub const HISTORY_SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+
+/// Trim conversation history to keep only the last N exchanges. An assistant message with
+/// `tool_calls` and the `tool` results after it are kept or dropped together, since a `tool`
+/// message without its call is rejected by the API.
 pub fn trim_conversation_history(messages: &mut Vec<Message>) {
     // Keep system message (if exists) + last N conversation pairs
     let mut system_messages: Vec<Message> = messages
@@ -19,34 +28,244 @@ pub fn trim_conversation_history(messages: &mut Vec<Message>) {
         .cloned()
         .collect();
 
-    let conversation_messages: Vec<Message> = messages
+    let mut conversation_messages: Vec<Message> = messages
         .iter()
         .filter(|m| m.role != "system")
         .cloned()
         .collect();
 
-    // Keep only the last MAX_CONVERSATION_PAIRS exchanges
-    let keep_count = MAX_CONVERSATION_PAIRS * 2; // Each pair has user + assistant
-    let trimmed: Vec<Message> = conversation_messages
-        .into_iter()
-        .rev()
-        .take(keep_count)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect();
+    let start = trim_start(&conversation_messages);
+    let trimmed = conversation_messages.split_off(start);
 
     messages.clear();
     messages.append(&mut system_messages);
     messages.extend(trimmed);
 }
 
+/// Index of the first conversation (non-system) message that trimming keeps
+fn trim_start(conversation_messages: &[Message]) -> usize {
+    // Keep only the last MAX_CONVERSATION_PAIRS exchanges
+    let keep_count = MAX_CONVERSATION_PAIRS * 2; // Each pair has user + assistant
+    let mut start = conversation_messages.len().saturating_sub(keep_count);
+
+    // A cut inside a tool-call turn drops the rest of it rather than orphaning its results
+    while conversation_messages
+        .get(start)
+        .is_some_and(|m| m.role == "tool")
+    {
+        start += 1;
+    }
+    start
+}
+
+/// The messages `trim_conversation_history` would drop, oldest first
+pub fn messages_to_trim(messages: &[Message]) -> Vec<Message> {
+    let conversation_messages: Vec<Message> = messages
+        .iter()
+        .filter(|m| m.role != "system")
+        .cloned()
+        .collect();
+    let start = trim_start(&conversation_messages);
+    conversation_messages[..start].to_vec()
+}
+
+/// Plain-text transcript of `messages` for a summary request. When it would exceed
+/// `max_tokens` (by estimate), the oldest messages are left out.
+pub fn history_transcript(messages: &[Message], max_tokens: usize) -> String {
+    let lines: Vec<String> = messages
+        .iter()
+        .filter_map(|m| {
+            let calls: Vec<String> = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| {
+                    format!(
+                        "[called {}({})]",
+                        call.function.name, call.function.arguments
+                    )
+                })
+                .collect();
+            let text = [m.content.clone().unwrap_or_default(), calls.join(" ")]
+                .into_iter()
+                .filter(|part| !part.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            (!text.is_empty()).then(|| format!("{}: {}", m.role, text))
+        })
+        .collect();
+
+    let mut kept = Vec::new();
+    let mut chars = 0;
+    for line in lines.iter().rev() {
+        chars += line.chars().count() + 2;
+        if chars.div_ceil(4) > max_tokens {
+            break;
+        }
+        kept.push(line.as_str());
+    }
+    kept.reverse();
+    kept.join("\n\n")
+}
+
+/// Stand `summary` in for trimmed history: an assistant message right after the system
+/// messages. On a later run it is the oldest message, so it gets folded into the next summary.
+pub fn insert_history_summary(messages: &mut Vec<Message>, summary: &str) {
+    let position = messages.iter().take_while(|m| m.role == "system").count();
+    messages.insert(
+        position,
+        Message {
+            role: "assistant".to_string(),
+            content: Some(format!("{}\n{}", HISTORY_SUMMARY_PREFIX, summary.trim())),
+            tool_calls: None,
+            tool_call_id: None,
+        },
+    );
+}
+
+/// Rough prompt size: about 4 characters per token, counting content and tool-call arguments
+pub fn estimate_tokens(messages: &[Message]) -> usize {
+    let chars: usize = messages
+        .iter()
+        .map(|m| {
+            let content = m.content.as_deref().map_or(0, |c| c.chars().count());
+            let calls: usize = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| {
+                    call.function.name.chars().count() + call.function.arguments.chars().count()
+                })
+                .sum();
+            content + calls
+        })
+        .sum();
+    chars.div_ceil(4)
+}
+
+/// Drop the oldest conversation messages until the estimate fits `context_window`, keeping
+/// system messages and the current turn (the last user message and any tool calls and
+/// results after it). A tool-call turn is dropped with its results.
+/// Returns how many messages were dropped; errors if the rest alone is still too large.
+pub fn fit_context_window(
+    messages: &mut Vec<Message>,
+    context_window: usize,
+) -> Result<usize, String> {
+    let first_conversation = messages.iter().take_while(|m| m.role == "system").count();
+    let mut current_turn = messages
+        .iter()
+        .rposition(|m| m.role == "user")
+        .unwrap_or(messages.len().saturating_sub(1))
+        .max(first_conversation);
+    let mut dropped = 0;
+
+    while estimate_tokens(messages) > context_window && current_turn > first_conversation {
+        messages.remove(first_conversation);
+        current_turn -= 1;
+        dropped += 1;
+        // Results of a dropped tool-call turn go with it
+        while current_turn > first_conversation && messages[first_conversation].role == "tool" {
+            messages.remove(first_conversation);
+            current_turn -= 1;
+            dropped += 1;
+        }
+    }
+
+    let estimate = estimate_tokens(messages);
+    if estimate > context_window {
+        return Err(format!(
+            "The prompt is about {} tokens, more than model.context_window ({}) even without \
+             history. Shorten the input or the system prompt, or use a model with a larger \
+             context window.",
+            estimate, context_window
+        ));
+    }
+    Ok(dropped)
+}
+
+/// Merge runs of same-role messages into one, joining their content with a blank line,
+/// so e.g. a stored system message and an inserted one reach the provider as a single
+/// system message. Tool calls and tool results are never merged.
+pub fn merge_consecutive_messages(messages: &mut Vec<Message>) {
+    let mergeable =
+        |m: &Message| m.role != "tool" && m.tool_calls.is_none() && m.tool_call_id.is_none();
+
+    let mut merged: Vec<Message> = Vec::with_capacity(messages.len());
+    for message in messages.drain(..) {
+        match merged.last_mut() {
+            Some(last) if last.role == message.role && mergeable(last) && mergeable(&message) => {
+                let parts: Vec<String> = [last.content.take(), message.content]
+                    .into_iter()
+                    .flatten()
+                    .filter(|content| !content.is_empty())
+                    .collect();
+                last.content = Some(parts.join("\n\n"));
+            }
+            _ => merged.push(message),
+        }
+    }
+    *messages = merged;
+}
+
 /// Create a new session
 pub fn create_new_session() -> crate::models::Session {
     crate::models::Session {
         session_id: Uuid::new_v4().to_string(),
         last_updated: Local::now(),
         messages: vec![],
+        system_prompt: None,
+        last_request_id: None,
+    }
+}
+
+/// Resolve the session's system prompt and write it as the first message
+/// A prompt stored in the session wins over the config one; `replace` (--system) swaps it
+/// out and `append` (--append-system) extends whichever prompt is in effect.
+pub fn apply_system_prompt(
+    session: &mut crate::models::Session,
+    config_prompt: Option<&str>,
+    replace: Option<&str>,
+    append: Option<&str>,
+    date_prompt: Option<&str>,
+) {
+    let existing_system = session.messages.first().is_some_and(|m| m.role == "system");
+
+    // Older sessions only have the rendered message; leave those alone unless asked
+    if session.system_prompt.is_none() && existing_system && replace.is_none() && append.is_none() {
+        return;
+    }
+
+    let base = replace
+        .map(String::from)
+        .or_else(|| session.system_prompt.clone())
+        .or_else(|| config_prompt.map(String::from));
+    let effective = match (base, append) {
+        (Some(base), Some(extra)) => Some(format!("{}\n\n{}", base, extra)),
+        (None, Some(extra)) => Some(extra.to_string()),
+        (base, None) => base,
+    };
+
+    let content = [date_prompt, effective.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    session.system_prompt = effective;
+
+    if existing_system {
+        session.messages.remove(0);
+    }
+    // With date injection off and no prompt there is nothing to send
+    if !content.is_empty() {
+        session.messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: Some(content),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        );
     }
 }
 
@@ -55,8 +274,21 @@ pub fn find_recent_session() -> Option<crate::models::Session> {
     FilesystemSessionStore::new().find_recent_session()
 }
 
-pub fn save_session(session: &crate::models::Session) -> Result<(), Box<dyn std::error::Error>> {
-    FilesystemSessionStore::new().save_session(session)
+/// Save, then prune to `max_files` session files (0 keeps them all)
+pub fn save_session(
+    session: &crate::models::Session,
+    max_files: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = FilesystemSessionStore::new();
+    if max_files == 0 {
+        store.save_session(session)
+    } else {
+        store.with_max_files(max_files).save_session(session)
+    }
+}
+
+pub fn prune_sessions(max_files: usize) -> Result<usize, Box<dyn std::error::Error>> {
+    FilesystemSessionStore::new().prune_sessions(max_files)
 }
 
 pub fn clear_all_sessions() -> Result<(), Box<dyn std::error::Error>> {
@@ -8,6 +8,9 @@ pub trait SessionStore: Send + Sync {
     /// Save a session
     fn save_session(&self, session: &Session) -> Result<(), Box<dyn std::error::Error>>;
 
+    /// Delete the oldest sessions so at most `max_files` remain; returns how many were removed
+    fn prune_sessions(&self, max_files: usize) -> Result<usize, Box<dyn std::error::Error>>;
+
     /// Clear all sessions
     fn clear_all_sessions(&self) -> Result<(), Box<dyn std::error::Error>>;
 }
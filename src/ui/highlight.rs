@@ -1,58 +1,205 @@
 use colored::*;
+use std::sync::OnceLock;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{Style, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
 use terminal_size::{terminal_size, Width};
 
+use super::options::{BorderStyle, RenderOptions};
+
 const ANSI_RESET: &str = "\x1b[0m";
 
+/// Widest code block border, however wide the terminal
+const MAX_BOX_WIDTH: usize = 120;
+
+/// Minimum width of the line number gutter (numbers are right-aligned within it)
+const LINE_NUMBER_WIDTH: usize = 4;
+
+/// Characters used to draw code block borders
+struct BoxChars {
+    top_left: &'static str,
+    bottom_left: &'static str,
+    horizontal: &'static str,
+}
+
+const UNICODE_BOX: BoxChars = BoxChars {
+    top_left: "┌",
+    bottom_left: "└",
+    horizontal: "─",
+};
+
+const ASCII_BOX: BoxChars = BoxChars {
+    top_left: "+",
+    bottom_left: "+",
+    horizontal: "-",
+};
+
+/// Fence labels that syntect's default syntaxes don't recognize, mapped to the closest syntax name
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("ts", "JavaScript"),
+    ("typescript", "JavaScript"),
+    ("tsx", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("node", "JavaScript"),
+    ("jsonc", "JSON"),
+    ("json5", "JSON"),
+    ("sh", "Bourne Again Shell (bash)"),
+    ("shell", "Bourne Again Shell (bash)"),
+    ("console", "Bourne Again Shell (bash)"),
+    ("shellsession", "Bourne Again Shell (bash)"),
+    ("zsh", "Bourne Again Shell (bash)"),
+    ("yml", "YAML"),
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("python3", "Python"),
+    ("golang", "Go"),
+    ("c++", "C++"),
+    ("cpp", "C++"),
+    ("objc", "Objective-C"),
+    ("md", "Markdown"),
+    ("htm", "HTML"),
+    ("make", "Makefile"),
+    ("patch", "Diff"),
+];
+
+/// Syntaxes and themes are read-only, so every `CodeBuffer` shares one copy loaded on first use
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Width of code block borders for a terminal `columns` wide: the full width up to 120, so
+/// borders never wrap on narrow panes, or 80 when the width is unknown
+pub fn box_width(columns: Option<usize>) -> usize {
+    match columns {
+        Some(columns) if columns > 0 => columns.min(MAX_BOX_WIDTH),
+        _ => 80,
+    }
+}
+
+/// The first opening fence in `text`: the offset of its delimiter, the delimiter itself
+/// (three or more backticks or tildes) and how many bytes of indentation precede it on its
+/// line. Backtick fences are found anywhere; tilde fences only at the start of a line.
+fn find_code_block_start(text: &str) -> Option<(usize, String, usize)> {
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let found = if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            Some((line_start + indent, indent))
+        } else {
+            line.find("```").map(|pos| (line_start + pos, 0))
+        };
+        if let Some((start, indent)) = found {
+            let marker = text[start..].chars().next().unwrap();
+            let length = text[start..].chars().take_while(|&c| c == marker).count();
+            return Some((start, marker.to_string().repeat(length), indent));
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+/// Length of a run of one or two backticks or tildes ending `text` (after optional
+/// indentation on its line), which may be the start of a fence split across chunks
+fn partial_fence_len(text: &str) -> usize {
+    let last_line = &text[text.rfind('\n').map_or(0, |pos| pos + 1)..];
+    let run = last_line
+        .chars()
+        .rev()
+        .take_while(|&c| c == '`' || c == '~')
+        .count();
+    let before = &last_line[..last_line.len() - run];
+    let tilde_run = last_line.ends_with('~') && before.trim().is_empty();
+    if (1..3).contains(&run) && (last_line.ends_with('`') || tilde_run) {
+        run
+    } else {
+        0
+    }
+}
+
 pub struct CodeBuffer {
     buffer: String,
     in_code_block: bool,
     code_block_content: String,
     code_block_lang: Option<String>,
-    syntax_set: SyntaxSet,
-    theme_set: ThemeSet,
+    syntax_set: &'static SyntaxSet,
+    theme_set: &'static ThemeSet,
     displayed_lines: usize,
+    /// Delimiter that opened the current block (``` or ~~~, possibly longer); closing it
+    /// takes a run of the same character at least as long
+    fence: String,
+    /// Indentation of the opening fence, removed from the block's lines
+    fence_indent: usize,
+    options: RenderOptions,
 }
 
 impl CodeBuffer {
     pub fn new() -> Self {
+        Self::with_options(RenderOptions::default())
+    }
+
+    pub fn with_options(options: RenderOptions) -> Self {
         Self {
             buffer: String::new(),
             in_code_block: false,
             code_block_content: String::new(),
             code_block_lang: None,
-            syntax_set: SyntaxSet::load_defaults_newlines(),
-            theme_set: ThemeSet::load_defaults(),
+            syntax_set: syntax_set(),
+            theme_set: theme_set(),
             displayed_lines: 0,
+            fence: String::new(),
+            fence_indent: 0,
+            options,
         }
     }
 
-    /// Compute target width for code block borders
-    /// Returns width between 50 and 120, defaulting to 80 if terminal size unavailable
-    fn compute_box_width(&self) -> usize {
-        if let Some((Width(w), _)) = terminal_size() {
-            let cols = w as usize;
-            cols.min(120).max(50)
+    fn box_chars(&self) -> &'static BoxChars {
+        if self.options.ascii_boxes {
+            &ASCII_BOX
         } else {
-            80
+            &UNICODE_BOX
         }
     }
 
+    /// Compute target width for code block borders: the fixed `--width`, else the terminal's
+    fn compute_box_width(&self) -> usize {
+        self.options
+            .width
+            .unwrap_or_else(|| box_width(terminal_size().map(|(Width(w), _)| w as usize)))
+    }
+
     /// Generate header line for code block with dynamic width
     fn format_header(&self, label: &str) -> String {
+        let chars = self.box_chars();
+        let prefix = format!("{}{}", chars.top_left, chars.horizontal);
+        match self.options.border_style {
+            BorderStyle::Hidden => return String::new(),
+            BorderStyle::Compact => {
+                return format!("{}{}[{}]\n", ANSI_RESET, prefix.dimmed(), label.cyan())
+            }
+            BorderStyle::Full => {}
+        }
+
         let width = self.compute_box_width();
         // Calculate label length: label itself + 2 brackets
         let label_len = label.len() + 2;
         // Account for "┌─" prefix (2 chars)
+        // On a terminal too narrow for the label, no dashes rather than a wrapped border
         let dash_count = width.saturating_sub(2 + label_len);
-        let dashes = "─".repeat(dash_count.max(1));
+        let dashes = chars.horizontal.repeat(dash_count);
         format!(
             "{}{}[{}]{}\n",
             ANSI_RESET,
-            "┌─".dimmed(),
+            prefix.dimmed(),
             label.cyan(),
             dashes.dimmed()
         )
@@ -60,51 +207,152 @@ impl CodeBuffer {
 
     /// Generate footer line for code block with dynamic width
     fn format_footer(&self) -> String {
+        let chars = self.box_chars();
+        let prefix = format!("{}{}", chars.bottom_left, chars.horizontal);
+        match self.options.border_style {
+            BorderStyle::Hidden => return String::new(),
+            BorderStyle::Compact => return format!("\n{}{}", ANSI_RESET, prefix.dimmed()),
+            BorderStyle::Full => {}
+        }
+
         let width = self.compute_box_width();
         // Account for "└─" prefix (2 chars) to mirror the header
         let dash_count = width.saturating_sub(2);
-        let dashes = "─".repeat(dash_count.max(1));
+        let dashes = chars.horizontal.repeat(dash_count);
         format!(
-            "\n{}{}{}", 
-            ANSI_RESET, 
-            "└─".dimmed(), 
+            "\n{}{}{}",
+            ANSI_RESET,
+            prefix.dimmed(),
             dashes.dimmed()
         )
     }
 
-    fn find_code_block_end(&self, text: &str) -> Option<usize> {
-        // Look for ``` at the beginning of a line
-        if text.starts_with("```") {
-            return Some(0);
-        }
+    /// Find the closing fence in `text`, the code block content that follows what has been
+    /// buffered so far: a complete line that starts with ``` after optional indentation.
+    /// Returns where the fence line starts and where it ends (at its newline), plus text after
+    /// the backticks worth keeping: a stray language tag (```rust) or whitespace is dropped,
+    /// longer text is returned to be shown after the block.
+    fn find_code_block_end(&self, text: &str) -> Option<(usize, usize, String)> {
+        // The first line of `text` may continue a line already in the block
+        let mut line_start = if self.code_block_content.is_empty()
+            || self.code_block_content.ends_with('\n')
+        {
+            0
+        } else {
+            text.find('\n')? + 1
+        };
 
-        // Look for \n``` in the text
-        if let Some(pos) = text.find("\n```") {
-            // Return position of the backticks (after the newline)
-            return Some(pos + 1);
+        while line_start < text.len() {
+            // An unfinished line is decided once the rest of it arrives
+            let line_end = line_start + text[line_start..].find('\n')?;
+            let line = &text[line_start..line_end];
+            if let Some(trailing) = self.strip_closing_fence(line) {
+                let trailing = trailing.trim();
+                let kept = if trailing.contains(char::is_whitespace) {
+                    trailing.to_string()
+                } else {
+                    String::new()
+                };
+                return Some((line_start, line_end, kept));
+            }
+            line_start = line_end + 1;
         }
 
         None
     }
 
-    fn highlight_code(&self, code: &str, lang: Option<&str>) -> String {
-        let theme = &self.theme_set.themes["Solarized (dark)"];
+    /// What follows the delimiter when `line` closes the current block: after optional
+    /// indentation, a run of the opening fence's character at least as long as it
+    fn strip_closing_fence<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let marker = self.fence.chars().next()?;
+        let start = line.trim_start();
+        let run = start.chars().take_while(|&c| c == marker).count();
+        (run >= self.fence.len()).then(|| &start[run..])
+    }
 
-        let syntax = if let Some(lang) = lang {
-            self.syntax_set
-                .find_syntax_by_token(lang)
-                .or_else(|| self.syntax_set.find_syntax_by_extension(lang))
-                .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
+    /// Length of the unfinished last line of `text` when it could still become a closing
+    /// fence, so it is held back rather than added to the block
+    fn pending_fence_len(&self, text: &str) -> usize {
+        let last_line = &text[text.rfind('\n').map_or(0, |pos| pos + 1)..];
+        let start = last_line.trim_start();
+        if self.fence.starts_with(start) || self.strip_closing_fence(last_line).is_some() {
+            last_line.len()
         } else {
-            self.syntax_set.find_syntax_plain_text()
+            0
+        }
+    }
+
+    /// Resolve a fence label to a syntax, trying the alias map before falling back to plain text
+    /// Only the first word is used, so labels like `python {.numberLines}` still highlight
+    fn find_syntax(&self, lang: Option<&str>) -> &SyntaxReference {
+        let token = lang
+            .and_then(|l| l.split(|c: char| c.is_whitespace() || c == ',').next())
+            .map(|t| t.trim_start_matches('.').to_lowercase())
+            .filter(|t| !t.is_empty());
+
+        let Some(token) = token else {
+            return self.syntax_set.find_syntax_plain_text();
         };
 
+        let alias = LANGUAGE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == token)
+            .and_then(|(_, name)| self.syntax_set.find_syntax_by_name(name));
+
+        alias
+            .or_else(|| self.syntax_set.find_syntax_by_token(&token))
+            .or_else(|| self.syntax_set.find_syntax_by_extension(&token))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
+    }
+
+    /// Name of the syntax used to highlight a fence label (e.g. `ts` -> `JavaScript`)
+    pub fn syntax_name(&self, lang: Option<&str>) -> &str {
+        &self.find_syntax(lang).name
+    }
+
+    /// Highlight `code`, whose first line is line `first_line` (0-based) of the current block
+    fn highlight_code(&self, code: &str, lang: Option<&str>, first_line: usize) -> String {
+        let theme = &self.theme_set.themes["Solarized (dark)"];
+        let syntax = self.find_syntax(lang);
+
         let mut highlighter = HighlightLines::new(syntax, theme);
         let mut output = String::new();
 
-        for line in LinesWithEndings::from(code) {
+        for (offset, line) in LinesWithEndings::from(code).enumerate() {
+            // Lines of an indented fence (e.g. in a list item) lose that indentation
+            let indent = line
+                .bytes()
+                .take(self.fence_indent)
+                .take_while(|b| *b == b' ' || *b == b'\t')
+                .count();
+            let line = &line[indent..];
+            let number = first_line + offset;
+            let plain = self
+                .options
+                .max_highlight_lines
+                .is_some_and(|max| number >= max);
+            // Very long blocks (a dumped file) would stall streaming in the highlighter
+            if plain && Some(number) == self.options.max_highlight_lines {
+                output.push_str(&format!(
+                    "{}{}\n",
+                    ANSI_RESET,
+                    format!("[highlighting off after {} lines]", number).dimmed()
+                ));
+            }
+            if self.options.code_line_numbers {
+                let number = format!(
+                    "{:>width$} ",
+                    number + 1,
+                    width = LINE_NUMBER_WIDTH
+                );
+                output.push_str(&number.dimmed().to_string());
+            }
+            if plain {
+                output.push_str(line);
+                continue;
+            }
             let ranges: Vec<(Style, &str)> =
-                highlighter.highlight_line(line, &self.syntax_set).unwrap();
+                highlighter.highlight_line(line, self.syntax_set).unwrap();
             let escaped = as_24_bit_terminal_escaped(&ranges[..], false);
             output.push_str(&escaped);
         }
@@ -119,12 +367,13 @@ impl CodeBuffer {
         while !self.buffer.is_empty() {
             if !self.in_code_block {
                 // Look for code block start
-                if let Some(code_start) = self.buffer.find("```") {
-                    // Output everything before the code block
-                    output.push_str(&self.buffer[..code_start]);
+                if let Some((code_start, fence, indent)) = find_code_block_start(&self.buffer) {
+                    // Output everything before the code block, less the fence's indentation
+                    let indentation = self.buffer[code_start - indent..code_start].to_string();
+                    output.push_str(&self.buffer[..code_start - indent]);
 
                     // Extract the code block marker and language
-                    self.buffer = self.buffer[code_start + 3..].to_string();
+                    self.buffer = self.buffer[code_start + fence.len()..].to_string();
 
                     // Check if we have a complete first line with language
                     if let Some(newline_pos) = self.buffer.find('\n') {
@@ -139,24 +388,28 @@ impl CodeBuffer {
                         self.in_code_block = true;
                         self.code_block_content.clear();
                         self.displayed_lines = 0;
+                        self.fence = fence;
+                        self.fence_indent = indent;
 
                         // Output code block header
                         let label = self.code_block_lang.as_deref().unwrap_or("code");
                         output.push_str(&self.format_header(label));
                     } else {
                         // Incomplete first line, wait for more content
-                        self.buffer = format!("```{}", self.buffer);
+                        self.buffer = format!("{}{}{}", indentation, fence, self.buffer);
                         break;
                     }
                 } else {
-                    // No code block found, output everything and clear buffer
-                    output.push_str(&self.buffer);
-                    self.buffer.clear();
+                    // No code block found, output everything but a possible fence start
+                    let held = self.buffer.len() - partial_fence_len(&self.buffer);
+                    output.push_str(&self.buffer[..held]);
+                    self.buffer.drain(..held);
+                    break;
                 }
             } else {
                 // In code block, look for end marker at the beginning of a line
                 let code_end = self.find_code_block_end(&self.buffer);
-                if let Some(code_end) = code_end {
+                if let Some((code_end, fence_end, trailing)) = code_end {
                     // Add content before the end marker to code block
                     // Strip trailing newline if present (the \n before ```)
                     let content_before_marker = &self.buffer[..code_end];
@@ -183,7 +436,11 @@ impl CodeBuffer {
                                     remaining_content
                                 };
                             let highlighted = self
-                                .highlight_code(&final_content, self.code_block_lang.as_deref());
+                                .highlight_code(
+                                &final_content,
+                                self.code_block_lang.as_deref(),
+                                self.displayed_lines,
+                            );
                             output.push_str(&highlighted);
                         }
                     }
@@ -192,7 +449,7 @@ impl CodeBuffer {
                     output.push_str(&self.format_footer());
 
                     // Consume the closing ``` and check what comes after
-                    let after_marker = &self.buffer[code_end + 3..];
+                    let after_marker = format!("{}{}", trailing, &self.buffer[fence_end..]);
 
                     // Only add newline after footer if there's content following or a newline
                     if !after_marker.is_empty() {
@@ -200,14 +457,17 @@ impl CodeBuffer {
                     }
 
                     // Reset state
-                    self.buffer = after_marker.to_string();
+                    self.buffer = after_marker;
                     self.in_code_block = false;
                     self.code_block_content.clear();
                     self.code_block_lang = None;
+                    self.fence.clear();
+                    self.fence_indent = 0;
                     self.displayed_lines = 0;
                 } else {
                     // Still in code block, accumulate content and highlight incrementally
-                    self.code_block_content.push_str(&self.buffer);
+                    let held = self.buffer.len() - self.pending_fence_len(&self.buffer);
+                    self.code_block_content.push_str(&self.buffer[..held]);
 
                     // Count complete lines in the accumulated content
                     let complete_lines: Vec<&str> = self.code_block_content.lines().collect();
@@ -233,13 +493,17 @@ impl CodeBuffer {
                         if !new_lines.is_empty() {
                             let new_content = new_lines.join("\n") + "\n";
                             let highlighted =
-                                self.highlight_code(&new_content, self.code_block_lang.as_deref());
+                                self.highlight_code(
+                                    &new_content,
+                                    self.code_block_lang.as_deref(),
+                                    self.displayed_lines,
+                                );
                             output.push_str(&highlighted);
                             self.displayed_lines = lines_to_display;
                         }
                     }
 
-                    self.buffer.clear();
+                    self.buffer.drain(..held);
                     break;
                 }
             }
@@ -252,6 +516,11 @@ impl CodeBuffer {
         let mut output = String::new();
 
         if self.in_code_block {
+            // A held-back line is content unless it is the closing fence
+            if self.strip_closing_fence(&self.buffer).is_none() {
+                let held = std::mem::take(&mut self.buffer);
+                self.code_block_content.push_str(&held);
+            }
             // Unterminated code block
             if !self.code_block_content.is_empty() {
                 // Highlight any remaining lines that haven't been displayed
@@ -267,7 +536,11 @@ impl CodeBuffer {
                             remaining_content
                         };
                         let highlighted =
-                            self.highlight_code(&final_content, self.code_block_lang.as_deref());
+                            self.highlight_code(
+                                &final_content,
+                                self.code_block_lang.as_deref(),
+                                self.displayed_lines,
+                            );
                         output.push_str(&highlighted);
                     }
                 }
@@ -281,8 +554,16 @@ impl CodeBuffer {
         self.code_block_content.clear();
         self.in_code_block = false;
         self.code_block_lang = None;
+        self.fence.clear();
+        self.fence_indent = 0;
         self.displayed_lines = 0;
 
         output
     }
 }
+
+impl Default for CodeBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,8 @@
 pub mod highlight;
+pub mod options;
 pub mod output;
+pub mod spinner;
 
+pub use options::RenderOptions;
 pub use output::{display_content, display_reasoning, display_tool_error, display_tool_result};
-
+pub use spinner::Spinner;
@@ -0,0 +1,153 @@
+use crate::config::{expand_prompt_variables, UiConfig, DEFAULT_MAX_HIGHLIGHT_LINES};
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+/// How code block borders are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BorderStyle {
+    /// Borders stretched to the terminal width
+    #[default]
+    Full,
+    /// Short borders without trailing dashes (piped output with `ui.piped_borders`)
+    Compact,
+    /// No borders at all (piped output by default)
+    Hidden,
+}
+
+/// Resolved rendering settings shared by every `CodeBuffer`
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub ascii_boxes: bool,
+    pub border_style: BorderStyle,
+    pub code_line_numbers: bool,
+    /// Emit OSC-8 hyperlinks for citations
+    pub hyperlinks: bool,
+    /// Route reasoning blocks to stderr (`--reasoning-to-stderr`)
+    pub reasoning_to_stderr: bool,
+    /// `ui.answer_header` template, printed before each answer
+    pub answer_header: Option<String>,
+    /// Exact border width (`--width` / `AI_WIDTH`); None follows the terminal
+    pub width: Option<usize>,
+    /// Lines of a code block highlighted before falling back to plain text; None = no limit
+    pub max_highlight_lines: Option<usize>,
+}
+
+impl RenderOptions {
+    pub fn from_config(config: &UiConfig) -> Self {
+        let ascii_boxes = config.ascii_boxes.unwrap_or_else(|| !locale_is_utf8());
+
+        // Long dashed lines only make sense on a terminal; keep files and pipes clean
+        let border_style = if io::stdout().is_terminal() {
+            BorderStyle::Full
+        } else if config.piped_borders {
+            BorderStyle::Compact
+        } else {
+            BorderStyle::Hidden
+        };
+
+        // Escape sequences would leak into files and pipes
+        let hyperlinks = config
+            .hyperlinks
+            .unwrap_or_else(terminal_supports_hyperlinks)
+            && io::stdout().is_terminal()
+            && colored::control::SHOULD_COLORIZE.should_colorize();
+
+        Self {
+            ascii_boxes,
+            border_style,
+            code_line_numbers: config.code_line_numbers,
+            hyperlinks,
+            reasoning_to_stderr: false,
+            answer_header: config.answer_header.clone(),
+            width: None,
+            max_highlight_lines: Some(
+                config
+                    .max_highlight_lines
+                    .unwrap_or(DEFAULT_MAX_HIGHLIGHT_LINES),
+            )
+            .filter(|&lines| lines > 0),
+        }
+    }
+
+    /// The answer header for `model`, rendered from `ui.answer_header`
+    pub fn answer_header(&self, model: &str) -> Option<String> {
+        let template = self.answer_header.as_deref()?;
+        let time = chrono::Local::now().format("%H:%M").to_string();
+        Some(expand_prompt_variables(template, |key| match key {
+            "model" => Some(model.to_string()),
+            "time" => Some(time.clone()),
+            _ => None,
+        }))
+    }
+
+    /// Options for prose blocks (reasoning, tool output) where line numbers are just noise
+    pub fn for_text_block(&self) -> Self {
+        Self {
+            code_line_numbers: false,
+            ..self.clone()
+        }
+    }
+
+    /// Options for the reasoning block, which may be written to stderr instead of stdout
+    pub fn for_reasoning(&self) -> Self {
+        let mut options = self.for_text_block();
+        if self.reasoning_to_stderr && io::stderr().is_terminal() {
+            options.border_style = BorderStyle::Full;
+        }
+        options
+    }
+
+    /// Write reasoning output to stdout, or stderr with `reasoning_to_stderr`
+    pub fn print_reasoning(&self, text: &str) {
+        let _ = self.write_reasoning(&mut io::stdout(), &mut io::stderr(), text);
+    }
+
+    /// Write reasoning output to `out`, or to `err` (the caller's stand-in for stderr)
+    /// with `reasoning_to_stderr`
+    pub fn write_reasoning(
+        &self,
+        out: &mut dyn Write,
+        err: &mut dyn Write,
+        text: &str,
+    ) -> io::Result<()> {
+        if self.reasoning_to_stderr {
+            write!(err, "{}", text)
+        } else {
+            write!(out, "{}", text)
+        }
+    }
+}
+
+/// Check whether the active locale advertises UTF-8 (LC_ALL > LC_CTYPE > LANG)
+pub fn locale_is_utf8() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty());
+
+    match locale {
+        Some(value) => {
+            let value = value.to_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        }
+        None => false,
+    }
+}
+
+/// Best-effort check for terminals known to render OSC-8 hyperlinks
+pub fn terminal_supports_hyperlinks() -> bool {
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+
+    matches!(
+        term_program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) || ["kitty", "alacritty", "foot", "wezterm"]
+        .iter()
+        .any(|name| term.contains(name))
+        || env::var("WT_SESSION").is_ok()
+        || env::var("VTE_VERSION")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|v| v >= 5000)
+}
@@ -1,11 +1,12 @@
 use crate::ui::highlight::CodeBuffer;
+use crate::ui::options::RenderOptions;
 
 /// Display a tool result in a boxed format
-pub fn display_tool_result(name: &str, result: &str) {
+pub fn display_tool_result(name: &str, result: &str, options: &RenderOptions) {
     // Avoid double newline if result_text already ends with one
     let sep = if result.ends_with('\n') { "" } else { "\n" };
     let tool_block = format!("```TOOL: {}\n{}{}\n```", name, result, sep);
-    let mut code_buffer = CodeBuffer::new();
+    let mut code_buffer = CodeBuffer::with_options(options.for_text_block());
     let formatted = code_buffer.append(&tool_block);
     if !formatted.is_empty() {
         print!("{}", formatted);
@@ -18,11 +19,11 @@ pub fn display_tool_result(name: &str, result: &str) {
 }
 
 /// Display a tool error in a boxed format
-pub fn display_tool_error(name: &str, error: &str) {
+pub fn display_tool_error(name: &str, error: &str, options: &RenderOptions) {
     // Avoid double newline if error_text already ends with one
     let sep = if error.ends_with('\n') { "" } else { "\n" };
     let tool_error_block = format!("```TOOL ERROR: {}\n{}{}\n```", name, error, sep);
-    let mut code_buffer = CodeBuffer::new();
+    let mut code_buffer = CodeBuffer::with_options(options.for_text_block());
     let formatted = code_buffer.append(&tool_error_block);
     if !formatted.is_empty() {
         print!("{}", formatted);
@@ -35,7 +36,7 @@ pub fn display_tool_error(name: &str, error: &str) {
 }
 
 /// Display reasoning content in a boxed format
-pub fn display_reasoning(reasoning: &str) {
+pub fn display_reasoning(reasoning: &str, options: &RenderOptions) {
     // Clean up markdown formatting for display
     let display_reasoning = reasoning.replace("**", "").trim().to_string();
 
@@ -43,23 +44,24 @@ pub fn display_reasoning(reasoning: &str) {
     // Avoid double newline if content already ends with one
     let sep = if display_reasoning.ends_with('\n') { "" } else { "\n" };
     let reasoning_block = format!("```REASONING\n{}{}\n```", display_reasoning, sep);
-    let mut reasoning_code_buffer = CodeBuffer::new();
+    let mut reasoning_code_buffer = CodeBuffer::with_options(options.for_reasoning());
     let formatted = reasoning_code_buffer.append(&reasoning_block);
     if !formatted.is_empty() {
-        println!();
-        print!("{}", formatted);
+        options.print_reasoning("\n");
+        options.print_reasoning(&formatted);
     }
     let remaining = reasoning_code_buffer.flush();
     if !remaining.is_empty() {
-        print!("{}", remaining.trim_end());
+        options.print_reasoning(remaining.trim_end());
     }
-    println!();
+    options.print_reasoning("\n");
 }
 
 /// Display content with syntax highlighting
-pub fn display_content(content: &str) {
-    let mut code_buffer = CodeBuffer::new();
-    let formatted = code_buffer.append(content);
+pub fn display_content(content: &str, options: &RenderOptions) {
+    let mut code_buffer = CodeBuffer::with_options(options.clone());
+    let json_block = json_response_block(content);
+    let formatted = code_buffer.append(json_block.as_deref().unwrap_or(content));
     if !formatted.is_empty() {
         print!("{}", formatted);
     }
@@ -70,3 +72,83 @@ pub fn display_content(content: &str) {
     println!();
 }
 
+/// If the whole response is a JSON document (e.g. structured output), return it
+/// pretty-printed as a json code block. JSON embedded in prose is left alone.
+pub fn json_response_block(content: &str) -> Option<String> {
+    let trimmed = content.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(trimmed).ok()?;
+    let pretty = serde_json::to_string_pretty(&value).ok()?;
+    Some(format!("```json\n{}\n```\n", pretty))
+}
+
+/// Whether a partly streamed answer could still become a whole JSON document: brackets
+/// balance so far, nothing follows the outermost one, and outside strings there is only
+/// JSON punctuation, numbers and `true`/`false`/`null`. "[Note] ..." or "[a link](...)"
+/// fails as soon as the prose starts.
+pub fn may_be_json_document(prefix: &str) -> bool {
+    JsonPrefixScanner::default().feed(prefix)
+}
+
+/// [`may_be_json_document`] for a streamed answer: the scanner keeps its place between
+/// chunks, so each delta is looked at once
+#[derive(Debug, Default)]
+pub struct JsonPrefixScanner {
+    open: Vec<char>,
+    in_string: bool,
+    escaped: bool,
+    closed: bool,
+    failed: bool,
+}
+
+impl JsonPrefixScanner {
+    /// Scan the next chunk; returns whether everything fed so far may still be JSON
+    pub fn feed(&mut self, chunk: &str) -> bool {
+        if !self.failed {
+            self.failed = chunk.chars().any(|c| !self.accept(c));
+        }
+        !self.failed
+    }
+
+    fn accept(&mut self, c: char) -> bool {
+        if self.in_string {
+            match c {
+                _ if self.escaped => self.escaped = false,
+                '\\' => self.escaped = true,
+                '"' => self.in_string = false,
+                _ => {}
+            }
+            return true;
+        }
+        if c.is_whitespace() {
+            return true;
+        }
+        if self.closed {
+            return false;
+        }
+        match c {
+            '{' | '[' => self.open.push(c),
+            '}' | ']' => {
+                let expected = if c == '}' { '{' } else { '[' };
+                if self.open.pop() != Some(expected) {
+                    return false;
+                }
+                self.closed = self.open.is_empty();
+            }
+            '"' => self.in_string = true,
+            ',' | ':' | '-' | '+' | '.' => {}
+            _ if c.is_ascii_digit() => {}
+            // Letters of true, false, null and exponents
+            't' | 'r' | 'u' | 'e' | 'f' | 'a' | 'l' | 's' | 'n' | 'E' => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Wrap `text` in an OSC-8 escape so supporting terminals render it as a link to `url`
+pub fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
@@ -0,0 +1,79 @@
+use crate::ui::options::locale_is_utf8;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(80);
+const UNICODE_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_FRAMES: &[&str] = &["|", "/", "-", "\\"];
+
+/// Spinner on stderr while waiting for the first token. Cleared on `stop` or drop;
+/// never drawn when stderr isn't a terminal, so captured output is unaffected.
+pub struct Spinner {
+    /// Set under the stderr lock, so no frame is drawn after the line is cleared
+    stopped: Arc<AtomicBool>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(enabled: bool, message: &str) -> Self {
+        if !enabled || !io::stderr().is_terminal() {
+            return Self::hidden();
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let frames = if locale_is_utf8() {
+            UNICODE_FRAMES
+        } else {
+            ASCII_FRAMES
+        };
+        let message = message.to_string();
+        let flag = Arc::clone(&stopped);
+        let task = tokio::spawn(async move {
+            for frame in frames.iter().cycle() {
+                {
+                    let mut stderr = io::stderr().lock();
+                    if flag.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let _ = write!(stderr, "\r{} {}", frame, message);
+                    let _ = stderr.flush();
+                }
+                tokio::time::sleep(FRAME_INTERVAL).await;
+            }
+        });
+
+        Self {
+            stopped,
+            task: Some(task),
+        }
+    }
+
+    /// A spinner that draws nothing
+    pub fn hidden() -> Self {
+        Self {
+            stopped: Arc::new(AtomicBool::new(true)),
+            task: None,
+        }
+    }
+
+    /// Stop and clear the spinner line; later calls do nothing
+    pub fn stop(&mut self) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+        let mut stderr = io::stderr().lock();
+        self.stopped.store(true, Ordering::SeqCst);
+        task.abort();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
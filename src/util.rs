@@ -0,0 +1,219 @@
+use regex::Regex;
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Truncate a string to at most `max_bytes` bytes for log previews, appending "..." when cut
+/// The cut is moved back to the nearest char boundary so multibyte UTF-8 never panics
+pub fn truncate_for_display(s: &str, max_bytes: usize) -> String {
+    if s.len() <= max_bytes {
+        return s.to_string();
+    }
+
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}...", &s[..end])
+}
+
+/// Keep the beginning and end of `s` within about `max_bytes`, with a note of how much was
+/// left out in between
+pub fn shorten_middle(s: &str, max_bytes: usize) -> String {
+    if s.len() <= max_bytes {
+        return s.to_string();
+    }
+
+    let mut head_end = max_bytes / 2;
+    while !s.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = s.len() - (max_bytes - max_bytes / 2);
+    while !s.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+
+    format!(
+        "{}\n\n[... {} bytes omitted ...]\n\n{}",
+        &s[..head_end],
+        tail_start - head_end,
+        &s[tail_start..]
+    )
+}
+
+/// Frame the user's command with `--prepend` / `--append` text, separated by blank lines
+pub fn wrap_command(command: &str, prepend: Option<&str>, append: Option<&str>) -> String {
+    [prepend, Some(command), append]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Apply a JSON merge patch (RFC 7386): objects merge key by key, `null` removes a key,
+/// anything else (arrays included) replaces the value
+pub fn merge_json(base: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *base = patch.clone();
+        return;
+    };
+    if !base.is_object() {
+        *base = Value::Object(Default::default());
+    }
+    let base = base.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if value.is_null() {
+            base.remove(key);
+        } else {
+            merge_json(base.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Reduce Markdown to plain text: fences, heading marks, emphasis, inline-code backticks,
+/// quote markers and rules are dropped, links become `text (url)`. Code inside fences and
+/// backticks is kept verbatim.
+pub fn strip_markdown(text: &str) -> String {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static RULE: OnceLock<Regex> = OnceLock::new();
+    static BULLET: OnceLock<Regex> = OnceLock::new();
+    static CODE_SPAN: OnceLock<Regex> = OnceLock::new();
+    let heading = cached_regex(&HEADING, r"^#{1,6}\s+");
+    let rule = cached_regex(&RULE, r"^(?:[-*_]\s*){3,}$");
+    let bullet = cached_regex(&BULLET, r"^(\s*)[*+]\s+");
+    let code_span = cached_regex(&CODE_SPAN, r"`([^`]+)`");
+
+    let mut lines = Vec::new();
+    // The delimiter of the open fence (``` or ~~~, possibly longer)
+    let mut fence: Option<String> = None;
+    for line in text.lines() {
+        let start = line.trim_start();
+        let marker = start.chars().next().filter(|&c| c == '`' || c == '~');
+        let run = marker.map_or(0, |m| start.chars().take_while(|&c| c == m).count());
+        match &fence {
+            // Closed by a run of the same character at least as long, with nothing after it
+            Some(open)
+                if marker == open.chars().next()
+                    && run >= open.len()
+                    && start[run..].trim().is_empty() =>
+            {
+                fence = None;
+                continue;
+            }
+            Some(_) => {
+                lines.push(line.to_string());
+                continue;
+            }
+            None if run >= 3 => {
+                fence = Some(start[..run].to_string());
+                continue;
+            }
+            None => {}
+        }
+        if rule.is_match(line.trim()) {
+            continue;
+        }
+
+        let line = match line.trim_start().strip_prefix('>') {
+            Some(quoted) => quoted.trim_start_matches('>').trim_start(),
+            None => line,
+        };
+        let line = heading.replace(line, "");
+        let line = bullet.replace(&line, "${1}- ");
+
+        // Inline formatting applies only outside `code` spans
+        let mut plain = String::new();
+        let mut last = 0;
+        for span in code_span.captures_iter(&line) {
+            let whole = span.get(0).unwrap();
+            plain.push_str(&strip_inline_markdown(&line[last..whole.start()]));
+            plain.push_str(&span[1]);
+            last = whole.end();
+        }
+        plain.push_str(&strip_inline_markdown(&line[last..]));
+        lines.push(plain);
+    }
+
+    lines.join("\n")
+}
+
+fn strip_inline_markdown(text: &str) -> String {
+    static IMAGE: OnceLock<Regex> = OnceLock::new();
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static STRONG: OnceLock<Regex> = OnceLock::new();
+    static EMPHASIS: OnceLock<Regex> = OnceLock::new();
+    static STRIKE: OnceLock<Regex> = OnceLock::new();
+    let image = cached_regex(&IMAGE, r"!\[([^\]]*)\]\([^)]*\)");
+    let link = cached_regex(&LINK, r"\[([^\]]+)\]\(([^)\s]+)\)");
+    let strong = cached_regex(&STRONG, r"\*\*(\S(?:.*?\S)?)\*\*|__(\S(?:.*?\S)?)__");
+    let emphasis = cached_regex(&EMPHASIS, r"\*(\S(?:[^*]*?\S)?)\*");
+    let strike = cached_regex(&STRIKE, r"~~(.+?)~~");
+
+    let text = image.replace_all(text, "$1");
+    let text = link.replace_all(&text, "$1 ($2)");
+    let text = strong.replace_all(&text, "$1$2");
+    let text = emphasis.replace_all(&text, "$1");
+    strike.replace_all(&text, "$1").into_owned()
+}
+
+/// A regex compiled on first use and shared from then on
+fn cached_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Write the plain assistant answer to `path`, overwriting or appending
+/// A trailing newline is added so appended answers don't run together
+pub fn write_answer(path: &Path, content: &str, append: bool) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
+    if !content.ends_with('\n') {
+        file.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Clipboard helpers tried in order; the first one that runs successfully wins
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy text to the system clipboard using the platform's clipboard command
+/// Returns the name of the command used, or an error if none is available (headless/CI)
+pub fn copy_to_clipboard(text: &str) -> Result<&'static str, String> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let child = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        let written = child
+            .stdin
+            .take()
+            .map(|mut stdin| stdin.write_all(text.as_bytes()).is_ok())
+            .unwrap_or(false);
+        if child.wait().map(|s| s.success()).unwrap_or(false) && written {
+            return Ok(program);
+        }
+    }
+
+    Err("no clipboard available (tried pbcopy, wl-copy, xclip, xsel, clip.exe)".to_string())
+}
@@ -1,5 +1,14 @@
-use cmd2ai::api::response::{extract_content, extract_reasoning, parse_tool_calls};
+use cmd2ai::api::{
+    format_model_list, models_url, parse_model_list, request_id_from_headers, suggest_models,
+};
+use cmd2ai::api::response::{
+    extract_citations, extract_content, extract_finish_reason, extract_reasoning, extract_refusal,
+    extract_usage, merge_tool_call_delta, parse_tool_calls, repair_tool_arguments,
+};
+use cmd2ai::api::streaming::{format_latency, format_stream_stats, normalize_citation_url};
+use reqwest::header::{HeaderMap, HeaderValue};
 use serde_json::json;
+use std::time::Duration;
 
 #[test]
 fn test_extract_content_with_content() {
@@ -112,6 +121,77 @@ fn test_extract_reasoning_with_reasoning() {
     assert_eq!(reasoning, Some("Let me think about this...".to_string()));
 }
 
+#[test]
+fn test_extract_reasoning_from_reasoning_key() {
+    let response = json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": "Done",
+                "reasoning": "Checking the files first"
+            }
+        }]
+    });
+
+    let reasoning = extract_reasoning(&response).unwrap();
+    assert_eq!(reasoning, Some("Checking the files first".to_string()));
+}
+
+#[test]
+fn test_extract_reasoning_prefers_reasoning_content() {
+    let response = json!({
+        "choices": [{
+            "message": {
+                "reasoning_content": "From reasoning_content",
+                "reasoning": "From reasoning"
+            }
+        }]
+    });
+    let reasoning = extract_reasoning(&response).unwrap();
+    assert_eq!(reasoning, Some("From reasoning_content".to_string()));
+
+    // An empty value does not hide the other key
+    let response = json!({
+        "choices": [{
+            "message": {
+                "reasoning_content": "",
+                "reasoning": "From reasoning"
+            }
+        }]
+    });
+    let reasoning = extract_reasoning(&response).unwrap();
+    assert_eq!(reasoning, Some("From reasoning".to_string()));
+}
+
+#[test]
+fn test_extract_reasoning_from_reasoning_details() {
+    let response = json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "reasoning": null,
+                "reasoning_details": [
+                    {"type": "reasoning.summary", "summary": "Summarized plan"},
+                    {"type": "reasoning.encrypted", "data": "opaque"},
+                    {"type": "reasoning.text", "text": "Step one"}
+                ]
+            }
+        }]
+    });
+
+    let reasoning = extract_reasoning(&response).unwrap();
+    assert_eq!(reasoning, Some("Summarized plan\n\nStep one".to_string()));
+
+    let encrypted_only = json!({
+        "choices": [{
+            "message": {
+                "reasoning_details": [{"type": "reasoning.encrypted", "data": "opaque"}]
+            }
+        }]
+    });
+    assert_eq!(extract_reasoning(&encrypted_only).unwrap(), None);
+}
+
 #[test]
 fn test_extract_reasoning_without_reasoning() {
     let response = json!({
@@ -127,3 +207,266 @@ fn test_extract_reasoning_without_reasoning() {
     assert_eq!(reasoning, None);
 }
 
+#[test]
+fn test_extract_finish_reason_usage_and_citations() {
+    let response = json!({
+        "choices": [{
+            "finish_reason": "length",
+            "message": {
+                "role": "assistant",
+                "content": "See the docs",
+                "annotations": [
+                    {"type": "url_citation", "url_citation": {"url": "https://example.com/a/", "title": "A"}},
+                    {"type": "url_citation", "url_citation": {"url": "https://example.com/a", "title": "Again"}},
+                    {"type": "file", "file": {}}
+                ]
+            }
+        }],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 20, "total_tokens": 30}
+    });
+
+    assert_eq!(extract_finish_reason(&response).as_deref(), Some("length"));
+    let usage = extract_usage(&response).unwrap();
+    assert_eq!(
+        (usage.prompt_tokens, usage.completion_tokens, usage.total_tokens),
+        (10, 20, 30)
+    );
+    let citations = extract_citations(&response);
+    assert_eq!(citations.len(), 1);
+    assert_eq!(citations[0].title, "A");
+
+    let bare = json!({"choices": [{"message": {"content": "hi"}}]});
+    assert_eq!(extract_finish_reason(&bare), None);
+    assert!(extract_usage(&bare).is_none());
+    assert!(extract_citations(&bare).is_empty());
+}
+
+#[test]
+fn test_repair_tool_arguments_fixes_common_mistakes() {
+    assert_eq!(
+        repair_tool_arguments(r#"{"path": "a.txt",}"#),
+        Some(json!({"path": "a.txt"}))
+    );
+    assert_eq!(
+        repair_tool_arguments(r#"{'path': 'it\'s "here".txt'}"#),
+        Some(json!({"path": "it's \"here\".txt"}))
+    );
+    assert_eq!(
+        repair_tool_arguments("{\"text\": \"line one\nline two\"}"),
+        Some(json!({"text": "line one\nline two"}))
+    );
+    assert_eq!(
+        repair_tool_arguments("```json\n{\"items\": [1, 2,]}\n```"),
+        Some(json!({"items": [1, 2]}))
+    );
+}
+
+#[test]
+fn test_repair_tool_arguments_leaves_valid_and_hopeless_input_alone() {
+    assert_eq!(repair_tool_arguments(r#"{"path": "a.txt"}"#), None);
+    assert_eq!(repair_tool_arguments(r#"{"path": "#), None);
+    // Commas inside strings are not trailing commas
+    assert_eq!(
+        repair_tool_arguments(r#"{"text": "a,}", }"#),
+        Some(json!({"text": "a,}"}))
+    );
+}
+
+#[test]
+fn test_format_stream_stats() {
+    let stats = format_stream_stats(Duration::from_secs(2), 800, 1);
+    assert_eq!(stats, "[AI] Stream: 2.0s, 800 chars, ~100 tokens/s, 1 citation");
+
+    let stats = format_stream_stats(Duration::ZERO, 0, 0);
+    assert_eq!(stats, "[AI] Stream: 0.0s, 0 chars, ~0 tokens/s, 0 citations");
+}
+
+#[test]
+fn test_format_latency() {
+    let latency = format_latency(Some(Duration::from_millis(840)), Duration::from_millis(3210));
+    assert_eq!(latency, "[AI] Latency: first token 0.84s, total 3.21s");
+
+    let latency = format_latency(None, Duration::from_secs(1));
+    assert_eq!(latency, "[AI] Latency: first token none, total 1.00s");
+}
+
+#[test]
+fn test_normalize_citation_url() {
+    let expected = "https://example.com/page";
+    assert_eq!(normalize_citation_url("https://example.com/page"), expected);
+    assert_eq!(normalize_citation_url("https://example.com/page/"), expected);
+    assert_eq!(normalize_citation_url("https://example.com/page#section"), expected);
+    assert_eq!(
+        normalize_citation_url("https://example.com/page?utm_source=x&fbclid=y"),
+        expected
+    );
+    assert_eq!(
+        normalize_citation_url("https://example.com/page/?id=7&utm_medium=email"),
+        "https://example.com/page?id=7"
+    );
+    assert_eq!(normalize_citation_url("https://example.com/"), "https://example.com");
+    assert_eq!(normalize_citation_url("not a url/"), "not a url");
+}
+
+#[test]
+fn test_request_id_from_headers() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(request_id_from_headers(&headers), None);
+
+    headers.insert("x-generation-id", HeaderValue::from_static("gen-123"));
+    assert_eq!(request_id_from_headers(&headers), Some("gen-123".to_string()));
+
+    headers.insert("x-request-id", HeaderValue::from_static("req-456"));
+    assert_eq!(request_id_from_headers(&headers), Some("req-456".to_string()));
+}
+
+#[test]
+fn test_models_url_sits_next_to_chat_completions() {
+    assert_eq!(
+        models_url("http://localhost:11434/v1/chat/completions"),
+        "http://localhost:11434/v1/models"
+    );
+    assert_eq!(
+        models_url("https://openrouter.ai/api/v1/chat/completions/"),
+        "https://openrouter.ai/api/v1/models"
+    );
+}
+
+#[test]
+fn test_parse_model_list_shapes() {
+    let openrouter = json!({"data": [
+        {
+            "id": "openai/gpt-4o-mini",
+            "context_length": 128000,
+            "pricing": {"prompt": "0.00000015", "completion": "0.0000006"}
+        },
+        {"id": "anthropic/claude-3.5-sonnet", "context_length": 200000},
+        {"name": "no id, skipped"}
+    ]});
+    let models = parse_model_list(&openrouter);
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].id, "anthropic/claude-3.5-sonnet");
+    assert_eq!(models[1].context_length, Some(128000));
+    assert_eq!(
+        format_model_list(&models),
+        "anthropic/claude-3.5-sonnet  200k context\n\
+         openai/gpt-4o-mini           128k context, $0.15/$0.60 per 1M tokens\n"
+    );
+
+    let openai = json!({"object": "list", "data": [{"id": "llama3", "object": "model"}]});
+    let models = parse_model_list(&openai);
+    assert_eq!(format_model_list(&models), "llama3\n");
+
+    let bare = json!([{"id": "b"}, {"id": "a"}]);
+    let ids: Vec<String> = parse_model_list(&bare).into_iter().map(|m| m.id).collect();
+    assert_eq!(ids, ["a", "b"]);
+
+    assert!(parse_model_list(&json!({"error": "nope"})).is_empty());
+}
+
+#[test]
+fn test_suggest_models_by_edit_distance() {
+    let models = parse_model_list(&json!([
+        {"id": "openai/gpt-4o"},
+        {"id": "openai/gpt-4.1"},
+        {"id": "openai/gpt-4o-mini"},
+        {"id": "anthropic/claude-3.5-sonnet"},
+        {"id": "google/gemini-pro"}
+    ]));
+
+    // A typo in the full id, and a bare name without the provider prefix
+    assert_eq!(
+        suggest_models(&models, "anthropic/claude-3.5-sonet", 3),
+        ["anthropic/claude-3.5-sonnet"]
+    );
+    assert_eq!(
+        suggest_models(&models, "gpt-4o", 3),
+        ["openai/gpt-4o", "openai/gpt-4.1"]
+    );
+    assert_eq!(suggest_models(&models, "gpt-4o", 1), ["openai/gpt-4o"]);
+
+    // Nothing close, or the model is actually listed
+    assert!(suggest_models(&models, "mistral/mixtral-8x7b", 3).is_empty());
+    assert!(suggest_models(&models, "openai/gpt-4o", 3).is_empty());
+}
+
+#[test]
+fn test_extract_refusal() {
+    let refused = json!({"choices": [{"message": {
+        "role": "assistant",
+        "content": null,
+        "refusal": "I can't help with that."
+    }}]});
+    assert_eq!(
+        extract_refusal(&refused).as_deref(),
+        Some("I can't help with that.")
+    );
+
+    let answered = json!({"choices": [{"message": {"content": "Sure", "refusal": null}}]});
+    assert_eq!(extract_refusal(&answered), None);
+}
+
+#[test]
+fn test_parse_tool_calls_legacy_function_call() {
+    let response = json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "function_call": {
+                    "name": "read_file",
+                    "arguments": "{\"path\": \"test.txt\"}"
+                }
+            },
+            "finish_reason": "function_call"
+        }]
+    });
+
+    let calls = parse_tool_calls(&response).unwrap().unwrap();
+    assert_eq!(
+        calls,
+        vec![json!({
+            "id": "call_0",
+            "type": "function",
+            "function": {"name": "read_file", "arguments": "{\"path\": \"test.txt\"}"}
+        })]
+    );
+}
+
+#[test]
+fn test_merge_tool_call_delta_assembles_fragments_by_index() {
+    let mut calls = Vec::new();
+    for fragment in [
+        json!({
+            "index": 0,
+            "id": "call_1",
+            "type": "function",
+            "function": {"name": "read_file", "arguments": ""}
+        }),
+        json!({"index": 0, "function": {"arguments": "{\"path\": "}}),
+        json!({
+            "index": 1,
+            "id": "call_2",
+            "function": {"name": "list_dir", "arguments": "{}"}
+        }),
+        json!({"index": 0, "function": {"arguments": "\"notes.txt\"}"}}),
+    ] {
+        merge_tool_call_delta(&mut calls, &fragment);
+    }
+
+    assert_eq!(
+        calls,
+        vec![
+            json!({
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "read_file", "arguments": "{\"path\": \"notes.txt\"}"}
+            }),
+            json!({
+                "id": "call_2",
+                "type": "function",
+                "function": {"name": "list_dir", "arguments": "{}"}
+            }),
+        ]
+    );
+}
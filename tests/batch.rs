@@ -0,0 +1,33 @@
+use cmd2ai::batch::{batch_messages, parse_batch};
+use cmd2ai::models::Message;
+
+#[test]
+fn test_parse_batch_one_prompt_per_line() {
+    let prompts = parse_batch("first question\n\n  second question  \n");
+    assert_eq!(prompts, vec!["first question", "second question"]);
+}
+
+#[test]
+fn test_parse_batch_separator_blocks() {
+    let contents = "Summarize:\nline one\nline two\n---\n\n---\nTranslate hello\n";
+    let prompts = parse_batch(contents);
+    assert_eq!(
+        prompts,
+        vec!["Summarize:\nline one\nline two", "Translate hello"]
+    );
+}
+
+#[test]
+fn test_batch_messages_start_from_the_system_messages() {
+    let system = vec![Message {
+        role: "system".to_string(),
+        content: Some("Be brief".to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    }];
+    let messages = batch_messages(&system, "hi");
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].role, "system");
+    assert_eq!(messages[1].role, "user");
+    assert_eq!(messages[1].content.as_deref(), Some("hi"));
+}
@@ -0,0 +1,490 @@
+use clap::Parser;
+use cmd2ai::cli::Args;
+use cmd2ai::config::{
+    compile_routes, expand_env_var_in_string, expand_env_vars, load_env_file, parse_env_file,
+    route_model, Config, DateTimezone, JsonConfig, LocalToolConfig, LocalToolsConfig, ModelConfig,
+    OutputFilter, RoutingConfig, RoutingRule, SessionConfig, TemplateValidation, ToolsConfig,
+    UiConfig, CONFIG_SCHEMA, DEFAULT_MODEL, DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+    DEFAULT_POOL_MAX_IDLE_PER_HOST,
+};
+use jsonschema::{Draft, JSONSchema};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+// Config resolution reads process-wide env vars, so everything lives in one test
+#[test]
+fn test_config_resolution_priority() {
+    let temp_dir = TempDir::new().unwrap();
+    env::set_var("HOME", temp_dir.path());
+    env::set_var("OPENROUTER_API_KEY", "test-key");
+    for var in [
+        "AI_MODEL",
+        "AI_API_ENDPOINT",
+        "AI_SYSTEM_PROMPT",
+        "AI_VERBOSE",
+    ] {
+        env::remove_var(var);
+    }
+
+    // No config file: built-in defaults
+    let args = Args::parse_from(["ai", "hello"]);
+    let config = Config::from_env_and_args(&args).unwrap();
+    assert_eq!(config.api_key, "test-key");
+    assert_eq!(config.model, DEFAULT_MODEL);
+    assert!(config.model_fallbacks.is_empty());
+    assert!(config.inject_date);
+    assert_eq!(config.timezone, DateTimezone::Local);
+    // Session history is never deleted unless session.max_files asks for it
+    assert_eq!(config.max_session_files, 0);
+    assert_eq!(
+        config.pool_idle_timeout_secs,
+        DEFAULT_POOL_IDLE_TIMEOUT_SECS
+    );
+    assert_eq!(
+        config.pool_max_idle_per_host,
+        DEFAULT_POOL_MAX_IDLE_PER_HOST
+    );
+
+    // Config file values
+    let config_dir = temp_dir.path().join(".config").join("cmd2ai");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("cmd2ai.yaml"),
+        r#"
+api:
+  endpoint: http://localhost:11434/v1
+  pool_idle_timeout_secs: 5
+  pool_max_idle_per_host: 0
+model:
+  default_model: file/model
+  frequency_penalty: 0.5
+  presence_penalty: 0.25
+  fallbacks: [backup/model]
+  timezone: utc
+tools:
+  max_exposed: 5
+prompts:
+  review: "Review this code:\n{{input}}"
+  terse: Answer in one sentence.
+"#,
+    )
+    .unwrap();
+    let config = Config::from_env_and_args(&args).unwrap();
+    assert_eq!(config.model, "file/model");
+    assert_eq!(config.model_fallbacks, vec!["backup/model"]);
+    assert_eq!(
+        config.api_endpoint,
+        "http://localhost:11434/v1/chat/completions"
+    );
+    assert_eq!(config.timezone, DateTimezone::Utc);
+    assert_eq!(config.max_exposed_tools, Some(5));
+    assert_eq!(config.pool_idle_timeout_secs, 5);
+    assert_eq!(config.pool_max_idle_per_host, 0);
+    assert_eq!(config.frequency_penalty, Some(0.5));
+    assert_eq!(config.presence_penalty, Some(0.25));
+    assert_eq!(
+        config
+            .render_prompt_template("review", "fn main() {}")
+            .unwrap(),
+        "Review this code:\nfn main() {}"
+    );
+    assert_eq!(
+        config
+            .render_prompt_template("terse", "why is the sky blue")
+            .unwrap(),
+        "Answer in one sentence.\n\nwhy is the sky blue"
+    );
+    assert_eq!(
+        config
+            .render_prompt_template("summarize", "text")
+            .unwrap_err(),
+        "Unknown prompt 'summarize'. Available prompts: review, terse"
+    );
+
+    // Env vars beat the file, CLI args beat both
+    env::set_var("AI_MODEL", "env/model");
+    env::set_var("AI_API_ENDPOINT", "http://env.example/v1");
+    env::set_var("AI_FREQUENCY_PENALTY", "1.5");
+    env::set_var("AI_PRESENCE_PENALTY", "1.0");
+    let args = Args::parse_from([
+        "ai",
+        "--api-endpoint",
+        "http://cli.example/v1",
+        "--no-date",
+        "--presence-penalty",
+        "-1",
+        "hi",
+    ]);
+    let config = Config::from_env_and_args(&args).unwrap();
+    assert_eq!(config.model, "env/model");
+    assert_eq!(
+        config.api_endpoint,
+        "http://cli.example/v1/chat/completions"
+    );
+    assert!(!config.inject_date);
+    assert_eq!(config.date_prompt(), None);
+    assert_eq!(config.frequency_penalty, Some(1.5));
+    assert_eq!(config.presence_penalty, Some(-1.0));
+
+    // Penalties outside -2.0..2.0 are rejected
+    let args = Args::parse_from(["ai", "--frequency-penalty", "3", "hi"]);
+    let err = Config::from_env_and_args(&args).err().unwrap();
+    assert!(err.contains("frequency_penalty"), "got {}", err);
+
+    env::remove_var("AI_MODEL");
+    env::remove_var("AI_API_ENDPOINT");
+    env::remove_var("AI_FREQUENCY_PENALTY");
+    env::remove_var("AI_PRESENCE_PENALTY");
+}
+
+#[test]
+fn test_parse_env_file() {
+    let vars = parse_env_file(
+        "# comment\n\nexport OPENROUTER_API_KEY=sk-123\nAI_MODEL = openai/gpt-4o # trailing\n\
+         SINGLE='a # b'\nDOUBLE=\"line1\\nline2 \\\"q\\\"\"\nEMPTY=\n",
+    )
+    .unwrap();
+    let expected = [
+        ("OPENROUTER_API_KEY", "sk-123"),
+        ("AI_MODEL", "openai/gpt-4o"),
+        ("SINGLE", "a # b"),
+        ("DOUBLE", "line1\nline2 \"q\""),
+        ("EMPTY", ""),
+    ];
+    let expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    assert_eq!(vars, expected);
+
+    assert!(parse_env_file("no equals sign")
+        .unwrap_err()
+        .contains("line 1"));
+    assert!(parse_env_file("1BAD=x").is_err());
+    assert!(parse_env_file("A=\"open").is_err());
+}
+
+#[test]
+fn test_load_env_file_keeps_existing_variables() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join(".env");
+    fs::write(
+        &path,
+        "CMD2AI_TEST_ENV_NEW=from-file\nCMD2AI_TEST_ENV_SET=from-file\n",
+    )
+    .unwrap();
+    env::remove_var("CMD2AI_TEST_ENV_NEW");
+    env::set_var("CMD2AI_TEST_ENV_SET", "from-env");
+
+    assert_eq!(load_env_file(&path).unwrap(), 1);
+    assert_eq!(env::var("CMD2AI_TEST_ENV_NEW").unwrap(), "from-file");
+    assert_eq!(env::var("CMD2AI_TEST_ENV_SET").unwrap(), "from-env");
+
+    assert!(load_env_file(&temp_dir.path().join("missing.env")).is_err());
+}
+
+#[test]
+fn test_expand_env_var_forms() {
+    env::set_var("CMD2AI_TEST_EXPAND_SET", "value");
+    env::set_var("CMD2AI_TEST_EXPAND_EMPTY", "");
+    env::remove_var("CMD2AI_TEST_EXPAND_UNSET");
+
+    // ${VAR}: unset variables stay literal
+    assert_eq!(
+        expand_env_var_in_string("a=${CMD2AI_TEST_EXPAND_SET}, b=${CMD2AI_TEST_EXPAND_UNSET}")
+            .unwrap(),
+        "a=value, b=${CMD2AI_TEST_EXPAND_UNSET}"
+    );
+
+    // ${VAR:-default}: default when unset or empty
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_SET:-x}").unwrap(),
+        "value"
+    );
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_UNSET:-/tmp/out}").unwrap(),
+        "/tmp/out"
+    );
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_EMPTY:-fallback}").unwrap(),
+        "fallback"
+    );
+    assert_eq!(
+        expand_env_var_in_string("[${CMD2AI_TEST_EXPAND_UNSET:-}]").unwrap(),
+        "[]"
+    );
+
+    // ${VAR:?message}: error when unset or empty
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_SET:?needed}").unwrap(),
+        "value"
+    );
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_UNSET:?set it in .env}").unwrap_err(),
+        "Environment variable 'CMD2AI_TEST_EXPAND_UNSET' is required: set it in .env"
+    );
+    assert_eq!(
+        expand_env_var_in_string("${CMD2AI_TEST_EXPAND_EMPTY:?}").unwrap_err(),
+        "Environment variable 'CMD2AI_TEST_EXPAND_EMPTY' is required but not set"
+    );
+
+    let env_map = HashMap::from([(
+        "TOKEN".to_string(),
+        "${CMD2AI_TEST_EXPAND_UNSET:?missing token}".to_string(),
+    )]);
+    assert!(expand_env_vars(&env_map)
+        .unwrap_err()
+        .contains("missing token"));
+}
+
+#[test]
+fn test_config_schema_accepts_the_example_config() {
+    let schema: serde_json::Value = serde_json::from_str(CONFIG_SCHEMA).unwrap();
+    let validator = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .unwrap();
+
+    let example: serde_json::Value =
+        serde_yaml::from_str(include_str!("../config.example.yaml")).unwrap();
+    if let Err(errors) = validator.validate(&example) {
+        let errors: Vec<String> = errors
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect();
+        panic!(
+            "config.example.yaml does not match the schema: {:?}",
+            errors
+        );
+    }
+
+    let typo = serde_json::json!({"api": {"endpont": "http://localhost"}});
+    assert!(!validator.is_valid(&typo));
+}
+
+/// Every key of `value` must be declared at the matching place in `schema`: object
+/// properties, map values (`additionalProperties`) and array items, following `$ref`s
+fn assert_keys_in_schema(
+    value: &serde_json::Value,
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+    path: &str,
+) {
+    let schema = match schema["$ref"].as_str() {
+        Some(reference) => root.pointer(reference.trim_start_matches('#')).unwrap(),
+        None => schema,
+    };
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields {
+                let field_path = format!("{}.{}", path, key);
+                if let Some(properties) = schema["properties"].as_object() {
+                    let property = properties.get(key).unwrap_or_else(|| {
+                        panic!("{} is missing from the config schema", field_path)
+                    });
+                    assert_keys_in_schema(field, property, root, &field_path);
+                } else if schema["additionalProperties"].is_object() {
+                    assert_keys_in_schema(
+                        field,
+                        &schema["additionalProperties"],
+                        root,
+                        &field_path,
+                    );
+                }
+            }
+        }
+        serde_json::Value::Array(items) if schema["items"].is_object() => {
+            for item in items {
+                assert_keys_in_schema(item, &schema["items"], root, &format!("{}[]", path));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_config_schema_covers_every_field() {
+    // Structs with fields left out when unset are written out in full, so a new field
+    // does not compile until it is given a value here and so checked against the schema
+    let validation = TemplateValidation {
+        kind: "path".to_string(),
+        allow_patterns: Some(vec!["^src/".to_string()]),
+        deny_patterns: Some(vec!["\\.env$".to_string()]),
+        allow_absolute: true,
+        expand_arrays: true,
+    };
+    let tool = LocalToolConfig {
+        name: "grep".to_string(),
+        enabled: true,
+        settings: serde_json::json!({}),
+        r#type: Some("command".to_string()),
+        description: Some("Search files".to_string()),
+        input_schema: Some(serde_json::json!({"type": "object"})),
+        examples: vec![serde_json::json!({"pattern": "todo"})],
+        interpreter: Some("bash".to_string()),
+        script: Some("echo".to_string()),
+        script_path: Some("grep.sh".to_string()),
+        command: Some("grep".to_string()),
+        args: vec!["-r".to_string()],
+        args_from: Some(vec!["pattern".to_string()]),
+        flag_args: HashMap::from([("ignore_case".to_string(), "-i".to_string())]),
+        timeout_secs: Some(5),
+        max_output_bytes: Some(1024),
+        working_dir: Some("src".to_string()),
+        env: HashMap::from([("LC_ALL".to_string(), "C".to_string())]),
+        stdin_json: false,
+        network: false,
+        max_concurrency: Some(2),
+        output_filter: Some(OutputFilter {
+            json_path: Some(".items".to_string()),
+            regex: Some("\\w+".to_string()),
+        }),
+        restrict_to_base_dir: false,
+        insert_double_dash: Some(true),
+        template_validations: Some(HashMap::from([("path".to_string(), validation)])),
+    };
+    let config = JsonConfig {
+        model: ModelConfig {
+            default_model: Some("openai/gpt-5".to_string()),
+            system_prompt: Some("Be brief".to_string()),
+            fallbacks: vec!["openai/gpt-4o".to_string()],
+            inject_date: Some(true),
+            date_format: Some("%Y-%m-%d".to_string()),
+            timezone: Some("utc".to_string()),
+            logit_bias: Some(HashMap::from([("50256".to_string(), -100.0)])),
+            frequency_penalty: Some(0.5),
+            presence_penalty: Some(0.5),
+            context_window: Some(128000),
+        },
+        session: SessionConfig {
+            verbose: Some(false),
+            store_plain: true,
+            max_files: Some(100),
+            summarize: true,
+            summary_model: Some("openai/gpt-4o-mini".to_string()),
+        },
+        tools: ToolsConfig {
+            enabled: true,
+            repair_arguments: true,
+            max_exposed: Some(8),
+            summarize_large_results: Some(50000),
+        },
+        local_tools: LocalToolsConfig {
+            enabled: true,
+            base_dir: Some("~".to_string()),
+            builtins_enabled: true,
+            max_file_size_mb: 10,
+            strict_schema: true,
+            temp_dir: Some("/tmp".to_string()),
+            strict_env: true,
+            default_timeout_secs: 30,
+            default_max_output_bytes: 1024,
+            tools: vec![tool],
+        },
+        ui: UiConfig {
+            ascii_boxes: Some(true),
+            piped_borders: true,
+            code_line_numbers: true,
+            hyperlinks: Some(false),
+            answer_header: Some("{{model}}".to_string()),
+            max_highlight_lines: Some(2000),
+            flush_interval_ms: Some(50),
+        },
+        routing: RoutingConfig {
+            rules: vec![RoutingRule {
+                keywords: vec!["sql".to_string()],
+                pattern: Some("^translate".to_string()),
+                model: "openai/gpt-4o".to_string(),
+            }],
+        },
+        prompts: HashMap::from([("review".to_string(), "Review: {{input}}".to_string())]),
+        ..JsonConfig::default()
+    };
+
+    let schema: serde_json::Value = serde_json::from_str(CONFIG_SCHEMA).unwrap();
+    let config = serde_json::to_value(config).unwrap();
+    assert_keys_in_schema(&config, &schema, &schema, "config");
+
+    let validator = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .unwrap();
+    if let Err(errors) = validator.validate(&config) {
+        let errors: Vec<String> = errors
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect();
+        panic!("a fully set config does not match the schema: {:?}", errors);
+    };
+}
+
+#[test]
+fn test_routing_rules_first_match_wins() {
+    let routing: RoutingConfig = serde_yaml::from_str(
+        r#"
+rules:
+  - keywords: [prove, debug]
+    model: reasoning-model
+  - pattern: "^(translate|define)\\b"
+    model: cheap-model
+  - keywords: [define]
+    model: unused-model
+"#,
+    )
+    .unwrap();
+    let routes = compile_routes(&routing).unwrap();
+
+    assert_eq!(
+        route_model(&routes, "please DEBUG this crash"),
+        Some((1, "reasoning-model"))
+    );
+    assert_eq!(
+        route_model(&routes, "define entropy"),
+        Some((2, "cheap-model"))
+    );
+    // Keywords match whole words only
+    assert_eq!(route_model(&routes, "the debugger is slow"), None);
+    assert_eq!(route_model(&routes, "what is 2 + 2"), None);
+}
+
+#[test]
+fn test_routing_rules_are_validated() {
+    let invalid = |yaml: &str| {
+        let routing: RoutingConfig = serde_yaml::from_str(yaml).unwrap();
+        compile_routes(&routing).unwrap_err()
+    };
+
+    assert_eq!(
+        invalid("rules: [{keywords: [a], model: x}, {pattern: \"(\", model: y}]")
+            .split(':')
+            .next(),
+        Some("routing rule 2")
+    );
+    assert_eq!(
+        invalid("rules: [{keywords: [a]}]"),
+        "routing rule 1 has no model"
+    );
+    assert_eq!(
+        invalid("rules: [{model: x}]"),
+        "routing rule 1 has no keywords or pattern"
+    );
+}
+
+#[test]
+fn test_contradictory_flags_are_rejected() {
+    for flags in [
+        ["--new", "--continue"],
+        ["--no-tools", "--use-tools"],
+        ["--no-tools", "--auto-tools"],
+        ["--no-system", "--system=be brief"],
+    ] {
+        let error = Args::try_parse_from(["ai", flags[0], flags[1], "hello"]).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            clap::error::ErrorKind::ArgumentConflict,
+            "{:?}",
+            flags
+        );
+    }
+    assert!(Args::try_parse_from(["ai", "--new", "--no-tools", "hello"]).is_ok());
+}
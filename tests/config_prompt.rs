@@ -0,0 +1,27 @@
+use cmd2ai::config::{expand_prompt_variables, prompt_variable};
+
+#[test]
+fn test_expand_known_and_unknown_variables() {
+    let lookup = |key: &str| match key {
+        "os" => Some("linux".to_string()),
+        "git_branch" => Some(String::new()),
+        _ => None,
+    };
+    let expanded = expand_prompt_variables("On {{os}} ({{ git_branch }}), keep {{other}}", lookup);
+    assert_eq!(expanded, "On linux (), keep {{other}}");
+}
+
+#[test]
+fn test_builtin_prompt_variables() {
+    assert_eq!(
+        prompt_variable("date", "Monday"),
+        Some("Monday".to_string())
+    );
+    assert_eq!(
+        prompt_variable("os", "Monday"),
+        Some(std::env::consts::OS.to_string())
+    );
+    assert!(!prompt_variable("cwd", "Monday").unwrap().is_empty());
+    assert!(prompt_variable("git_branch", "Monday").is_some());
+    assert_eq!(prompt_variable("unknown", "Monday"), None);
+}
@@ -0,0 +1,44 @@
+use chrono::FixedOffset;
+use cmd2ai::config::DateTimezone;
+
+#[test]
+fn test_parse_named_timezones() {
+    assert_eq!(DateTimezone::parse("local").unwrap(), DateTimezone::Local);
+    assert_eq!(DateTimezone::parse("UTC").unwrap(), DateTimezone::Utc);
+    assert_eq!(
+        DateTimezone::parse("Europe/Berlin").unwrap(),
+        DateTimezone::Named(chrono_tz::Europe::Berlin)
+    );
+}
+
+#[test]
+fn test_parse_fixed_offsets() {
+    let nine = DateTimezone::Fixed(FixedOffset::east_opt(9 * 3600).unwrap());
+    assert_eq!(DateTimezone::parse("+09:00").unwrap(), nine);
+    assert_eq!(DateTimezone::parse("UTC+9").unwrap(), nine);
+    assert_eq!(DateTimezone::parse("Utc+9").unwrap(), nine);
+
+    let minus = DateTimezone::Fixed(FixedOffset::west_opt(5 * 3600 + 1800).unwrap());
+    assert_eq!(DateTimezone::parse("-0530").unwrap(), minus);
+}
+
+#[test]
+fn test_parse_rejects_unknown_timezones() {
+    let err = DateTimezone::parse("Mars/Olympus_Mons").unwrap_err();
+    assert!(err.contains("Invalid model.timezone"));
+    assert!(DateTimezone::parse("+25:00").is_err());
+}
+
+#[test]
+fn test_format_now_uses_timezone() {
+    let utc = DateTimezone::Utc.format_now("%z");
+    assert_eq!(utc, "+0000");
+    let fixed = DateTimezone::parse("+09:00").unwrap().format_now("%z");
+    assert_eq!(fixed, "+0900");
+
+    // Named zones follow daylight saving: Berlin is +0100 or +0200
+    let berlin = DateTimezone::parse("Europe/Berlin")
+        .unwrap()
+        .format_now("%z");
+    assert!(berlin == "+0100" || berlin == "+0200", "{}", berlin);
+}
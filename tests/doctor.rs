@@ -0,0 +1,42 @@
+use cmd2ai::config::LocalToolsConfig;
+use cmd2ai::doctor::check_tools;
+
+#[test]
+fn test_check_tools_finds_programs_on_path() {
+    let config: LocalToolsConfig = serde_yaml::from_str(
+        r#"
+tools:
+  - name: read_file
+    settings: {}
+  - name: shell_script
+    type: script
+    interpreter: sh
+    script: "echo hi"
+    description: Runs a script
+  - name: missing_command
+    type: command
+    command: cmd2ai-no-such-program
+    description: Not installed
+  - name: disabled
+    enabled: false
+    type: command
+    command: cmd2ai-no-such-program
+    description: Skipped
+"#,
+    )
+    .unwrap();
+
+    let checks = check_tools(&config);
+
+    // Built-in settings and disabled tools are not checked
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0].name, "Tool 'shell_script'");
+    assert!(checks[0].passed, "{}", checks[0].detail);
+    assert_eq!(checks[1].name, "Tool 'missing_command'");
+    assert!(!checks[1].passed);
+    assert_eq!(
+        checks[1].detail,
+        "command 'cmd2ai-no-such-program' not found on PATH"
+    );
+    assert!(checks[1].hint.is_some());
+}
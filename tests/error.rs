@@ -0,0 +1,26 @@
+use cmd2ai::error::Cmd2AiError;
+
+fn api_error(status: u16, message: &str) -> Cmd2AiError {
+    Cmd2AiError::ApiError {
+        status,
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn test_model_unavailable_errors() {
+    assert!(api_error(404, "Not Found").is_model_unavailable());
+    assert!(api_error(
+        400,
+        r#"{"error":{"message":"openai/gpt-9 is not a valid model ID"}}"#
+    )
+    .is_model_unavailable());
+    assert!(api_error(503, "No endpoints found for model x").is_model_unavailable());
+}
+
+#[test]
+fn test_other_errors_are_not_model_unavailable() {
+    assert!(!api_error(401, "No auth credentials found").is_model_unavailable());
+    assert!(!api_error(400, "messages: field required").is_model_unavailable());
+    assert!(!Cmd2AiError::Timeout.is_model_unavailable());
+}
@@ -0,0 +1,318 @@
+use cmd2ai::ui::highlight::{box_width, CodeBuffer};
+use cmd2ai::ui::options::BorderStyle;
+use cmd2ai::ui::output::{hyperlink, json_response_block, may_be_json_document, JsonPrefixScanner};
+use cmd2ai::ui::RenderOptions;
+
+fn render(options: RenderOptions, input: &str) -> String {
+    let mut buffer = CodeBuffer::with_options(options);
+    let mut output = buffer.append(input);
+    output.push_str(&buffer.flush());
+    output
+}
+
+#[test]
+fn test_ascii_boxes_avoid_box_drawing_characters() {
+    let options = RenderOptions {
+        ascii_boxes: true,
+        ..Default::default()
+    };
+    let output = render(options, "```rust\nfn main() {}\n```\n");
+
+    assert!(output.contains("+-"));
+    assert!(!output.contains('┌'));
+    assert!(!output.contains('└'));
+    assert!(!output.contains('─'));
+}
+
+#[test]
+fn test_unicode_boxes_by_default() {
+    let output = render(RenderOptions::default(), "```rust\nfn main() {}\n```\n");
+
+    assert!(output.contains('┌'));
+    assert!(output.contains('└'));
+}
+
+#[test]
+fn test_hidden_borders_emit_only_code() {
+    let options = RenderOptions {
+        border_style: BorderStyle::Hidden,
+        ..Default::default()
+    };
+    let output = render(options, "```\nplain\n```\n");
+
+    assert!(!output.contains('┌'));
+    assert!(!output.contains('└'));
+    assert!(output.contains("plain"));
+}
+
+#[test]
+fn test_compact_borders_have_no_trailing_dashes() {
+    let options = RenderOptions {
+        border_style: BorderStyle::Compact,
+        ..Default::default()
+    };
+    let output = render(options, "```\nplain\n```\n");
+
+    assert!(output.contains("[code]\n"));
+    assert!(!output.contains("──"));
+}
+
+#[test]
+fn test_language_aliases_resolve() {
+    let buffer = CodeBuffer::new();
+
+    assert_eq!(buffer.syntax_name(Some("ts")), "JavaScript");
+    assert_eq!(buffer.syntax_name(Some("sh")), "Bourne Again Shell (bash)");
+    assert_eq!(buffer.syntax_name(Some("yml")), "YAML");
+    assert_eq!(buffer.syntax_name(Some("jsonc")), "JSON");
+    assert_eq!(buffer.syntax_name(Some("rs")), "Rust");
+    assert_eq!(buffer.syntax_name(Some("Python")), "Python");
+}
+
+#[test]
+fn test_language_line_uses_first_word() {
+    let buffer = CodeBuffer::new();
+
+    assert_eq!(buffer.syntax_name(Some("python {.numberLines}")), "Python");
+    assert_eq!(buffer.syntax_name(Some("rust,ignore")), "Rust");
+    assert_eq!(buffer.syntax_name(Some("unknown-lang")), "Plain Text");
+    assert_eq!(buffer.syntax_name(None), "Plain Text");
+}
+
+fn strip_ansi(text: &str) -> String {
+    regex::Regex::new(r"\x1b\[[0-9;]*m")
+        .unwrap()
+        .replace_all(text, "")
+        .to_string()
+}
+
+#[test]
+fn test_line_numbers_stay_consistent_across_chunks() {
+    let options = RenderOptions {
+        code_line_numbers: true,
+        ..Default::default()
+    };
+    let mut buffer = CodeBuffer::with_options(options);
+    let mut output = String::new();
+    for chunk in ["```py\nfirst = 1\nsec", "ond = 2\n", "third = 3\n```\n"] {
+        output.push_str(&buffer.append(chunk));
+    }
+    output.push_str(&buffer.flush());
+
+    let plain = strip_ansi(&output);
+    assert!(plain.contains("   1 first = 1"));
+    assert!(plain.contains("   2 second = 2"));
+    assert!(plain.contains("   3 third = 3"));
+}
+
+#[test]
+fn test_json_only_responses_become_json_blocks() {
+    let block = json_response_block(" {\"a\": [1, 2]} \n").unwrap();
+    assert_eq!(block, "```json\n{\n  \"a\": [\n    1,\n    2\n  ]\n}\n```\n");
+
+    assert_eq!(json_response_block("Here you go: {\"a\": 1}"), None);
+    assert_eq!(json_response_block("[1] is a footnote, not JSON"), None);
+}
+
+#[test]
+fn test_json_prefixes_are_told_apart_from_bracketed_prose() {
+    for prefix in [
+        "{",
+        "{\"name\": \"Ada [1]\", \"tags\": [",
+        "[1, 2.5e3, true, null",
+        "{\"a\": \"quote \\\" inside\"}\n",
+    ] {
+        assert!(may_be_json_document(prefix), "{:?}", prefix);
+    }
+
+    for prefix in [
+        "[Note] This is prose",
+        "[the docs](https://example.com)",
+        "[1] Smith, 2020",
+        "{\"a\": 1} and more",
+        "[1, 2}",
+    ] {
+        assert!(!may_be_json_document(prefix), "{:?}", prefix);
+    }
+}
+
+#[test]
+fn test_json_scanner_keeps_its_place_across_chunks() {
+    // A string and an escape split over chunks
+    let mut scanner = JsonPrefixScanner::default();
+    for chunk in ["{\"na", "me\": \"a \\", "\" [b]\"", "}\n"] {
+        assert!(scanner.feed(chunk), "{:?}", chunk);
+    }
+    assert!(!scanner.feed(" trailing prose"));
+    // Once ruled out, it stays ruled out
+    assert!(!scanner.feed("}"));
+
+    let mut scanner = JsonPrefixScanner::default();
+    assert!(scanner.feed("["));
+    assert!(!scanner.feed("Note] prose"));
+}
+
+#[test]
+fn test_hyperlink_wraps_text_in_osc8() {
+    assert_eq!(
+        hyperlink("Docs", "https://example.com"),
+        "\x1b]8;;https://example.com\x1b\\Docs\x1b]8;;\x1b\\"
+    );
+}
+
+#[test]
+fn test_answer_header_fills_in_model_and_time() {
+    let options = RenderOptions {
+        answer_header: Some("── {{model}} at {{time}} {{other}} ──".to_string()),
+        ..RenderOptions::default()
+    };
+    let header = options.answer_header("openai/gpt-4o").unwrap();
+
+    assert!(header.starts_with("── openai/gpt-4o at "), "{}", header);
+    assert!(header.ends_with(" {{other}} ──"), "{}", header);
+    assert_eq!(RenderOptions::default().answer_header("m"), None);
+}
+
+#[test]
+fn test_box_width_follows_narrow_terminals() {
+    assert_eq!(box_width(Some(40)), 40);
+    assert_eq!(box_width(Some(20)), 20);
+    assert_eq!(box_width(Some(100)), 100);
+    assert_eq!(box_width(Some(300)), 120);
+    assert_eq!(box_width(Some(0)), 80);
+    assert_eq!(box_width(None), 80);
+}
+
+#[test]
+fn test_fixed_width_sets_border_length() {
+    let options = RenderOptions {
+        ascii_boxes: true,
+        width: Some(30),
+        ..RenderOptions::default()
+    };
+    let output = render(options, "```rust\nfn main() {}\n```");
+    let plain = strip_ansi(&output);
+    let lines: Vec<&str> = plain.lines().collect();
+
+    assert_eq!(lines.first().unwrap().chars().count(), 30, "{:?}", plain);
+    assert_eq!(lines.last().unwrap().chars().count(), 30, "{:?}", plain);
+}
+
+/// Stream `chunks` through a buffer with compact ASCII borders, without colour codes
+fn render_chunks(chunks: &[&str]) -> String {
+    let mut buffer = CodeBuffer::with_options(RenderOptions {
+        ascii_boxes: true,
+        border_style: BorderStyle::Compact,
+        ..RenderOptions::default()
+    });
+    let mut output = String::new();
+    for chunk in chunks {
+        output.push_str(&buffer.append(chunk));
+    }
+    output.push_str(&buffer.flush());
+    strip_ansi(&output)
+}
+
+#[test]
+fn test_closing_fence_variants_end_the_block() {
+    let variants: &[&[&str]] = &[
+        // Language repeated on the closer
+        &["```rust\nlet x = 1;\n```rust\nDone.\n"],
+        // Trailing whitespace
+        &["```rust\nlet x = 1;\n```   \nDone.\n"],
+        // Indented closer, as inside a list item
+        &["```rust\nlet x = 1;\n   ```\nDone.\n"],
+        // Closer split across stream chunks
+        &["```rust\nlet x = 1;\n``", "`\nDone.\n"],
+        &["```rust\nlet x = 1;\n", "```", "\nDone.\n"],
+    ];
+
+    for chunks in variants {
+        let plain = render_chunks(chunks);
+        assert!(plain.contains("+-[rust]\nlet x = 1;\n"), "{:?}", plain);
+        assert!(plain.trim_end().ends_with("+-\n\nDone."), "{:?}", plain);
+        assert!(!plain.contains("```") && !plain.contains("\nrust"), "{:?}", plain);
+    }
+}
+
+#[test]
+fn test_text_after_a_closing_fence_is_kept() {
+    let plain = render_chunks(&["```sh\nls\n``` and that lists the files\n"]);
+    assert!(
+        plain.ends_with("+-\nand that lists the files\n"),
+        "{:?}",
+        plain
+    );
+
+    // Backticks later in a line don't close the block
+    let plain = render_chunks(&["```md\nuse ``` to fence code\n```\n"]);
+    assert!(plain.contains("use ``` to fence code\n+-"), "{:?}", plain);
+}
+
+#[test]
+fn test_tilde_and_longer_fences_close_only_on_their_own_delimiter() {
+    let plain = render_chunks(&["~~~python\nx = 1\n```\ny = 2\n~~~\nDone.\n"]);
+    assert!(plain.starts_with("+-[python]\nx = 1\n```\ny = 2\n+-"), "{:?}", plain);
+    assert!(plain.trim_end().ends_with("+-\n\nDone."), "{:?}", plain);
+
+    // A markdown example containing a fence, inside a four-backtick fence
+    let plain = render_chunks(&["````md\n```sh\nls\n```\n````\nDone.\n"]);
+    assert!(
+        plain.starts_with("+-[md]\n```sh\nls\n```\n+-"),
+        "{:?}",
+        plain
+    );
+    assert!(plain.trim_end().ends_with("Done."), "{:?}", plain);
+}
+
+#[test]
+fn test_indented_fences_in_list_items() {
+    let plain = render_chunks(&["1. List files:\n   ```sh\n   ls -la\n   ```\n2. Done\n"]);
+    assert!(
+        plain.starts_with("1. List files:\n+-[sh]\nls -la\n+-"),
+        "{:?}",
+        plain
+    );
+    assert!(plain.trim_end().ends_with("2. Done"), "{:?}", plain);
+
+    // An opening fence split across chunks
+    let plain = render_chunks(&["Run:\n`", "``sh\nls\n```\n"]);
+    assert!(plain.starts_with("Run:\n+-[sh]\nls\n+-"), "{:?}", plain);
+    let plain = render_chunks(&["Run:\n~~", "~\nls\n~~~\n"]);
+    assert!(plain.starts_with("Run:\n+-[code]\nls\n+-"), "{:?}", plain);
+}
+
+#[test]
+fn test_long_code_blocks_stop_highlighting() {
+    let options = RenderOptions {
+        max_highlight_lines: Some(2),
+        ..Default::default()
+    };
+    let mut buffer = CodeBuffer::with_options(options);
+    let mut output = buffer.append("```rust\nlet a = 1;\nlet b = 2;\n");
+    output.push_str(&buffer.append("let c = 3;\nlet d = 4;\n```\n"));
+    output.push_str(&buffer.flush());
+
+    let (highlighted, plain) = output.split_once("[highlighting off after 2 lines]").unwrap();
+    assert!(highlighted.contains("\x1b[38;2;"), "{:?}", highlighted);
+    assert!(!plain.contains("\x1b[38;2;"), "{:?}", plain);
+    assert!(plain.contains("let c = 3;\nlet d = 4;"), "{:?}", plain);
+}
+
+#[test]
+fn test_reasoning_goes_to_the_writer_it_is_routed_to() {
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    RenderOptions::default()
+        .write_reasoning(&mut out, &mut err, "to stdout")
+        .unwrap();
+    let to_stderr = RenderOptions {
+        reasoning_to_stderr: true,
+        ..Default::default()
+    };
+    to_stderr
+        .write_reasoning(&mut out, &mut err, "to stderr")
+        .unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), "to stdout");
+    assert_eq!(String::from_utf8(err).unwrap(), "to stderr");
+}
@@ -96,7 +96,7 @@ deny_patterns:
 "#;
         let validation: TemplateValidation = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(validation.kind, "path");
-        assert_eq!(validation.allow_absolute, false);
+        assert!(!validation.allow_absolute);
         assert_eq!(validation.deny_patterns.unwrap().len(), 1);
     }
 
@@ -118,7 +118,7 @@ template_validations:
 "#;
         let config: LocalToolConfig = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(config.name, "test_tool");
-        assert_eq!(config.restrict_to_base_dir, true);
+        assert!(config.restrict_to_base_dir);
         assert_eq!(config.insert_double_dash, Some(true));
         assert!(config.template_validations.is_some());
     }
@@ -135,7 +135,7 @@ args: []
 "#;
         let config: LocalToolConfig = serde_yaml::from_str(yaml).unwrap();
         // restrict_to_base_dir should default to true
-        assert_eq!(config.restrict_to_base_dir, true);
+        assert!(config.restrict_to_base_dir);
         // insert_double_dash should default to None (auto-detect)
         assert_eq!(config.insert_double_dash, None);
     }
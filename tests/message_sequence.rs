@@ -0,0 +1,108 @@
+use cmd2ai::error::Cmd2AiError;
+use cmd2ai::models::{validate_message_sequence, FunctionCall, Message, ToolCall};
+
+fn message(role: &str, content: &str) -> Message {
+    Message {
+        role: role.to_string(),
+        content: Some(content.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+fn tool_turn(ids: &[&str]) -> Message {
+    let mut turn = message("assistant", "");
+    turn.tool_calls = Some(
+        ids.iter()
+            .map(|id| ToolCall {
+                id: id.to_string(),
+                tool_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "read_file".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            })
+            .collect(),
+    );
+    turn
+}
+
+fn tool_result(id: &str) -> Message {
+    let mut result = message("tool", "ok");
+    result.tool_call_id = Some(id.to_string());
+    result
+}
+
+fn error_text(messages: &[Message]) -> String {
+    match validate_message_sequence(messages) {
+        Err(Cmd2AiError::SessionError(text)) => text,
+        other => panic!("expected a session error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_valid_histories_pass() {
+    let messages = vec![
+        message("system", "Be brief"),
+        message("user", "read a and b"),
+        tool_turn(&["call_a", "call_b"]),
+        tool_result("call_b"),
+        tool_result("call_a"),
+        message("assistant", "done"),
+        message("user", "thanks"),
+    ];
+    assert!(validate_message_sequence(&messages).is_ok());
+    assert!(validate_message_sequence(&[]).is_ok());
+}
+
+#[test]
+fn test_system_messages_must_be_single_and_first() {
+    let text = error_text(&[
+        message("system", "date"),
+        message("system", "prompt"),
+        message("user", "hi"),
+    ]);
+    assert!(
+        text.contains("message 2 (system) is a second system message"),
+        "{}",
+        text
+    );
+
+    let text = error_text(&[message("user", "hi"), message("system", "late")]);
+    assert!(
+        text.contains("message 2 (system) comes after the conversation started"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_tool_results_must_answer_the_preceding_calls() {
+    let text = error_text(&[message("user", "hi"), tool_result("call_x")]);
+    assert!(
+        text.contains("message 2 (tool) answers tool call 'call_x'"),
+        "{}",
+        text
+    );
+
+    let text = error_text(&[
+        message("user", "hi"),
+        tool_turn(&["call_a", "call_b"]),
+        tool_result("call_a"),
+        message("user", "next"),
+    ]);
+    assert!(
+        text.contains("message 2 (assistant) has no tool result for call 'call_b'"),
+        "{}",
+        text
+    );
+
+    let mut missing_id = message("tool", "ok");
+    missing_id.tool_call_id = None;
+    let text = error_text(&[message("user", "hi"), tool_turn(&["call_a"]), missing_id]);
+    assert!(
+        text.contains("message 3 (tool) has no tool_call_id"),
+        "{}",
+        text
+    );
+}
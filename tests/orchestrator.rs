@@ -0,0 +1,1375 @@
+use clap::Parser;
+use cmd2ai::api::{
+    build_client, check_endpoint, fetch_models, process_streaming_response, StreamSettings,
+};
+use cmd2ai::batch::{batch_messages, parse_batch, run_prompts, BatchPrompt};
+use cmd2ai::cli::Args;
+use cmd2ai::config::{Config, LocalToolsConfig};
+use cmd2ai::error::Cmd2AiError;
+use cmd2ai::local_tools::{LocalSettings, LocalToolRegistry};
+use cmd2ai::models::Message;
+use cmd2ai::orchestrator::{run, trim_history, OrchestratorContext};
+use cmd2ai::query;
+use cmd2ai::ui::options::BorderStyle;
+use cmd2ai::ui::{RenderOptions, Spinner};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A canned HTTP response served by `MockServer`
+struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl MockResponse {
+    fn json(body: Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    /// Server-sent events with one `data:` line per chunk, terminated by `[DONE]`
+    fn sse(chunks: &[Value]) -> Self {
+        let mut body: String = chunks
+            .iter()
+            .map(|chunk| format!("data: {}\n\n", chunk))
+            .collect();
+        body.push_str("data: [DONE]\n\n");
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body,
+        }
+    }
+}
+
+/// Minimal chat-completions endpoint: answers each request with the next canned response
+/// (one connection per request) and records the JSON request bodies
+struct MockServer {
+    endpoint: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockServer {
+    async fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!(
+            "http://{}/v1/chat/completions",
+            listener.local_addr().unwrap()
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let body = read_request_body(&mut stream).await;
+                // Body-less requests (e.g. the endpoint check's GET) are recorded as null
+                let request = if body.is_empty() {
+                    Value::Null
+                } else {
+                    serde_json::from_slice(&body).unwrap()
+                };
+                recorded.lock().unwrap().push(request);
+
+                let head = format!(
+                    "HTTP/1.1 {} OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    response.status,
+                    response.content_type,
+                    response.body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(response.body.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        Self { endpoint, requests }
+    }
+
+    fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request_body(stream: &mut tokio::net::TcpStream) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the request was complete");
+        data.extend_from_slice(&buf[..n]);
+
+        let Some(header_end) = data.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&data[..header_end]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|v| v.trim().parse().unwrap())
+            .unwrap_or(0);
+        let body_start = header_end + 4;
+        if data.len() >= body_start + content_length {
+            return data[body_start..body_start + content_length].to_vec();
+        }
+    }
+}
+
+/// Config pointed at the mock server, isolated from the user's config files.
+/// Env vars are process-wide, so every test sets the same values exactly once.
+fn test_config(endpoint: &str) -> (Config, Args) {
+    static ENV: Once = Once::new();
+    ENV.call_once(|| {
+        let home = TempDir::new().unwrap().keep();
+        env::set_var("HOME", home);
+        env::set_var("OPENROUTER_API_KEY", "test-key");
+        for var in [
+            "AI_MODEL",
+            "AI_API_ENDPOINT",
+            "AI_SYSTEM_PROMPT",
+            "AI_VERBOSE",
+        ] {
+            env::remove_var(var);
+        }
+    });
+
+    let args = Args::parse_from(["ai", "--api-endpoint", endpoint, "hello"]);
+    let config = Config::from_env_and_args(&args).unwrap();
+    (config, args)
+}
+
+fn user_message(text: &str) -> Message {
+    Message {
+        role: "user".to_string(),
+        content: Some(text.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_response_end_to_end() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"id": "gen-1", "choices": [{"delta": {"content": "Hello"}}]}),
+        json!({"id": "gen-1", "choices": [{"delta": {"content": ", world"}, "finish_reason": "stop"}]}),
+        json!({"id": "gen-1", "choices": [], "usage": {"prompt_tokens": 5, "completion_tokens": 3, "total_tokens": 8}}),
+    ])])
+    .await;
+    let (config, args) = test_config(&server.endpoint);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: false,
+    };
+
+    let mut messages = vec![user_message("hello")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    assert_eq!(outcome.content, "Hello, world");
+    assert_eq!(outcome.request_id.as_deref(), Some("gen-1"));
+    assert_eq!(outcome.finish_reason.as_deref(), Some("stop"));
+    assert_eq!(outcome.usage.map(|u| u.total_tokens), Some(8));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["stream"], true);
+    assert_eq!(requests[0]["messages"][0]["content"], "hello");
+    assert!(requests[0].get("tools").is_none());
+    assert!(requests[0].get("logit_bias").is_none());
+}
+
+#[tokio::test]
+async fn test_logit_bias_is_passed_through() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"content": "ok"}}]}),
+    ])])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.logit_bias = Some(HashMap::from([("50256".to_string(), -100.0)]));
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: false,
+    };
+
+    let mut messages = vec![user_message("hello")];
+    run(&context, &mut messages).await.unwrap();
+
+    assert_eq!(server.requests()[0]["logit_bias"], json!({"50256": -100.0}));
+}
+
+#[tokio::test]
+async fn test_tool_call_round_trip() {
+    let base_dir = TempDir::new().unwrap();
+    fs::write(base_dir.path().join("notes.txt"), "buy milk").unwrap();
+
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "id": "gen-tool",
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {
+                            "name": "read_file",
+                            "arguments": "{\"path\": \"notes.txt\"}"
+                        }
+                    }]
+                }
+            }]
+        })),
+        MockResponse::sse(&[json!({
+            "id": "gen-answer",
+            "choices": [{"delta": {"content": "Your note says: buy milk"}}]
+        })]),
+    ])
+    .await;
+    let (config, args) = test_config(&server.endpoint);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        max_file_size_bytes: 1024,
+        script_dir: base_dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: false,
+    };
+
+    let mut messages = vec![user_message("what's in notes.txt?")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    assert_eq!(outcome.content, "Your note says: buy milk");
+    assert_eq!(outcome.tool_calls.len(), 1);
+    assert_eq!(outcome.tool_calls[0].name, "read_file");
+    assert_eq!(
+        outcome.tool_calls[0].arguments,
+        json!({"path": "notes.txt"})
+    );
+    assert_eq!(outcome.tool_calls[0].result, "buy milk");
+    assert!(!outcome.tool_calls[0].is_error);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    // Tools are offered without streaming, the follow-up streams without tools
+    assert_eq!(requests[0]["stream"], false);
+    assert_eq!(requests[0]["tools"][0]["function"]["name"], "read_file");
+    assert_eq!(requests[1]["stream"], true);
+    assert!(requests[1].get("tools").is_none());
+
+    let followup = requests[1]["messages"].as_array().unwrap();
+    assert_eq!(followup[1]["role"], "assistant");
+    assert_eq!(followup[1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(followup[2]["role"], "tool");
+    assert_eq!(followup[2]["tool_call_id"], "call_1");
+    assert_eq!(followup[2]["content"], "buy milk");
+}
+
+#[tokio::test]
+async fn test_large_tool_result_drops_old_history_from_the_follow_up() {
+    let base_dir = TempDir::new().unwrap();
+    fs::write(base_dir.path().join("notes.txt"), "x".repeat(300)).unwrap();
+
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {
+                            "name": "read_file",
+                            "arguments": "{\"path\": \"notes.txt\"}"
+                        }
+                    }]
+                }
+            }]
+        })),
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "Lots of x"}}]})]),
+    ])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    // The first request (~108 tokens) fits; with the tool result it no longer does
+    config.context_window = Some(150);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        max_file_size_bytes: 1024,
+        script_dir: base_dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![
+        user_message("old question"),
+        Message {
+            role: "assistant".to_string(),
+            content: Some("y".repeat(400)),
+            tool_calls: None,
+            tool_call_id: None,
+        },
+        user_message("what's in notes.txt?"),
+    ];
+    let outcome = run(&context, &mut messages).await.unwrap();
+    assert_eq!(outcome.content, "Lots of x");
+
+    let requests = server.requests();
+    assert_eq!(requests[0]["messages"].as_array().unwrap().len(), 3);
+    let followup = requests[1]["messages"].as_array().unwrap();
+    let roles: Vec<&str> = followup
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, vec!["user", "assistant", "tool"]);
+    assert_eq!(followup[0]["content"], "what's in notes.txt?");
+}
+
+#[tokio::test]
+async fn test_tool_call_without_id_is_left_out_of_the_follow_up() {
+    let base_dir = TempDir::new().unwrap();
+    fs::write(base_dir.path().join("notes.txt"), "buy milk").unwrap();
+
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "id": "gen-tool",
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [
+                        {
+                            "type": "function",
+                            "function": {"name": "read_file", "arguments": "{}"}
+                        },
+                        {
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {
+                                "name": "read_file",
+                                "arguments": "{\"path\": \"notes.txt\"}"
+                            }
+                        }
+                    ]
+                }
+            }]
+        })),
+        MockResponse::sse(&[json!({
+            "id": "gen-answer",
+            "choices": [{"delta": {"content": "Your note says: buy milk"}}]
+        })]),
+    ])
+    .await;
+    let (config, args) = test_config(&server.endpoint);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        max_file_size_bytes: 1024,
+        script_dir: base_dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: false,
+    };
+
+    let mut messages = vec![user_message("what's in notes.txt?")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    assert_eq!(outcome.content, "Your note says: buy milk");
+    assert_eq!(outcome.tool_calls.len(), 1);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    let followup = requests[1]["messages"].as_array().unwrap();
+    assert_eq!(followup.len(), 3);
+    assert_eq!(followup[1]["tool_calls"].as_array().unwrap().len(), 1);
+    assert_eq!(followup[1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(followup[2]["tool_call_id"], "call_1");
+}
+
+#[tokio::test]
+async fn test_check_endpoint_reports_unreachable_servers() {
+    let server = MockServer::start(vec![MockResponse {
+        status: 404,
+        content_type: "text/plain",
+        body: "not found".to_string(),
+    }])
+    .await;
+    let (config, _) = test_config(&server.endpoint);
+    let client = build_client(&config).unwrap();
+    let timeout = Duration::from_secs(5);
+
+    // Any HTTP answer means the server is there, even without a /models route
+    check_endpoint(&client, &server.endpoint, timeout, false)
+        .await
+        .unwrap();
+
+    // Nothing listens on a port we just released
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!(
+        "http://{}/v1/chat/completions",
+        listener.local_addr().unwrap()
+    );
+    drop(listener);
+    let err = check_endpoint(&client, &closed, timeout, false)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(&format!("Cannot reach endpoint {}", closed)));
+}
+
+#[tokio::test]
+async fn test_streaming_output_goes_to_the_given_writer() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"content": "Captured "}}]}),
+        json!({"choices": [{"delta": {"content": "output"}}]}),
+    ])])
+    .await;
+    let response = reqwest::get(&server.endpoint).await.unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let result = process_streaming_response(
+        response,
+        &mut out,
+        &StreamSettings {
+            timeout_secs: 30,
+            reasoning_exclude: false,
+            verbose: false,
+            show_stats: false,
+            request_started: std::time::Instant::now(),
+            answer_header: Some("-- test-model --".to_string()),
+            flush_interval: Duration::from_millis(50),
+            incremental: true,
+            quiet: false,
+        },
+        &RenderOptions::default(),
+        Spinner::hidden(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.content, "Captured output");
+    let rendered = String::from_utf8(out).unwrap();
+    assert!(rendered.contains("Captured output"), "got {:?}", rendered);
+    assert!(
+        rendered.starts_with("-- test-model --"),
+        "got {:?}",
+        rendered
+    );
+    assert!(rendered.ends_with('\n'));
+}
+
+#[tokio::test]
+async fn test_non_incremental_streaming_renders_the_whole_answer() {
+    let chunks = ["Run:\n``", "`\nls -la\n", "```\nDone."];
+    let events: Vec<_> = chunks
+        .iter()
+        .map(|chunk| json!({"choices": [{"delta": {"content": chunk}}]}))
+        .collect();
+    let server = MockServer::start(vec![MockResponse::sse(&events)]).await;
+    let response = reqwest::get(&server.endpoint).await.unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let result = process_streaming_response(
+        response,
+        &mut out,
+        &StreamSettings {
+            timeout_secs: 30,
+            reasoning_exclude: false,
+            verbose: false,
+            show_stats: false,
+            request_started: std::time::Instant::now(),
+            answer_header: None,
+            flush_interval: Duration::from_millis(50),
+            incremental: false,
+            quiet: false,
+        },
+        &RenderOptions {
+            border_style: BorderStyle::Hidden,
+            ..Default::default()
+        },
+        Spinner::hidden(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.content, chunks.concat());
+    let rendered = String::from_utf8(out).unwrap();
+    assert!(rendered.starts_with("Run:\n"), "got {:?}", rendered);
+    assert!(rendered.contains("ls -la"), "got {:?}", rendered);
+    assert!(!rendered.contains("```"), "got {:?}", rendered);
+    assert!(rendered.trim_end().ends_with("Done."), "got {:?}", rendered);
+}
+
+#[tokio::test]
+async fn test_streamed_content_is_kept_as_sent_when_display_reformats_it() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"reasoning": "**Plan:** answer in JSON"}}]}),
+        json!({"choices": [{"delta": {"content": "{\"a\":"}}]}),
+        json!({"choices": [{"delta": {"content": "[1,2]}"}}]}),
+    ])])
+    .await;
+    let response = reqwest::get(&server.endpoint).await.unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let result = process_streaming_response(
+        response,
+        &mut out,
+        &StreamSettings {
+            timeout_secs: 30,
+            reasoning_exclude: false,
+            verbose: false,
+            show_stats: false,
+            request_started: std::time::Instant::now(),
+            answer_header: None,
+            flush_interval: Duration::from_millis(50),
+            incremental: true,
+            quiet: false,
+        },
+        &RenderOptions::default(),
+        Spinner::hidden(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.content, "{\"a\":[1,2]}");
+    assert_eq!(
+        result.reasoning.as_deref(),
+        Some("**Plan:** answer in JSON")
+    );
+    let rendered = String::from_utf8(out).unwrap();
+    assert!(!rendered.contains("{\"a\":[1,2]}"), "got {:?}", rendered);
+    assert!(!rendered.contains("**Plan:**"), "got {:?}", rendered);
+}
+
+#[tokio::test]
+async fn test_query_returns_content_and_reasoning() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"reasoning": "Thinking it over"}}]}),
+        json!({"choices": [{"delta": {"content": "Forty-two"}}]}),
+    ])])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+
+    let outcome = query(config, vec![user_message("meaning of life?")])
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.content, "Forty-two");
+    assert_eq!(outcome.reasoning.as_deref(), Some("Thinking it over"));
+    assert!(outcome.tool_calls.is_empty());
+    assert_eq!(
+        server.requests()[0]["messages"][0]["content"],
+        "meaning of life?"
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_models_from_the_models_route() {
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "data": [{"id": "local/model-b"}, {"id": "local/model-a"}]
+    }))])
+    .await;
+    let (config, _) = test_config(&server.endpoint);
+    let client = build_client(&config).unwrap();
+
+    let models = fetch_models(&client, &server.endpoint, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["local/model-a", "local/model-b"]);
+}
+
+#[tokio::test]
+async fn test_retry_on_empty_asks_again_after_a_blank_answer() {
+    let server = MockServer::start(vec![
+        MockResponse::sse(&[json!({"choices": [{"delta": {}, "finish_reason": "stop"}]})]),
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "Second time lucky"}}]})]),
+    ])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    config.retry_on_empty = true;
+
+    let outcome = query(config, vec![user_message("hi")]).await.unwrap();
+
+    assert_eq!(outcome.content, "Second time lucky");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_fallback_model_is_tried_before_suggesting_models() {
+    let server = MockServer::start(vec![
+        MockResponse {
+            status: 404,
+            content_type: "application/json",
+            body: json!({"error": {"message": "No endpoints found for model"}}).to_string(),
+        },
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "From the fallback"}}]})]),
+    ])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    config.model_fallbacks = vec!["backup/model".to_string()];
+
+    let outcome = query(config, vec![user_message("hi")]).await.unwrap();
+
+    assert_eq!(outcome.content, "From the fallback");
+    // No /models lookup in between: both requests are chat completions
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["model"], "backup/model");
+}
+
+#[tokio::test]
+async fn test_retry_on_empty_skips_truncated_answers() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"reasoning": "Long thought"}, "finish_reason": "length"}]}),
+    ])])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    config.retry_on_empty = true;
+
+    let outcome = query(config, vec![user_message("hi")]).await.unwrap();
+
+    assert_eq!(outcome.content, "");
+    assert_eq!(outcome.finish_reason.as_deref(), Some("length"));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_mid_stream_error_keeps_held_back_output_on_screen() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"reasoning": "Planning the object"}}]}),
+        json!({"choices": [{"delta": {"content": "{\"partial\": "}}]}),
+        json!({"error": {"code": "server_error", "message": "Provider disconnected"}}),
+    ])])
+    .await;
+    let response = reqwest::get(&server.endpoint).await.unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let error = process_streaming_response(
+        response,
+        &mut out,
+        &StreamSettings {
+            timeout_secs: 30,
+            reasoning_exclude: false,
+            verbose: false,
+            show_stats: false,
+            request_started: std::time::Instant::now(),
+            answer_header: None,
+            flush_interval: Duration::from_millis(50),
+            incremental: true,
+            quiet: false,
+        },
+        &RenderOptions {
+            border_style: BorderStyle::Compact,
+            ascii_boxes: true,
+            ..Default::default()
+        },
+        Spinner::hidden(),
+    )
+    .await
+    .err()
+    .unwrap();
+
+    assert!(matches!(error, Cmd2AiError::ApiError { .. }));
+    let rendered = String::from_utf8(out).unwrap();
+    assert!(
+        rendered.contains("Planning the object"),
+        "got {:?}",
+        rendered
+    );
+    // The reasoning block is closed before the held-back answer is shown
+    assert!(
+        rendered.ends_with("+-\n{\"partial\": \n"),
+        "got {:?}",
+        rendered
+    );
+}
+
+#[tokio::test]
+async fn test_timeout_keeps_held_back_output_on_screen() {
+    // A server that sends one chunk and then goes quiet
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_request_body(&mut stream).await;
+        let chunk = json!({"choices": [{"delta": {"content": "Half an answer"}}]});
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n";
+        stream.write_all(head.as_bytes()).await.unwrap();
+        let event = format!("data: {}\n\n", chunk);
+        stream.write_all(event.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+    let response = reqwest::get(&url).await.unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    let error = process_streaming_response(
+        response,
+        &mut out,
+        &StreamSettings {
+            timeout_secs: 1,
+            reasoning_exclude: false,
+            verbose: false,
+            show_stats: false,
+            request_started: std::time::Instant::now(),
+            answer_header: None,
+            flush_interval: Duration::from_millis(50),
+            incremental: false,
+            quiet: false,
+        },
+        &RenderOptions::default(),
+        Spinner::hidden(),
+    )
+    .await
+    .err()
+    .unwrap();
+
+    assert!(matches!(error, Cmd2AiError::Timeout));
+    let rendered = String::from_utf8(out).unwrap();
+    assert_eq!(rendered, "Half an answer\n");
+}
+
+#[tokio::test]
+async fn test_client_accepts_and_decodes_compressed_responses() {
+    // gzip of "compressed answer"
+    const GZIPPED: [u8; 37] = [
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 75, 206, 207, 45, 40, 74, 45, 46, 78, 77, 81, 72, 204, 43,
+        46, 79, 45, 2, 0, 46, 243, 116, 252, 17, 0, 0, 0,
+    ];
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the headers were complete");
+            data.extend_from_slice(&buf[..n]);
+        }
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            GZIPPED.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(&GZIPPED).await.unwrap();
+        String::from_utf8_lossy(&data).to_lowercase()
+    });
+    let (config, _) = test_config(&url);
+    let client = build_client(&config).unwrap();
+
+    let body = client.get(&url).send().await.unwrap().text().await.unwrap();
+
+    assert_eq!(body, "compressed answer");
+    let headers = server.await.unwrap();
+    let accept_encoding = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("accept-encoding:"))
+        .unwrap();
+    assert!(accept_encoding.contains("gzip"));
+    assert!(accept_encoding.contains("br"));
+}
+
+#[tokio::test]
+async fn test_mid_stream_error_event_becomes_an_api_error() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"content": "Partial"}}]}),
+        json!({"error": {"code": "server_error", "message": "Provider disconnected"}}),
+    ])])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+
+    let error = query(config, vec![user_message("hi")]).await.unwrap_err();
+
+    match error {
+        Cmd2AiError::ApiError { status, message } => {
+            assert_eq!(status, 502);
+            assert_eq!(message, "Provider disconnected (server_error)");
+        }
+        other => panic!("expected an API error, got {}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_refusal_is_an_error_not_an_answer() {
+    let base_dir = TempDir::new().unwrap();
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "choices": [{
+            "message": {"role": "assistant", "content": null, "refusal": "I can't help with that."},
+            "finish_reason": "stop"
+        }]
+    }))])
+    .await;
+    let (config, args) = test_config(&server.endpoint);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        max_file_size_bytes: 1024,
+        script_dir: base_dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![user_message("something disallowed")];
+    match run(&context, &mut messages).await {
+        Err(Cmd2AiError::Refused(message)) => assert_eq!(message, "I can't help with that."),
+        Err(other) => panic!("expected a refusal, got {}", other),
+        Ok(outcome) => panic!("expected a refusal, got answer {:?}", outcome.content),
+    }
+}
+
+#[tokio::test]
+async fn test_batch_prompts_share_a_context_but_not_a_conversation() {
+    let server = MockServer::start(vec![
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "One"}}]})]),
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "Two"}}]})]),
+    ])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut answers = Vec::new();
+    for prompt in parse_batch("first\nsecond\n") {
+        let mut messages = batch_messages(&[], &prompt);
+        answers.push(run(&context, &mut messages).await.unwrap().content);
+    }
+
+    assert_eq!(answers, vec!["One", "Two"]);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(requests[1]["messages"][0]["content"], "second");
+}
+
+#[tokio::test]
+async fn test_concurrent_batch_results_keep_prompt_order() {
+    let server = MockServer::start(
+        ["A", "B", "C"]
+            .iter()
+            .map(|text| MockResponse::sse(&[json!({"choices": [{"delta": {"content": text}}]})]))
+            .collect(),
+    )
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+    let prompts: Vec<BatchPrompt> = parse_batch("one\ntwo\nthree\n")
+        .into_iter()
+        .map(|message| BatchPrompt {
+            model: if message == "two" {
+                "routed-model".to_string()
+            } else {
+                context.config.model.clone()
+            },
+            message,
+        })
+        .collect();
+    let started = Mutex::new(Vec::new());
+
+    let results: Vec<_> = run_prompts(&context, &[], &prompts, 3, |index| {
+        started.lock().unwrap().push(index)
+    })
+    .collect()
+    .await;
+
+    let indices: Vec<usize> = results.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, vec![0, 1, 2]);
+    let mut answers: Vec<String> = results
+        .into_iter()
+        .map(|(_, result)| result.unwrap().content)
+        .collect();
+    answers.sort();
+    assert_eq!(answers, vec!["A", "B", "C"]);
+    assert_eq!(started.lock().unwrap().len(), 3);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    // Each prompt is sent to its own model
+    for request in &requests {
+        let prompt = request["messages"][0]["content"].as_str().unwrap();
+        let expected = if prompt == "two" {
+            "routed-model".to_string()
+        } else {
+            context.config.model.clone()
+        };
+        assert_eq!(request["model"], expected);
+    }
+}
+
+#[tokio::test]
+async fn test_malformed_history_fails_before_any_request() {
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "choices": [{"message": {"role": "assistant", "content": "unreachable"}}]
+    }))])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+
+    let mut orphan = user_message("result");
+    orphan.role = "tool".to_string();
+    orphan.tool_call_id = Some("call_gone".to_string());
+
+    match query(config, vec![orphan, user_message("hi")]).await {
+        Err(Cmd2AiError::SessionError(message)) => {
+            assert!(message.contains("message 1 (tool)"), "{}", message)
+        }
+        other => panic!(
+            "expected a session error, got {:?}",
+            other.map(|o| o.content)
+        ),
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_override_forces_a_non_streamed_request() {
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "choices": [{"message": {"role": "assistant", "content": "{\"ok\": true}"}}]
+    }))])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    config.stream = Some(false);
+
+    let outcome = query(config, vec![user_message("json please")])
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.content, "{\"ok\": true}");
+    assert_eq!(server.requests()[0]["stream"], false);
+}
+
+#[tokio::test]
+async fn test_raw_request_fields_are_merged_into_the_body() {
+    let server = MockServer::start(vec![MockResponse::sse(&[
+        json!({"choices": [{"delta": {"content": "hi"}, "finish_reason": "stop"}]}),
+    ])])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = false;
+    config.presence_penalty = Some(0.5);
+    config.raw_request = Some(json!({
+        "top_k": 20,
+        "provider": {"order": ["x"]},
+        "presence_penalty": null
+    }));
+
+    query(config, vec![user_message("hello")]).await.unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request["top_k"], 20);
+    assert_eq!(request["provider"]["order"][0], "x");
+    assert!(request.get("presence_penalty").is_none());
+    assert_eq!(request["messages"][0]["content"], "hello");
+}
+
+#[tokio::test]
+async fn test_summarize_history_replaces_trimmed_turns() {
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "choices": [{"message": {"role": "assistant", "content": "They asked about u1 and u2."}}]
+    }))])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.summarize_history = true;
+    config.summary_model = Some("cheap/model".to_string());
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = Vec::new();
+    for turn in 1..=4 {
+        messages.push(user_message(&format!("u{}", turn)));
+        messages.push(Message {
+            role: "assistant".to_string(),
+            content: Some(format!("a{}", turn)),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+    messages.push(user_message("u5"));
+    trim_history(&context, &mut messages).await;
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["model"], "cheap/model");
+    assert_eq!(requests[0]["stream"], false);
+    assert_eq!(
+        requests[0]["messages"][1]["content"],
+        "user: u1\n\nassistant: a1\n\nuser: u2"
+    );
+
+    assert_eq!(messages.len(), 7);
+    assert_eq!(messages[0].role, "assistant");
+    assert_eq!(
+        messages[0].content.as_deref(),
+        Some("Summary of the earlier conversation:\nThey asked about u1 and u2.")
+    );
+    assert_eq!(messages[1].content.as_deref(), Some("a2"));
+}
+
+#[tokio::test]
+async fn test_large_tool_results_are_shortened_for_the_model() {
+    let base_dir = TempDir::new().unwrap();
+    let contents = format!("{}{}", "a".repeat(300), "b".repeat(300));
+    fs::write(base_dir.path().join("big.txt"), &contents).unwrap();
+
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "read_file", "arguments": "{\"path\": \"big.txt\"}"}
+                    }]
+                }
+            }]
+        })),
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "It is long"}}]})]),
+    ])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.large_tool_result_bytes = Some(100);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        max_file_size_bytes: 4096,
+        script_dir: base_dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![user_message("read big.txt")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    let expected = format!(
+        "{}\n\n[... 500 bytes omitted ...]\n\n{}",
+        "a".repeat(50),
+        "b".repeat(50)
+    );
+    assert_eq!(outcome.tool_calls[0].result, expected);
+    assert_eq!(server.requests()[1]["messages"][2]["content"], expected);
+}
+
+#[test]
+fn test_context_is_built_from_config_and_args() {
+    let (config, _) = test_config("http://127.0.0.1:9");
+    let args = Args::parse_from(["ai", "--no-tools", "--reasoning-to-stderr", "hello"]);
+    let context = OrchestratorContext::new(config, args).unwrap();
+    assert!(context.local_tools_registry.is_none());
+    assert!(context.render_options.reasoning_to_stderr);
+    assert!(!context.quiet);
+
+    let (config, _) = test_config("http://127.0.0.1:9");
+    let context = OrchestratorContext::new(config, Args::default()).unwrap();
+    assert_eq!(
+        context.local_tools_registry.is_some(),
+        context.config.tools_enabled && context.config.local_tools_config.enabled
+    );
+    assert!(!context.render_options.reasoning_to_stderr);
+}
+
+#[tokio::test]
+async fn test_exposed_tools_are_ranked_by_the_prompt_sent() {
+    let server = MockServer::start(vec![MockResponse::json(json!({
+        "choices": [{"message": {"role": "assistant", "content": "Sunny"}}]
+    }))])
+    .await;
+    let (mut config, _) = test_config(&server.endpoint);
+    config.tools_enabled = true;
+    config.local_tools_config.enabled = true;
+    config.local_tools_config.tools = [
+        "aardvark: Facts about aardvarks",
+        "weather: Today's weather",
+    ]
+    .iter()
+    .map(|tool| {
+        let (name, description) = tool.split_once(": ").unwrap();
+        serde_yaml::from_str(&format!(
+            "name: {}\ntype: command\ndescription: {}\ncommand: echo\n",
+            name, description
+        ))
+        .unwrap()
+    })
+    .collect();
+    config.max_exposed_tools = Some(1);
+
+    // `query` has no command line; the ranking comes from the message itself
+    let outcome = query(config, vec![user_message("what's the weather like?")])
+        .await
+        .unwrap();
+    assert_eq!(outcome.content, "Sunny");
+
+    let tools = server.requests()[0]["tools"].as_array().unwrap().clone();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0]["function"]["name"], "weather");
+}
+
+#[tokio::test]
+async fn test_tool_call_without_local_tools_reports_one_error() {
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "read_file", "arguments": "{}"}
+                    }]
+                }
+            }]
+        })),
+        MockResponse::json(json!({
+            "choices": [{"message": {"role": "assistant", "content": "No tools"}}]
+        })),
+    ])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    // Nothing is offered, so only --no-stream gets the tool call parsed
+    config.stream = Some(false);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: None,
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![user_message("read a file")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    let error = "Error: Tool 'read_file' not found. No tools are available.";
+    assert_eq!(outcome.tool_calls[0].result, error);
+    assert!(outcome.tool_calls[0].is_error);
+    assert_eq!(server.requests()[1]["messages"][2]["content"], error);
+}
+
+#[tokio::test]
+async fn test_tool_call_without_arguments_is_recorded_as_failed() {
+    let server = MockServer::start(vec![
+        MockResponse::json(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "read_file"}
+                    }]
+                }
+            }]
+        })),
+        MockResponse::sse(&[json!({"choices": [{"delta": {"content": "Sorry"}}]})]),
+    ])
+    .await;
+    let (config, args) = test_config(&server.endpoint);
+    let base_dir = TempDir::new().unwrap();
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        ..LocalSettings::from_config(&LocalToolsConfig::default(), false)
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![user_message("read a file")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    // The invocation matches the tool message the model got
+    assert_eq!(outcome.tool_calls.len(), 1);
+    let invocation = &outcome.tool_calls[0];
+    assert_eq!(invocation.id, "call_1");
+    assert_eq!(invocation.arguments, Value::Null);
+    assert!(invocation.is_error);
+    let followup = server.requests()[1]["messages"].clone();
+    assert_eq!(followup[2]["tool_call_id"], "call_1");
+    assert_eq!(followup[2]["content"], invocation.result.as_str());
+}
+
+#[tokio::test]
+async fn test_forced_stream_still_runs_tool_calls() {
+    let base_dir = TempDir::new().unwrap();
+    fs::write(base_dir.path().join("notes.txt"), "buy milk").unwrap();
+
+    let server = MockServer::start(vec![
+        MockResponse::sse(&[
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 0,
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "read_file", "arguments": "{\"path\": "}
+            }]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 0,
+                "function": {"arguments": "\"notes.txt\"}"}
+            }]}, "finish_reason": "tool_calls"}]}),
+        ]),
+        MockResponse::sse(&[json!({
+            "choices": [{"delta": {"content": "Your note says: buy milk"}}]
+        })]),
+    ])
+    .await;
+    let (mut config, args) = test_config(&server.endpoint);
+    config.stream = Some(true);
+    let settings = LocalSettings {
+        base_dir: base_dir.path().to_path_buf(),
+        ..LocalSettings::from_config(&LocalToolsConfig::default(), false)
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+    let client = build_client(&config).unwrap();
+    let context = OrchestratorContext {
+        config,
+        client,
+        args,
+        local_tools_registry: Some(registry),
+        render_options: RenderOptions::default(),
+        rate_limiter: None,
+        quiet: true,
+    };
+
+    let mut messages = vec![user_message("what's in notes.txt?")];
+    let outcome = run(&context, &mut messages).await.unwrap();
+
+    assert_eq!(outcome.content, "Your note says: buy milk");
+    assert_eq!(outcome.tool_calls.len(), 1);
+    assert_eq!(
+        outcome.tool_calls[0].arguments,
+        json!({"path": "notes.txt"})
+    );
+    assert_eq!(outcome.tool_calls[0].result, "buy milk");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["stream"], true);
+    assert_eq!(requests[0]["tools"][0]["function"]["name"], "read_file");
+    let followup = requests[1]["messages"].as_array().unwrap();
+    assert_eq!(followup[1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(followup[2]["tool_call_id"], "call_1");
+    assert_eq!(followup[2]["content"], "buy milk");
+}
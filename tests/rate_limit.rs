@@ -0,0 +1,29 @@
+use cmd2ai::api::RateLimiter;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn test_rate_limiter_spaces_requests_evenly() {
+    // 1200 per minute: one request every 50ms, the first immediately
+    let limiter = RateLimiter::per_minute(1200);
+    let start = Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn test_rate_limiter_backs_off_on_retry_after() {
+    let limiter = RateLimiter::per_minute(60_000);
+    let mut headers = HeaderMap::new();
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("1"));
+
+    limiter.acquire().await;
+    limiter.back_off(&headers);
+    let start = Instant::now();
+    limiter.acquire().await;
+    assert!(start.elapsed() >= Duration::from_millis(900));
+}
@@ -1,5 +1,9 @@
-use cmd2ai::models::{Message, Session};
-use cmd2ai::session::{FilesystemSessionStore, SessionStore};
+use cmd2ai::models::{FunctionCall, Message, Session, ToolCall};
+use cmd2ai::session::{
+    apply_system_prompt, estimate_tokens, fit_context_window, history_transcript,
+    merge_consecutive_messages, messages_to_trim, trim_conversation_history,
+    FilesystemSessionStore, SessionStore,
+};
 use chrono::Local;
 use std::fs;
 use tempfile::TempDir;
@@ -14,6 +18,8 @@ fn create_test_session(id: &str, age_minutes: i64) -> Session {
             tool_calls: None,
             tool_call_id: None,
         }],
+        system_prompt: None,
+        last_request_id: None,
     }
 }
 
@@ -103,3 +109,250 @@ fn test_find_most_recent_session() {
     assert_eq!(found.session_id, "new");
 }
 
+fn system_content(session: &Session) -> &str {
+    assert_eq!(session.messages[0].role, "system");
+    session.messages[0].content.as_deref().unwrap()
+}
+
+#[test]
+fn test_continue_keeps_custom_system_prompt() {
+    // First run: --system sets the prompt for the conversation
+    let mut session = create_test_session("system-123", 0);
+    let replace = Some("Be terse");
+    apply_system_prompt(&mut session, Some("From config"), replace, None, Some("Today"));
+    assert_eq!(system_content(&session), "Today\n\nBe terse");
+    assert_eq!(session.messages.len(), 2);
+
+    // Continue without flags: the stored prompt wins over config, and survives a reload
+    let json = serde_json::to_string(&session).unwrap();
+    let mut continued: Session = serde_json::from_str(&json).unwrap();
+    apply_system_prompt(&mut continued, Some("From config"), None, None, Some("Tomorrow"));
+    assert_eq!(system_content(&continued), "Tomorrow\n\nBe terse");
+    assert_eq!(continued.messages.len(), 2);
+
+    // --append-system extends the prompt in effect
+    let append = Some("Use bullets");
+    apply_system_prompt(&mut continued, Some("From config"), None, append, Some("Today"));
+    assert_eq!(system_content(&continued), "Today\n\nBe terse\n\nUse bullets");
+    assert_eq!(continued.system_prompt.as_deref(), Some("Be terse\n\nUse bullets"));
+}
+
+#[test]
+fn test_new_session_uses_config_system_prompt() {
+    let mut session = create_test_session("system-456", 0);
+    session.messages.clear();
+    apply_system_prompt(&mut session, Some("From config"), None, None, Some("Today"));
+    assert_eq!(system_content(&session), "Today\n\nFrom config");
+
+    let mut bare = create_test_session("system-789", 0);
+    apply_system_prompt(&mut bare, None, None, None, Some("Today"));
+    assert_eq!(system_content(&bare), "Today");
+    assert_eq!(bare.system_prompt, None);
+}
+
+#[test]
+fn test_disabled_date_injection_omits_date_line() {
+    let mut session = create_test_session("nodate-123", 0);
+    apply_system_prompt(&mut session, Some("From config"), None, None, None);
+    assert_eq!(system_content(&session), "From config");
+
+    // No date and no prompt: no system message at all
+    let mut bare = create_test_session("nodate-456", 0);
+    apply_system_prompt(&mut bare, None, None, None, None);
+    assert_eq!(bare.messages.len(), 1);
+    assert_eq!(bare.messages[0].role, "user");
+}
+
+#[test]
+fn test_save_prunes_oldest_sessions_beyond_max_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = FilesystemSessionStore::new()
+        .with_cache_dir(temp_dir.path().to_path_buf())
+        .with_max_files(2);
+
+    for (id, age_minutes) in [("oldest", 90), ("older", 60), ("old", 30)] {
+        store.save_session(&create_test_session(id, age_minutes)).unwrap();
+    }
+
+    let mut remaining: Vec<String> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    remaining.sort();
+    assert_eq!(remaining, ["session-old.json", "session-older.json"]);
+
+    // Explicit pruning reports what it removed
+    let removed = FilesystemSessionStore::new()
+        .with_cache_dir(temp_dir.path().to_path_buf())
+        .prune_sessions(1)
+        .unwrap();
+    assert_eq!(removed, 1);
+    assert!(temp_dir.path().join("session-old.json").exists());
+}
+
+fn message(role: &str, content: &str) -> Message {
+    Message {
+        role: role.to_string(),
+        content: Some(content.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+#[test]
+fn test_trim_never_orphans_tool_results() {
+    let call = |id: &str| ToolCall {
+        id: id.to_string(),
+        tool_type: "function".to_string(),
+        function: FunctionCall {
+            name: "read_file".to_string(),
+            arguments: "{}".to_string(),
+        },
+    };
+    let mut tool_turn = message("assistant", "");
+    tool_turn.tool_calls = Some(vec![call("call_1"), call("call_2")]);
+    let mut result_1 = message("tool", "one");
+    result_1.tool_call_id = Some("call_1".to_string());
+    let mut result_2 = message("tool", "two");
+    result_2.tool_call_id = Some("call_2".to_string());
+
+    // Keeping the last 6 conversation messages would start at `result_2`
+    let mut messages = vec![
+        message("system", "Be brief"),
+        message("user", "read both files"),
+        tool_turn,
+        result_1,
+        result_2,
+        message("assistant", "Both read"),
+        message("user", "u2"),
+        message("assistant", "a2"),
+        message("user", "u3"),
+        message("assistant", "a3"),
+    ];
+    trim_conversation_history(&mut messages);
+
+    let contents: Vec<&str> = messages
+        .iter()
+        .map(|m| m.content.as_deref().unwrap_or_default())
+        .collect();
+    assert_eq!(
+        contents,
+        vec!["Be brief", "Both read", "u2", "a2", "u3", "a3"]
+    );
+}
+
+#[test]
+fn test_merge_consecutive_messages_collapses_system_messages() {
+    let mut tool_turn = message("assistant", "");
+    tool_turn.tool_calls = Some(Vec::new());
+    let mut result_1 = message("tool", "one");
+    result_1.tool_call_id = Some("call_1".to_string());
+    let mut result_2 = message("tool", "two");
+    result_2.tool_call_id = Some("call_2".to_string());
+
+    let mut messages = vec![
+        message("system", "Today is Monday"),
+        message("system", "Be brief"),
+        message("user", "interrupted question"),
+        message("user", "new question"),
+        tool_turn,
+        result_1,
+        result_2,
+        message("assistant", "answer"),
+    ];
+    merge_consecutive_messages(&mut messages);
+
+    let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "tool", "tool", "assistant"]);
+    assert_eq!(
+        messages[0].content.as_deref(),
+        Some("Today is Monday\n\nBe brief")
+    );
+    assert_eq!(
+        messages[1].content.as_deref(),
+        Some("interrupted question\n\nnew question")
+    );
+}
+
+#[test]
+fn test_fit_context_window_drops_oldest_history_first() {
+    let long = "x".repeat(400); // ~100 tokens
+    let mut messages = vec![
+        message("system", "Be brief"),
+        message("user", &long),
+        message("assistant", &long),
+        message("user", "short question"),
+    ];
+    assert_eq!(estimate_tokens(&messages), 206);
+
+    // Nothing to do while the prompt fits
+    assert_eq!(fit_context_window(&mut messages, 1000), Ok(0));
+    assert_eq!(messages.len(), 4);
+
+    assert_eq!(fit_context_window(&mut messages, 120), Ok(1));
+    let contents: Vec<&str> = messages
+        .iter()
+        .map(|m| m.content.as_deref().unwrap_or_default())
+        .collect();
+    assert_eq!(contents, vec!["Be brief", &long, "short question"]);
+
+    // The system prompt and the current message are never dropped
+    let mut messages = vec![message("system", "Be brief"), message("user", &long)];
+    let error = fit_context_window(&mut messages, 50).unwrap_err();
+    assert!(error.contains("about 102 tokens"), "{}", error);
+    assert_eq!(messages.len(), 2);
+}
+
+#[test]
+fn test_fit_context_window_keeps_the_current_tool_turn() {
+    let long = "x".repeat(400); // ~100 tokens
+    let mut tool_turn = message("assistant", "");
+    tool_turn.tool_calls = Some(vec![ToolCall {
+        id: "call_1".to_string(),
+        tool_type: "function".to_string(),
+        function: FunctionCall {
+            name: "read_file".to_string(),
+            arguments: "{}".to_string(),
+        },
+    }]);
+    let mut result = message("tool", &long);
+    result.tool_call_id = Some("call_1".to_string());
+    let mut messages = vec![
+        message("user", &long),
+        message("assistant", &long),
+        message("user", "read it"),
+        tool_turn,
+        result,
+    ];
+
+    // The old exchange goes, the prompt and the call answering it stay together
+    assert_eq!(fit_context_window(&mut messages, 150), Ok(2));
+    let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, vec!["user", "assistant", "tool"]);
+}
+
+#[test]
+fn test_messages_to_trim_and_their_transcript() {
+    let mut messages = vec![message("system", "Be brief")];
+    for turn in 1..=5 {
+        messages.push(message("user", &format!("u{}", turn)));
+        messages.push(message("assistant", &format!("a{}", turn)));
+    }
+
+    let dropped = messages_to_trim(&messages);
+    let contents: Vec<&str> = dropped
+        .iter()
+        .map(|m| m.content.as_deref().unwrap_or_default())
+        .collect();
+    assert_eq!(contents, vec!["u1", "a1", "u2", "a2"]);
+
+    assert_eq!(
+        history_transcript(&dropped, 1000),
+        "user: u1\n\nassistant: a1\n\nuser: u2\n\nassistant: a2"
+    );
+    // Over the budget, the oldest lines go first
+    assert_eq!(history_transcript(&dropped, 8), "user: u2\n\nassistant: a2");
+
+    trim_conversation_history(&mut messages);
+    assert!(messages_to_trim(&messages).is_empty());
+}
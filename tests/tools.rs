@@ -1,20 +1,32 @@
+use cmd2ai::config::{LocalToolConfig, LocalToolsConfig};
 use cmd2ai::local_tools::builtins::handle_read_file;
-use cmd2ai::local_tools::LocalSettings;
+use cmd2ai::local_tools::{
+    call_local_tool, cleanup_temp_scripts, format_tool_list, format_tools_for_llm,
+    limit_exposed_tools, network_isolation_supported, LocalSettings, LocalToolRegistry,
+};
 use serde_json::json;
 use std::fs;
 use tempfile::TempDir;
 
+/// Settings rooted at `dir`, with a 1 KiB file size limit
+fn settings(dir: &TempDir) -> LocalSettings {
+    LocalSettings {
+        base_dir: dir.path().to_path_buf(),
+        max_file_size_bytes: 1024,
+        script_dir: dir.path().join("scripts"),
+        strict_env: false,
+        timeout_override: None,
+        verbose: false,
+    }
+}
+
 #[test]
 fn test_read_file_success() {
     let temp_dir = TempDir::new().unwrap();
     let test_file = temp_dir.path().join("test.txt");
     fs::write(&test_file, "Hello, world!").unwrap();
 
-    let settings = LocalSettings {
-        base_dir: temp_dir.path().to_path_buf(),
-        max_file_size_bytes: 1024,
-        verbose: false,
-    };
+    let settings = settings(&temp_dir);
 
     let args = json!({
         "path": "test.txt"
@@ -27,11 +39,7 @@ fn test_read_file_success() {
 #[test]
 fn test_read_file_missing_path() {
     let temp_dir = TempDir::new().unwrap();
-    let settings = LocalSettings {
-        base_dir: temp_dir.path().to_path_buf(),
-        max_file_size_bytes: 1024,
-        verbose: false,
-    };
+    let settings = settings(&temp_dir);
 
     let args = json!({});
 
@@ -43,11 +51,7 @@ fn test_read_file_missing_path() {
 #[test]
 fn test_read_file_not_found() {
     let temp_dir = TempDir::new().unwrap();
-    let settings = LocalSettings {
-        base_dir: temp_dir.path().to_path_buf(),
-        max_file_size_bytes: 1024,
-        verbose: false,
-    };
+    let settings = settings(&temp_dir);
 
     let args = json!({
         "path": "nonexistent.txt"
@@ -58,6 +62,21 @@ fn test_read_file_not_found() {
     assert!(result.unwrap_err().contains("File not found"));
 }
 
+#[test]
+fn test_read_file_outside_base_dir_is_not_reported_as_missing() {
+    let outer_dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new_in(outer_dir.path()).unwrap();
+    fs::write(outer_dir.path().join("secret.txt"), "hidden").unwrap();
+    let settings = settings(&temp_dir);
+
+    let args = json!({
+        "path": "../secret.txt"
+    });
+
+    let error = handle_read_file(&args, &settings).unwrap_err();
+    assert!(error.contains("escapes base directory"));
+}
+
 #[test]
 fn test_read_file_too_large() {
     let temp_dir = TempDir::new().unwrap();
@@ -65,11 +84,8 @@ fn test_read_file_too_large() {
     let large_content = "x".repeat(2048);
     fs::write(&test_file, large_content).unwrap();
 
-    let settings = LocalSettings {
-        base_dir: temp_dir.path().to_path_buf(),
-        max_file_size_bytes: 1024, // Smaller than file size
-        verbose: false,
-    };
+    // 1024 bytes is smaller than the file
+    let settings = settings(&temp_dir);
 
     let args = json!({
         "path": "large.txt"
@@ -83,11 +99,7 @@ fn test_read_file_too_large() {
 #[test]
 fn test_read_file_path_traversal_prevention() {
     let temp_dir = TempDir::new().unwrap();
-    let settings = LocalSettings {
-        base_dir: temp_dir.path().to_path_buf(),
-        max_file_size_bytes: 1024,
-        verbose: false,
-    };
+    let settings = settings(&temp_dir);
 
     // Try to access file outside base_dir
     let args = json!({
@@ -99,3 +111,664 @@ fn test_read_file_path_traversal_prevention() {
     // Should fail due to path traversal prevention
 }
 
+#[tokio::test]
+async fn test_call_local_tool_verbose_with_emoji_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = LocalSettings {
+        verbose: true,
+        ..settings(&temp_dir)
+    };
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+
+    // Long enough that the 200-byte verbose preview cuts through a 4-byte emoji
+    let args = json!({
+        "path": format!("a{}", "😀".repeat(80))
+    });
+
+    let result = call_local_tool(&registry, "read_file", &args).await;
+    assert!(result.is_err());
+}
+
+fn command_tool(name: &str, command: &str) -> LocalToolConfig {
+    serde_yaml::from_str(&format!(
+        r#"
+name: "{}"
+type: command
+description: test tool
+command: {}
+input_schema:
+  type: object
+  properties: {{}}
+"#,
+        name, command
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_registry_skips_invalid_and_duplicate_tool_names() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let config = LocalToolsConfig {
+        tools: vec![
+            command_tool("good_tool", "echo"),
+            command_tool("good_tool", "true"),
+            command_tool("bad name!", "echo"),
+            command_tool("read_file", "cat"),
+        ],
+        ..Default::default()
+    };
+
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    assert!(registry.get("good_tool").is_some());
+    assert!(registry.get("bad name!").is_none());
+
+    let skipped: Vec<&str> = registry
+        .skipped_tools()
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(skipped, vec!["good_tool", "bad name!", "read_file"]);
+    assert!(registry.skipped_tools()[0].reason.contains("duplicate"));
+}
+
+#[test]
+fn test_missing_input_schema_is_derived_from_placeholders() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let no_args: LocalToolConfig = serde_yaml::from_str(
+        "name: uptime\ntype: command\ndescription: Show uptime\ncommand: uptime\n",
+    )
+    .unwrap();
+    let templated: LocalToolConfig = serde_yaml::from_str(
+        "name: list\ntype: command\ndescription: List a dir\ncommand: ls\nargs: [\"{{path}}\"]\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![no_args, templated],
+        ..Default::default()
+    };
+
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let uptime = registry.get("uptime").unwrap();
+    assert_eq!(uptime.input_schema["type"], "object");
+    assert_eq!(uptime.input_schema["additionalProperties"], false);
+    assert_eq!(uptime.input_schema["required"], json!([]));
+
+    let list = registry.get("list").unwrap();
+    assert_eq!(list.input_schema["properties"]["path"]["type"], "string");
+    assert_eq!(list.input_schema["required"], json!(["path"]));
+}
+
+#[tokio::test]
+async fn test_args_from_appends_positional_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool: LocalToolConfig = serde_yaml::from_str(
+        "name: say\ntype: command\ndescription: Echo words\ncommand: echo\nargs: [\"static\"]\nargs_from: [name, count]\nstdin_json: false\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![tool],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    // args_from keys are optional in the derived schema
+    let say = registry.get("say").unwrap();
+    assert_eq!(say.input_schema["properties"]["name"]["type"], "string");
+    assert_eq!(say.input_schema["required"], json!([]));
+
+    let output = call_local_tool(&registry, "say", &json!({"name": "alice", "count": "3"}))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "static alice 3");
+
+    // Missing keys are skipped
+    let output = call_local_tool(&registry, "say", &json!({"count": "3"}))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "static 3");
+}
+
+#[tokio::test]
+async fn test_flag_args_map_booleans_to_flags() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool: LocalToolConfig = serde_yaml::from_str(
+        "name: say\ntype: command\ndescription: Echo words\ncommand: echo\nargs: [\"first\", \"{{word}}\"]\nflag_args: {loud: \"--loud\", quiet: \"--quiet\"}\ninsert_double_dash: true\nstdin_json: false\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![tool],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let say = registry.get("say").unwrap();
+    assert_eq!(say.input_schema["properties"]["loud"]["type"], "boolean");
+    assert_eq!(say.input_schema["required"], json!(["word"]));
+
+    // Flags land before the "--" that guards templated values
+    let output = call_local_tool(
+        &registry,
+        "say",
+        &json!({"word": "hi", "loud": true, "quiet": false}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(output.trim(), "first --loud -- hi");
+
+    let output = call_local_tool(&registry, "say", &json!({"word": "hi"}))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "first -- hi");
+}
+
+#[tokio::test]
+async fn test_expand_arrays_passes_one_argument_per_element() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+    fs::write(temp_dir.path().join("b.txt"), "b").unwrap();
+    let settings = settings(&temp_dir);
+    let tool: LocalToolConfig = serde_yaml::from_str(
+        r#"
+name: lint
+type: command
+description: Lint files
+command: echo
+args: ["--tag={{tags}}", "{{files}}"]
+stdin_json: false
+template_validations:
+  tags:
+    kind: string
+    expand_arrays: true
+  files:
+    kind: path
+    expand_arrays: true
+"#,
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![tool],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let lint = registry.get("lint").unwrap();
+    assert_eq!(lint.input_schema["properties"]["files"]["type"], "array");
+    assert_eq!(
+        lint.input_schema["properties"]["files"]["items"]["type"],
+        "string"
+    );
+
+    let output = call_local_tool(
+        &registry,
+        "lint",
+        &json!({"tags": ["x", "y"], "files": ["a.txt", "b.txt"]}),
+    )
+    .await
+    .unwrap();
+    let base = temp_dir.path().canonicalize().unwrap();
+    assert_eq!(
+        output.trim(),
+        format!(
+            "-- --tag=x --tag=y {} {}",
+            base.join("a.txt").display(),
+            base.join("b.txt").display()
+        )
+    );
+
+    // Every element is validated on its own
+    let err = call_local_tool(
+        &registry,
+        "lint",
+        &json!({"tags": [], "files": ["a.txt", "../outside"]}),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.contains("Invalid path argument 'files'"), "got {}", err);
+}
+
+#[test]
+fn test_format_tool_list_shows_source_and_required_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let uptime: LocalToolConfig = serde_yaml::from_str(
+        "name: uptime\ntype: command\ndescription: Show uptime\ncommand: uptime\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![uptime],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let listing = format_tool_list(&registry);
+    assert!(listing.starts_with("read_file (builtin)\n"));
+    assert!(listing.contains("    required: path\n"));
+    assert!(listing.ends_with("uptime (dynamic)\n    Show uptime\n"));
+}
+
+#[test]
+fn test_builtins_can_be_disabled_while_keeping_dynamic_tools() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let config: LocalToolsConfig = serde_yaml::from_str(
+        "builtins_enabled: false\ntools:\n  - name: uptime\n    type: command\n    description: Show uptime\n    command: uptime\n",
+    )
+    .unwrap();
+
+    let registry = LocalToolRegistry::new(&config, settings);
+    assert!(registry.get("read_file").is_none());
+    assert!(registry.get("uptime").is_some());
+}
+
+#[test]
+fn test_strict_schema_off_ignores_unknown_arguments() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let args = json!({"path": "notes.txt", "encoding": "utf-8"});
+
+    let strict = LocalToolRegistry::new(&LocalToolsConfig::default(), settings.clone());
+    assert!(strict.validate_arguments("read_file", &args).is_err());
+
+    let config = LocalToolsConfig {
+        strict_schema: false,
+        ..Default::default()
+    };
+    let relaxed = LocalToolRegistry::new(&config, settings);
+    assert!(relaxed.validate_arguments("read_file", &args).is_ok());
+    // Declared properties are still checked
+    assert!(relaxed
+        .validate_arguments("read_file", &json!({"path": 3, "encoding": "utf-8"}))
+        .is_err());
+}
+
+#[test]
+fn test_tool_examples_are_validated_and_shown_to_model() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool_yaml = |name: &str, example: &str| {
+        format!(
+            r#"
+name: {}
+type: command
+description: Count words
+command: wc
+input_schema:
+  type: object
+  properties:
+    text:
+      type: string
+  required: [text]
+examples:
+  - {}
+"#,
+            name, example
+        )
+    };
+    let good: LocalToolConfig =
+        serde_yaml::from_str(&tool_yaml("good_examples", "{text: hello}")).unwrap();
+    let bad: LocalToolConfig =
+        serde_yaml::from_str(&tool_yaml("bad_examples", "{text: 42}")).unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![good, bad],
+        ..Default::default()
+    };
+
+    let registry = LocalToolRegistry::new(&config, settings);
+    assert!(registry.get("bad_examples").is_none());
+
+    let tools = format_tools_for_llm(&registry);
+    let good_tool = tools
+        .iter()
+        .find(|t| t["function"]["name"] == "good_examples")
+        .unwrap();
+    let description = good_tool["function"]["description"].as_str().unwrap();
+    assert!(description.contains("Example arguments:"));
+    assert!(description.contains(r#"{"text":"hello"}"#));
+}
+
+#[test]
+fn test_validation_errors_name_the_failing_schema_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let registry = LocalToolRegistry::new(&LocalToolsConfig::default(), settings);
+
+    let err = registry
+        .validate_arguments("read_file", &json!({"path": 42}))
+        .unwrap_err();
+    assert!(err.starts_with("Invalid arguments for tool 'read_file'"));
+    assert!(err.contains("at '/path' (schema path '/properties/path/type')"));
+
+    let err = registry
+        .validate_arguments("read_file", &json!({}))
+        .unwrap_err();
+    assert!(err.contains("at '/' (schema path '/required')"));
+}
+
+#[test]
+fn test_limit_exposed_tools_prefers_relevant_tools() {
+    let tool = |name: &str, description: &str| {
+        json!({"type": "function", "function": {"name": name, "description": description}})
+    };
+    let tools = vec![
+        tool("alpha", "Does alpha things"),
+        tool("beta", "Does beta things"),
+        tool("git_log", "Show the git history"),
+        tool("weather", "Current weather forecast"),
+    ];
+
+    let (kept, omitted) = limit_exposed_tools(tools.clone(), 2, "what's the weather today?");
+    let names: Vec<&str> = kept
+        .iter()
+        .map(|t| t["function"]["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["alpha", "weather"]);
+    assert_eq!(omitted, vec!["beta", "git_log"]);
+
+    let (kept, omitted) = limit_exposed_tools(tools, 10, "anything");
+    assert_eq!(kept.len(), 4);
+    assert!(omitted.is_empty());
+}
+
+#[test]
+fn test_cleanup_temp_scripts_removes_leftovers() {
+    let temp_dir = TempDir::new().unwrap();
+    let scripts = temp_dir.path().join("scripts");
+    fs::create_dir_all(&scripts).unwrap();
+    let ours = scripts.join(format!("cmd2ai-{}-tool-abc.sh", std::process::id()));
+    let other_process = scripts.join("cmd2ai-1-tool-def.sh");
+    fs::write(&ours, "echo hi").unwrap();
+    fs::write(&other_process, "echo hi").unwrap();
+
+    cleanup_temp_scripts(&scripts).unwrap();
+    assert!(!ours.exists());
+    assert!(other_process.exists());
+
+    // Nothing to clean is not an error
+    cleanup_temp_scripts(&temp_dir.path().join("missing")).unwrap();
+}
+
+#[tokio::test]
+async fn test_inline_script_temp_files_are_removed() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let script_tool = |name: &str, script: &str| -> LocalToolConfig {
+        serde_yaml::from_str(&format!(
+            "name: {}\ntype: script\ndescription: test\ninterpreter: sh\nscript: \"{}\"\n",
+            name, script
+        ))
+        .unwrap()
+    };
+    let config = LocalToolsConfig {
+        tools: vec![
+            script_tool("hello", "cat >/dev/null; echo hi"),
+            script_tool("failing", "cat >/dev/null; exit 3"),
+        ],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let output = call_local_tool(&registry, "hello", &json!({})).await.unwrap();
+    assert_eq!(output.trim(), "hi");
+    assert!(call_local_tool(&registry, "failing", &json!({})).await.is_err());
+
+    // Scripts go to script_dir, never base_dir
+    let scripts = temp_dir.path().join("scripts");
+    assert_eq!(fs::read_dir(&scripts).unwrap().count(), 0);
+    assert!(!temp_dir.path().join(".cmd2ai-tools").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_inline_script_extension_uses_interpreter_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    // A full interpreter path still picks the extension from its file name
+    let tool: LocalToolConfig = serde_yaml::from_str(
+        "name: whoami\ntype: script\ndescription: test\ninterpreter: /bin/sh\nscript: \"cat >/dev/null; echo $0\"\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![tool],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let output = call_local_tool(&registry, "whoami", &json!({})).await.unwrap();
+    assert!(output.trim().ends_with(".sh"), "got {}", output);
+}
+
+#[cfg(windows)]
+#[tokio::test]
+async fn test_inline_script_runs_on_windows() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool: LocalToolConfig = serde_yaml::from_str(
+        "name: hello\ntype: script\ndescription: test\ninterpreter: cmd\nscript: \"@echo hello\"\n",
+    )
+    .unwrap();
+    let config = LocalToolsConfig {
+        tools: vec![tool],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let output = call_local_tool(&registry, "hello", &json!({})).await.unwrap();
+    assert_eq!(output.trim(), "hello");
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_network_false_runs_tool_in_its_own_network_namespace() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool = |name: &str, network: bool| -> LocalToolConfig {
+        serde_yaml::from_str(&format!(
+            "name: {}\ntype: command\ndescription: test\ncommand: readlink\nargs: [/proc/self/ns/net]\nstdin_json: false\nnetwork: {}\n",
+            name, network
+        ))
+        .unwrap()
+    };
+    let config = LocalToolsConfig {
+        tools: vec![tool("online", true), tool("offline", false)],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let online = call_local_tool(&registry, "online", &json!({})).await.unwrap();
+    // Without isolation support the tool still runs, just with network access
+    let offline = call_local_tool(&registry, "offline", &json!({})).await.unwrap();
+    assert_eq!(online != offline, network_isolation_supported());
+}
+
+#[tokio::test]
+async fn test_max_concurrency_serializes_calls() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let tool = |name: &str, max_concurrency: usize| -> LocalToolConfig {
+        serde_yaml::from_str(&format!(
+            "name: {}\ntype: command\ndescription: test\ncommand: sleep\nargs: [\"0.3\"]\nstdin_json: false\nmax_concurrency: {}\n",
+            name, max_concurrency
+        ))
+        .unwrap()
+    };
+    let config = LocalToolsConfig {
+        tools: vec![tool("limited", 1), tool("never", 0)],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    // A limit of 0 would block forever, so such tools are rejected
+    assert!(registry.get("never").is_none());
+
+    let args = json!({});
+    let started = std::time::Instant::now();
+    let (first, second) = tokio::join!(
+        call_local_tool(&registry, "limited", &args),
+        call_local_tool(&registry, "limited", &args)
+    );
+    first.unwrap();
+    second.unwrap();
+    assert!(started.elapsed() >= std::time::Duration::from_millis(600));
+}
+
+#[tokio::test]
+async fn test_output_filter_extracts_json_paths_and_regex_matches() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let output = r#"{"items": [{"name": "a", "size": 1}, {"name": "b", "size": 2}], "total count": 2}"#;
+    let tool = |name: &str, filter: serde_json::Value| -> LocalToolConfig {
+        serde_json::from_value(json!({
+            "name": name,
+            "type": "command",
+            "description": "test",
+            "command": "echo",
+            "args": [output],
+            "stdin_json": false,
+            "output_filter": filter
+        }))
+        .unwrap()
+    };
+    let config = LocalToolsConfig {
+        tools: vec![
+            tool("names", json!({"json_path": ".items[].name"})),
+            tool("first", json!({"json_path": ".items[0]"})),
+            tool("count", json!({"json_path": ".[\"total count\"]"})),
+            tool("sizes", json!({"regex": r#""size": (\d+)"#})),
+            tool("missing", json!({"json_path": ".nope"})),
+            tool("bad_path", json!({"json_path": "items"})),
+            tool("both", json!({"json_path": ".items", "regex": "a"})),
+        ],
+        ..Default::default()
+    };
+    let registry = LocalToolRegistry::new(&config, settings);
+    let no_args = json!({});
+    let call = |name: &'static str| call_local_tool(&registry, name, &no_args);
+
+    assert_eq!(call("names").await.unwrap(), r#"["a","b"]"#);
+    assert_eq!(call("first").await.unwrap(), r#"{"name":"a","size":1}"#);
+    assert_eq!(call("count").await.unwrap(), "2");
+    assert_eq!(call("sizes").await.unwrap(), "1\n2");
+
+    // A filter that doesn't match falls back to the raw output with a note
+    let fallback = call("missing").await.unwrap();
+    assert!(fallback.starts_with(output));
+    assert!(fallback.contains("output_filter not applied (no key 'nope')"));
+
+    // Malformed filters are caught when the tool is registered
+    assert!(registry.get("bad_path").is_none());
+    assert!(registry.get("both").is_none());
+}
+
+#[tokio::test]
+async fn test_global_tool_limit_defaults_apply_unless_overridden() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = settings(&temp_dir);
+    let config: LocalToolsConfig = serde_yaml::from_str(
+        r#"
+default_max_output_bytes: 4
+tools:
+  - name: uses_default
+    type: command
+    description: test
+    command: echo
+    args: ["hello"]
+    stdin_json: false
+  - name: overrides
+    type: command
+    description: test
+    command: echo
+    args: ["hello"]
+    stdin_json: false
+    max_output_bytes: 100
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.default_timeout_secs, 30);
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let err = call_local_tool(&registry, "uses_default", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.contains("(max: 4 bytes)"), "got {}", err);
+    let output = call_local_tool(&registry, "overrides", &json!({}))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "hello");
+}
+
+#[tokio::test]
+async fn test_strict_env_rejects_unset_variables() {
+    std::env::remove_var("CMD2AI_TEST_UNSET_A");
+    std::env::remove_var("CMD2AI_TEST_UNSET_B");
+    let temp_dir = TempDir::new().unwrap();
+    let settings = |strict_env: bool| LocalSettings {
+        strict_env,
+        ..settings(&temp_dir)
+    };
+    let config: LocalToolsConfig = serde_yaml::from_str(
+        r#"
+tools:
+  - name: echo_env
+    type: command
+    description: test
+    command: echo
+    args: ["${CMD2AI_TEST_UNSET_B}"]
+    stdin_json: false
+    env:
+      TOKEN: "${CMD2AI_TEST_UNSET_A}"
+"#,
+    )
+    .unwrap();
+
+    let strict = LocalToolRegistry::new(&config, settings(true));
+    let err = call_local_tool(&strict, "echo_env", &json!({}))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        "Tool 'echo_env' references unset environment variables: CMD2AI_TEST_UNSET_A, CMD2AI_TEST_UNSET_B"
+    );
+
+    // Lenient mode keeps the old behaviour and passes the reference through
+    let lenient = LocalToolRegistry::new(&config, settings(false));
+    let output = call_local_tool(&lenient, "echo_env", &json!({}))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "${CMD2AI_TEST_UNSET_B}");
+}
+
+#[tokio::test]
+async fn test_timeout_override_replaces_tool_timeout() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = LocalSettings {
+        timeout_override: Some(1),
+        ..settings(&temp_dir)
+    };
+    let config: LocalToolsConfig = serde_yaml::from_str(
+        r#"
+tools:
+  - name: slow
+    type: command
+    description: test
+    command: sleep
+    args: ["5"]
+    stdin_json: false
+    timeout_secs: 60
+"#,
+    )
+    .unwrap();
+    let registry = LocalToolRegistry::new(&config, settings);
+
+    let started = std::time::Instant::now();
+    let err = call_local_tool(&registry, "slow", &json!({}))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "Command execution timed out after 1 seconds");
+    assert!(started.elapsed() < std::time::Duration::from_secs(4));
+}
@@ -0,0 +1,145 @@
+use cmd2ai::util::{
+    merge_json, shorten_middle, strip_markdown, truncate_for_display, wrap_command, write_answer,
+};
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_truncate_short_string_unchanged() {
+    assert_eq!(truncate_for_display("hello", 100), "hello");
+}
+
+#[test]
+fn test_truncate_ascii_string() {
+    let long = "a".repeat(150);
+    let truncated = truncate_for_display(&long, 100);
+    assert_eq!(truncated, format!("{}...", "a".repeat(100)));
+}
+
+#[test]
+fn test_truncate_multibyte_string_does_not_panic() {
+    // 'é' is 2 bytes, so byte 100 falls in the middle of a character
+    let long = format!("a{}", "é".repeat(100));
+    let truncated = truncate_for_display(&long, 100);
+    assert!(truncated.ends_with("..."));
+    assert!(truncated.len() <= 103);
+    assert_eq!(truncated, format!("a{}...", "é".repeat(49)));
+}
+
+#[test]
+fn test_write_answer_overwrites_or_appends() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("answer.md");
+
+    write_answer(&path, "first", false).unwrap();
+    write_answer(&path, "second\n", true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+
+    write_answer(&path, "third", false).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+}
+
+#[test]
+fn test_wrap_command_with_prepend_and_append() {
+    assert_eq!(wrap_command("list files", None, None), "list files");
+    assert_eq!(
+        wrap_command("list files", Some("Answer concisely:"), Some("Use bash.")),
+        "Answer concisely:\n\nlist files\n\nUse bash."
+    );
+    assert_eq!(wrap_command("list files", Some(""), None), "list files");
+}
+
+#[test]
+fn test_strip_markdown() {
+    let markdown = "\
+# Setup
+
+Run **cargo build** or *cargo check*, see [the docs](https://doc.rust-lang.org).
+
+> Note: `__init__.py` stays as written
+
+* first
+  + nested
+---
+```rust
+let s = \"**not bold**\";
+```";
+
+    assert_eq!(
+        strip_markdown(markdown),
+        "\
+Setup
+
+Run cargo build or cargo check, see the docs (https://doc.rust-lang.org).
+
+Note: __init__.py stays as written
+
+- first
+  - nested
+let s = \"**not bold**\";"
+    );
+}
+
+#[test]
+fn test_strip_markdown_handles_tilde_and_longer_fences() {
+    let markdown = "\
+~~~python
+x = \"**kept**\"
+```
+~~~
+````markdown
+```
+nested
+```
+````
+Done.";
+
+    assert_eq!(
+        strip_markdown(markdown),
+        "x = \"**kept**\"\n```\n```\nnested\n```\nDone."
+    );
+}
+
+#[test]
+fn test_merge_json_follows_merge_patch_rules() {
+    let mut body = json!({
+        "model": "a",
+        "stream": true,
+        "presence_penalty": 0.5,
+        "provider": {"order": ["x"], "allow_fallbacks": true}
+    });
+    merge_json(
+        &mut body,
+        &json!({
+            "model": "b",
+            "presence_penalty": null,
+            "provider": {"allow_fallbacks": false},
+            "transforms": ["middle-out"]
+        }),
+    );
+
+    assert_eq!(
+        body,
+        json!({
+            "model": "b",
+            "stream": true,
+            "provider": {"order": ["x"], "allow_fallbacks": false},
+            "transforms": ["middle-out"]
+        })
+    );
+}
+
+#[test]
+fn test_shorten_middle_keeps_head_and_tail() {
+    assert_eq!(shorten_middle("short", 10), "short");
+    assert_eq!(
+        shorten_middle("abcdefghij", 4),
+        "ab\n\n[... 6 bytes omitted ...]\n\nij"
+    );
+    // Never splits a multi-byte character
+    assert_eq!(
+        shorten_middle("ééééé", 5),
+        "é\n\n[... 6 bytes omitted ...]\n\né"
+    );
+}